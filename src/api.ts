@@ -1,8 +1,17 @@
-import type { FastifyInstance } from "fastify";
+import type { FastifyInstance, FastifyReply } from "fastify";
 import type { McpManager } from "./manager.js";
 import type { McpServerConfig } from "./types.js";
 import { readLogs, watchLogs, getStats } from "./logger.js";
-import { loadConfig, saveConfig, addServer, removeServer, updateServer } from "./config.js";
+import { loadUsageStats } from "./stats.js";
+import {
+  loadConfig,
+  updateConfig,
+  addServer,
+  removeServer,
+  updateServer,
+  validateServerName,
+  redactServer,
+} from "./config.js";
 
 interface LogsQuery {
   limit?: string;
@@ -26,6 +35,17 @@ interface UpdateServerBody {
   env?: Record<string, string>;
 }
 
+// Server-sent events are written to reply.raw, which skips headers set on the reply
+// (including the CORS ones), so copy those over before switching to the raw stream
+export function startEventStream(reply: FastifyReply): void {
+  for (const [name, value] of Object.entries(reply.getHeaders())) {
+    if (value !== undefined) reply.raw.setHeader(name, value);
+  }
+  reply.raw.setHeader("Content-Type", "text/event-stream");
+  reply.raw.setHeader("Cache-Control", "no-cache");
+  reply.raw.setHeader("Connection", "keep-alive");
+}
+
 export function registerApiRoutes(app: FastifyInstance, manager: McpManager): void {
   // ============ LOGS API ============
 
@@ -65,10 +85,7 @@ export function registerApiRoutes(app: FastifyInstance, manager: McpManager): vo
   });
 
   app.get("/api/logs/stream", async (request, reply) => {
-    reply.raw.setHeader("Content-Type", "text/event-stream");
-    reply.raw.setHeader("Cache-Control", "no-cache");
-    reply.raw.setHeader("Connection", "keep-alive");
-    reply.raw.setHeader("Access-Control-Allow-Origin", "*");
+    startEventStream(reply);
 
     // Send initial connection message
     reply.raw.write(`data: ${JSON.stringify({ type: "connected" })}\n\n`);
@@ -88,10 +105,15 @@ export function registerApiRoutes(app: FastifyInstance, manager: McpManager): vo
   // ============ STATS API ============
 
   app.get("/api/stats", async () => {
-    return getStats();
+    return {
+      ...getStats(),
+      lifetime: manager.stats?.get() ?? loadUsageStats(),
+      connections: manager.getConnectionStats(),
+    };
   });
 
   // ============ SERVERS API ============
+  // Servers go out through redactServer, like list does, so secret env values never leave the config
 
   app.get("/api/servers", async () => {
     const config = loadConfig();
@@ -99,7 +121,7 @@ export function registerApiRoutes(app: FastifyInstance, manager: McpManager): vo
 
     return {
       servers: config.servers.map((s) => ({
-        ...s,
+        ...redactServer(s),
         connected: connected.includes(s.name),
       })),
     };
@@ -113,13 +135,17 @@ export function registerApiRoutes(app: FastifyInstance, manager: McpManager): vo
       return { error: "name and command are required" };
     }
 
-    const config = loadConfig();
+    try {
+      validateServerName(name);
+    } catch (e) {
+      reply.status(400);
+      return { error: e instanceof Error ? e.message : String(e) };
+    }
 
     try {
       const server: McpServerConfig = { name, command, args, env, enabled };
-      addServer(config, server);
-      saveConfig(config);
-      return { success: true, server };
+      updateConfig((config) => addServer(config, server));
+      return { success: true, server: redactServer(server) };
     } catch (e) {
       reply.status(409);
       return { error: e instanceof Error ? e.message : String(e) };
@@ -128,12 +154,9 @@ export function registerApiRoutes(app: FastifyInstance, manager: McpManager): vo
 
   app.delete<{ Params: { name: string } }>("/api/servers/:name", async (request, reply) => {
     const { name } = request.params;
-    const config = loadConfig();
-
     try {
-      const removed = removeServer(config, name);
-      saveConfig(config);
-      return { success: true, server: removed };
+      const removed = updateConfig((config) => removeServer(config, name));
+      return { success: true, server: redactServer(removed) };
     } catch (e) {
       reply.status(404);
       return { error: e instanceof Error ? e.message : String(e) };
@@ -143,34 +166,48 @@ export function registerApiRoutes(app: FastifyInstance, manager: McpManager): vo
   app.patch<{ Params: { name: string }; Body: UpdateServerBody }>("/api/servers/:name", async (request, reply) => {
     const { name } = request.params;
     const updates = request.body;
-    const config = loadConfig();
 
     try {
-      const updated = updateServer(config, name, updates);
-      saveConfig(config);
-      return { success: true, server: updated };
+      const updated = updateConfig((config) => updateServer(config, name, updates));
+      return { success: true, server: redactServer(updated) };
     } catch (e) {
       reply.status(404);
       return { error: e instanceof Error ? e.message : String(e) };
     }
   });
 
-  // ============ RELOAD API ============
+  app.post<{ Params: { name: string } }>("/api/servers/:name/reconnect", async (request, reply) => {
+    const { name } = request.params;
+    const server = loadConfig().servers.find((s) => s.name === name);
 
-  app.post("/api/reload", async () => {
-    // Shutdown all current connections
-    await manager.shutdownAll();
+    if (!server) {
+      reply.status(404);
+      return { error: `Server '${name}' not found` };
+    }
 
-    // Reload config and reconnect
-    const config = loadConfig();
-    await manager.connectAll(config);
+    try {
+      const tools = await manager.reconnect(name, server);
+      return { success: true, tools };
+    } catch (e) {
+      reply.status(502);
+      return { error: e instanceof Error ? e.message : String(e) };
+    }
+  });
 
+  // ============ RELOAD API ============
+
+  app.post("/api/reload", async () => {
+    // Only servers whose config changed are restarted, so calls running on the rest carry on
+    const { added, removed, changed } = await manager.reload(loadConfig());
     const connected = manager.getConnectedMcps();
 
     return {
       success: true,
       connected,
-      message: `Reconnected to ${connected.length} server(s)`,
+      added,
+      removed,
+      changed,
+      message: `Reloaded: ${added.length} added, ${removed.length} removed, ${changed.length} changed`,
     };
   });
 }
@@ -0,0 +1,55 @@
+import { describe, it, expect, beforeEach, afterEach } from "vitest";
+import * as fs from "node:fs";
+import * as os from "node:os";
+import * as path from "node:path";
+import { buildCallArguments } from "./args.js";
+
+describe("buildCallArguments", () => {
+  let dir: string;
+
+  beforeEach(() => {
+    dir = fs.mkdtempSync(path.join(os.tmpdir(), "mcp-central-args-"));
+  });
+
+  afterEach(() => {
+    fs.rmSync(dir, { recursive: true, force: true });
+  });
+
+  it("should parse inline JSON", () => {
+    expect(buildCallArguments('{"path":"/tmp/a"}')).toEqual({ path: "/tmp/a" });
+    expect(buildCallArguments(undefined)).toEqual({});
+  });
+
+  it("should read arguments from a file or stdin", () => {
+    const file = path.join(dir, "args.json");
+    fs.writeFileSync(file, '{"query":"select 1"}');
+
+    expect(buildCallArguments(`@${file}`)).toEqual({ query: "select 1" });
+    expect(buildCallArguments("@-", [], () => Buffer.from('{"n":1}'))).toEqual({ n: 1 });
+  });
+
+  it("should apply --arg overrides on top of the JSON arguments", () => {
+    const args = buildCallArguments('{"path":"/tmp/a","limit":10}', ["limit=5", "mode=fast", "path=/tmp/b"]);
+
+    expect(args).toEqual({ path: "/tmp/b", limit: 5, mode: "fast" });
+  });
+
+  it("should read --arg values from files, base64-encoding binary ones", () => {
+    const text = path.join(dir, "doc.md");
+    const image = path.join(dir, "image.png");
+    fs.writeFileSync(text, "# Title\n");
+    fs.writeFileSync(image, Buffer.from([0x89, 0x50, 0x4e, 0x47, 0x00, 0xff]));
+
+    const args = buildCallArguments(undefined, [`content=@${text}`, `data=@${image}`]);
+
+    expect(args).toEqual({ content: "# Title\n", data: "iVBORwD/" });
+  });
+
+  it("should reject invalid input", () => {
+    expect(() => buildCallArguments("[1, 2]")).toThrow("must be a JSON object");
+    expect(() => buildCallArguments("{oops")).toThrow("Invalid JSON arguments");
+    expect(() => buildCallArguments(undefined, ["novalue"])).toThrow("expected key=value");
+    expect(() => buildCallArguments(undefined, [`x=@${path.join(dir, "missing")}`])).toThrow("Failed to read");
+    expect(() => buildCallArguments("@-", ["x=@-"], () => Buffer.from("{}"))).toThrow("Only one argument");
+  });
+});
@@ -0,0 +1,85 @@
+import * as fs from "node:fs";
+
+// Where `@-` reads from; tests pass their own
+export type StdinReader = () => Buffer;
+
+const readStdin: StdinReader = () => fs.readFileSync(0);
+
+// Contents of `@path`, or of stdin for `@-`
+function readReference(reference: string, stdin: StdinReader): Buffer {
+  const file = reference.slice(1);
+  if (file === "-") return stdin();
+
+  try {
+    return fs.readFileSync(file);
+  } catch (e) {
+    throw new Error(`Failed to read ${file}: ${e instanceof Error ? e.message : e}`);
+  }
+}
+
+// NUL bytes or invalid UTF-8, e.g. an image
+function isBinary(data: Buffer): boolean {
+  return data.includes(0) || !Buffer.from(data.toString("utf-8"), "utf-8").equals(data);
+}
+
+function parseJsonObject(text: string, source: string): Record<string, unknown> {
+  let parsed: unknown;
+  try {
+    parsed = JSON.parse(text);
+  } catch (e) {
+    throw new Error(`Invalid JSON arguments in ${source}: ${e instanceof Error ? e.message : e}`);
+  }
+  if (typeof parsed !== "object" || parsed === null || Array.isArray(parsed)) {
+    throw new Error(`Arguments in ${source} must be a JSON object`);
+  }
+  return parsed as Record<string, unknown>;
+}
+
+// Arguments for a tool call from the CLI. The base object is inline JSON, `@file` or `@-` (stdin).
+// Each `key=value` override then sets one top-level key, replacing the base's: the value is
+// parsed as JSON when it can be and kept as a string otherwise, and `key=@file` uses the file's
+// contents as a string (base64 when the file is binary).
+export function buildCallArguments(
+  json: string | undefined,
+  overrides: string[] = [],
+  stdin: StdinReader = readStdin
+): Record<string, unknown> {
+  let stdinUsed = false;
+  const read = (reference: string) => {
+    if (reference === "@-") {
+      if (stdinUsed) throw new Error("Only one argument can be read from stdin (@-)");
+      stdinUsed = true;
+    }
+    return readReference(reference, stdin);
+  };
+
+  let args: Record<string, unknown> = {};
+  if (json) {
+    args = json.startsWith("@")
+      ? parseJsonObject(read(json).toString("utf-8"), json === "@-" ? "stdin" : json.slice(1))
+      : parseJsonObject(json, "the command line");
+  }
+
+  for (const override of overrides) {
+    const eq = override.indexOf("=");
+    if (eq <= 0) {
+      throw new Error(`Invalid --arg '${override}': expected key=value`);
+    }
+
+    const key = override.slice(0, eq);
+    const value = override.slice(eq + 1);
+    if (value.startsWith("@")) {
+      const data = read(value);
+      args[key] = isBinary(data) ? data.toString("base64") : data.toString("utf-8");
+      continue;
+    }
+
+    try {
+      args[key] = JSON.parse(value);
+    } catch {
+      args[key] = value;
+    }
+  }
+
+  return args;
+}
@@ -1,16 +1,100 @@
 #!/usr/bin/env node
 
+import * as fs from "node:fs";
 import { program } from "commander";
-import { loadConfig, saveConfig, addServer, removeServer } from "./config.js";
-import { runStdioServer } from "./server.js";
-import { runDaemon } from "./daemon.js";
+import {
+  loadConfig,
+  updateConfig,
+  addServer,
+  removeServer,
+  renameServer,
+  setServerEnv,
+  unsetServerEnv,
+  updateServer,
+  setServersEnabled,
+  hasTag,
+  redactServer,
+  getEnabledServers,
+  unknownFilterNames,
+  validateConfig,
+  useStdinConfig,
+  type ServerFilter,
+} from "./config.js";
+import { McpManager } from "./manager.js";
+import { StdioClient, findCommand } from "./client.js";
+import { loadUsageStats } from "./stats.js";
+import { runStdioServer, runOneShot } from "./server.js";
+import { runSocketServer } from "./socket.js";
+import { runRepl } from "./repl.js";
+import { fetchDaemonHealth, formatStatus } from "./status.js";
+import { buildCallArguments } from "./args.js";
+import { readJsonValueSync } from "./util.js";
+import { COMPLETION_SHELLS, generateCompletion, type CompletionShell } from "./completion.js";
 import { readLogs, watchLogs, formatLogEntry, formatLogHeader, formatWatchingHeader } from "./logger.js";
-import type { McpServerConfig } from "./types.js";
+import type { Config, McpServerConfig } from "./types.js";
+import { parseNamespacedTool } from "./types.js";
+
+function collect(value: string, previous: string[]): string[] {
+  return [...previous, value];
+}
+
+// Override the default request timeout for this run without saving it
+function applyTimeout(config: Config, timeout: string | undefined): void {
+  if (timeout === undefined) return;
+
+  const secs = Number(timeout);
+  if (!Number.isFinite(secs) || secs <= 0) {
+    console.error(`Invalid --timeout '${timeout}': expected a positive number of seconds`);
+    process.exit(1);
+  }
+  config.settings.requestTimeoutSecs = secs;
+}
+
+// --tag/--only/--exclude for this run, without touching the saved config
+function serverFilter(config: Config, options: { tag?: string; only?: string; exclude?: string }): ServerFilter {
+  if (options.only && options.exclude) {
+    console.error("--only and --exclude can't be used together");
+    process.exit(1);
+  }
+
+  const names = (list: string | undefined) => list?.split(",").map((n) => n.trim()).filter(Boolean);
+  const filter: ServerFilter = { tag: options.tag, only: names(options.only), exclude: names(options.exclude) };
+  for (const name of unknownFilterNames(config, filter)) {
+    console.error(`Warning: no server named '${name}' in config`);
+  }
+  return filter;
+}
+
+// The daemon pulls in fastify, so it's only loaded when the daemon command runs. stdio and
+// socket modes work on installs that skipped it (e.g. npm install --omit=optional).
+async function loadDaemon(): Promise<typeof import("./daemon.js")> {
+  try {
+    return await import("./daemon.js");
+  } catch (e) {
+    if ((e as NodeJS.ErrnoException).code === "ERR_MODULE_NOT_FOUND") {
+      console.error("The daemon needs fastify and @fastify/cors: npm install fastify @fastify/cors");
+      process.exit(1);
+    }
+    throw e;
+  }
+}
 
 program
   .name("mcp-central")
   .description("MCP aggregator - connect multiple MCP servers through a single endpoint")
-  .version("0.1.0");
+  .version("0.1.0")
+  .option("--config-stdin", "Read the whole config (JSON) from stdin instead of config.json; nothing is saved");
+
+program.hook("preAction", (_program, command) => {
+  if (!program.opts().configStdin) return;
+  try {
+    // serve takes its JSON-RPC requests from the same stdin, right after the config
+    useStdinConfig(command.name() === "serve" ? readJsonValueSync(0) : fs.readFileSync(0, "utf-8"));
+  } catch (e) {
+    console.error(e instanceof Error ? e.message : e);
+    process.exit(1);
+  }
+});
 
 program
   .command("add")
@@ -18,8 +102,9 @@ program
   .argument("<name>", "Unique name for this MCP server")
   .argument("<command>", "Command to execute")
   .argument("[args...]", "Arguments for the command")
-  .action((name: string, command: string, args: string[]) => {
-    const config = loadConfig();
+  .option("-t, --tag <tag>", "Tag this server (repeatable)", collect, [])
+  .option("-g, --group <group>", "Group with redundant servers (shared tool namespace with failover)")
+  .action((name: string, command: string, args: string[], options: { tag: string[]; group?: string }) => {
     const server: McpServerConfig = {
       name,
       command,
@@ -27,15 +112,24 @@ program
       env: {},
       enabled: true,
     };
+    if (options.tag.length > 0) {
+      server.tags = options.tag;
+    }
+    if (options.group) {
+      server.group = options.group;
+    }
 
     try {
-      addServer(config, server);
-      saveConfig(config);
+      updateConfig((config) => addServer(config, server));
       console.log(`Added MCP server '${name}'`);
     } catch (e) {
       console.error(e instanceof Error ? e.message : e);
       process.exit(1);
     }
+    // It may well be installed later, so this doesn't stop the add
+    if (!findCommand(command)) {
+      console.error(`Warning: command not found: ${command}`);
+    }
   });
 
 program
@@ -43,11 +137,8 @@ program
   .description("Remove an MCP server")
   .argument("<name>", "Name of the MCP server to remove")
   .action((name: string) => {
-    const config = loadConfig();
-
     try {
-      removeServer(config, name);
-      saveConfig(config);
+      updateConfig((config) => removeServer(config, name));
       console.log(`Removed MCP server '${name}'`);
     } catch (e) {
       console.error(e instanceof Error ? e.message : e);
@@ -55,23 +146,156 @@ program
     }
   });
 
+program
+  .command("rename")
+  .description("Rename an MCP server (its tools will be namespaced under the new name)")
+  .argument("<old>", "Current name of the MCP server")
+  .argument("<new>", "New name for the MCP server")
+  .action((oldName: string, newName: string) => {
+    try {
+      updateConfig((config) => renameServer(config, oldName, newName));
+      console.log(`Renamed MCP server '${oldName}' to '${newName}'`);
+    } catch (e) {
+      console.error(e instanceof Error ? e.message : e);
+      process.exit(1);
+    }
+  });
+
+program
+  .command("edit")
+  .description("Update the command and/or args of an MCP server")
+  .argument("<name>", "Name of the MCP server")
+  .argument("[args...]", "New arguments for the command (use -- before args starting with -)")
+  .option("-c, --command <command>", "New command to execute")
+  .option("--clear-args", "Remove all args (no args after -- leaves them as they are)")
+  .action((name: string, args: string[], options: { command?: string; clearArgs?: boolean }) => {
+    const updates: Partial<Omit<McpServerConfig, "name">> = {};
+
+    if (options.clearArgs && args.length > 0) {
+      console.error("--clear-args can't be combined with new args");
+      process.exit(1);
+    }
+
+    if (options.command) updates.command = options.command;
+    if (args.length > 0) updates.args = args;
+    if (options.clearArgs) updates.args = [];
+
+    if (Object.keys(updates).length === 0) {
+      console.error("Nothing to update. Pass --command, new args or --clear-args.");
+      process.exit(1);
+    }
+
+    try {
+      const server = updateConfig((config) => updateServer(config, name, updates));
+      console.log(`Updated MCP server '${name}': ${server.command} ${server.args.join(" ")}`);
+    } catch (e) {
+      console.error(e instanceof Error ? e.message : e);
+      process.exit(1);
+    }
+  });
+
+for (const [command, enabled] of [
+  ["enable", true],
+  ["disable", false],
+] as const) {
+  const verb = enabled ? "Enabled" : "Disabled";
+  program
+    .command(command)
+    .description(`${enabled ? "Enable" : "Disable"} an MCP server, or every server with a tag`)
+    .argument("[name]", "Name of the MCP server")
+    .option("-t, --tag <tag>", `${enabled ? "Enable" : "Disable"} all servers with this tag`)
+    .action((name: string | undefined, options: { tag?: string }) => {
+      if (!name === !options.tag) {
+        console.error("Pass either a server name or --tag");
+        process.exit(1);
+      }
+
+      try {
+        const servers = updateConfig((config) => setServersEnabled(config, { name, tag: options.tag }, enabled));
+        console.log(
+          options.tag
+            ? `${verb} ${servers.length} servers tagged '${options.tag}': ${servers.map((s) => s.name).join(", ")}`
+            : `${verb} MCP server '${name}'`
+        );
+      } catch (e) {
+        console.error(e instanceof Error ? e.message : e);
+        process.exit(1);
+      }
+    });
+}
+
+program
+  .command("set-env")
+  .description("Set an environment variable on an MCP server")
+  .argument("<name>", "Name of the MCP server")
+  .argument("<key>", "Environment variable name")
+  .argument("<value>", "Environment variable value")
+  .action((name: string, key: string, value: string) => {
+    try {
+      updateConfig((config) => setServerEnv(config, name, key, value));
+      console.log(`Set ${key} on MCP server '${name}'`);
+    } catch (e) {
+      console.error(e instanceof Error ? e.message : e);
+      process.exit(1);
+    }
+  });
+
+program
+  .command("unset-env")
+  .description("Remove an environment variable from an MCP server")
+  .argument("<name>", "Name of the MCP server")
+  .argument("<key>", "Environment variable name")
+  .option("--ignore-missing", "Succeed even if the variable is not set")
+  .action((name: string, key: string, options: { ignoreMissing?: boolean }) => {
+    try {
+      updateConfig((config) => unsetServerEnv(config, name, key, { ignoreMissing: options.ignoreMissing }));
+      console.log(`Unset ${key} on MCP server '${name}'`);
+    } catch (e) {
+      console.error(e instanceof Error ? e.message : e);
+      process.exit(1);
+    }
+  });
+
 program
   .command("list")
   .description("List all configured MCP servers")
-  .action(() => {
+  .option("--format <format>", "Output format (text or json)", "text")
+  .option("-t, --tag <tag>", "Only list servers with this tag")
+  .action((options: { format: string; tag?: string }) => {
     const config = loadConfig();
+    const matching = options.tag
+      ? config.servers.filter((s) => hasTag(s, options.tag!))
+      : config.servers;
+    // Secret env values are masked in both formats
+    const servers = matching.map(redactServer);
 
-    if (config.servers.length === 0) {
+    if (options.format === "json") {
+      console.log(JSON.stringify(servers, null, 2));
+      return;
+    }
+    if (options.format !== "text") {
+      console.error(`Unknown format '${options.format}'. Use 'text' or 'json'.`);
+      process.exit(1);
+    }
+
+    if (servers.length === 0) {
+      if (options.tag) {
+        console.log(`No MCP servers tagged '${options.tag}'.`);
+        return;
+      }
       console.log("No MCP servers configured.");
       console.log("\nAdd one with: mcp-central add <name> <command> [args...]");
       return;
     }
 
     console.log("Configured MCP servers:\n");
-    for (const server of config.servers) {
+    for (const server of servers) {
       const status = server.enabled ? "enabled" : "disabled";
       console.log(`  ${server.name} [${status}]`);
       console.log(`    command: ${server.command} ${server.args.join(" ")}`);
+      if (server.tags && server.tags.length > 0) {
+        console.log(`    tags: ${server.tags.join(", ")}`);
+      }
       if (Object.keys(server.env).length > 0) {
         console.log(`    env: ${JSON.stringify(server.env)}`);
       }
@@ -79,21 +303,302 @@ program
     }
   });
 
+program
+  .command("validate")
+  .description("Check the config for problems without connecting to any server")
+  .action(() => {
+    const config = loadConfig();
+    const issues = validateConfig(config);
+
+    if (issues.length === 0) {
+      console.log(`Config OK (${config.servers.length} servers)`);
+      return;
+    }
+
+    for (const issue of issues) {
+      const level = issue.fatal ? "error" : "warning";
+      console.log(`  ${issue.server}: ${level} - ${issue.message}`);
+    }
+
+    process.exit(issues.some((i) => i.fatal) ? 1 : 0);
+  });
+
+program
+  .command("ping")
+  .description("Ping MCP servers and report round-trip latency")
+  .argument("[name]", "Name of the MCP server to ping (default: all enabled servers)")
+  .action(async (name: string | undefined) => {
+    const config = loadConfig();
+    const servers = name ? config.servers.filter((s) => s.name === name) : getEnabledServers(config);
+
+    if (name && servers.length === 0) {
+      console.error(`Server '${name}' not found`);
+      process.exit(1);
+    }
+
+    const manager = new McpManager(config.settings);
+    let failed = false;
+
+    for (const server of servers) {
+      try {
+        await manager.connect(server);
+        const ms = await manager.ping(server.name);
+        console.log(`  ${server.name}: ok (${ms}ms)`);
+      } catch (e) {
+        failed = true;
+        console.log(`  ${server.name}: failed - ${e instanceof Error ? e.message : e}`);
+      }
+    }
+
+    await manager.shutdownAll();
+    process.exit(failed ? 1 : 0);
+  });
+
+program
+  .command("tools")
+  .description("List each server's tools under their own (not namespaced) names")
+  .option("-s, --server <name>", "Only list this server's tools")
+  .option("-t, --tag <tag>", "Only list servers with this tag")
+  .option("--only <names>", "Only list these servers (comma-separated)")
+  .option("--exclude <names>", "Don't list these servers (comma-separated)")
+  .option("--format <format>", "Output format (text or json)", "text")
+  .action(async (options: { server?: string; tag?: string; only?: string; exclude?: string; format: string }) => {
+    if (options.format !== "text" && options.format !== "json") {
+      console.error(`Unknown format '${options.format}'. Use 'text' or 'json'.`);
+      process.exit(1);
+    }
+    if (options.server && options.only) {
+      console.error("--server and --only can't be used together");
+      process.exit(1);
+    }
+
+    const config = loadConfig();
+    if (options.server && !config.servers.some((s) => s.name === options.server)) {
+      console.error(`Server '${options.server}' not found`);
+      process.exit(1);
+    }
+    const filter = serverFilter(config, { ...options, only: options.server ?? options.only });
+
+    const manager = new McpManager(config.settings);
+    await manager.connectAll(config, filter);
+    const byServer = manager.toolsByServer();
+    await manager.shutdownAll();
+
+    if (options.format === "json") {
+      console.log(JSON.stringify(byServer, null, 2));
+      return;
+    }
+    for (const [name, tools] of Object.entries(byServer)) {
+      console.log(`${name} (${tools.length} tools)`);
+      for (const tool of tools) {
+        console.log(tool.description ? `  ${tool.name} - ${tool.description}` : `  ${tool.name}`);
+      }
+      console.log();
+    }
+  });
+
+program
+  .command("inspect")
+  .description("Show exactly what one server returns from initialize and tools/list")
+  .argument("<name>", "Name of the MCP server to inspect")
+  .option("--timeout <secs>", "Request timeout in seconds", "10")
+  .action(async (name: string, options: { timeout: string }) => {
+    const server = loadConfig().servers.find((s) => s.name === name);
+    if (!server) {
+      console.error(`Server '${name}' not found`);
+      process.exit(1);
+    }
+
+    const timeoutSecs = Number(options.timeout);
+    if (!Number.isFinite(timeoutSecs) || timeoutSecs <= 0) {
+      console.error(`Invalid --timeout '${options.timeout}': expected a positive number of seconds`);
+      process.exit(1);
+    }
+
+    // Debugging a hung server shouldn't take the server's own (possibly long) timeout
+    const client = new StdioClient({ ...server, timeoutSecs });
+    let failed = false;
+
+    try {
+      await client.start();
+      const initResult = await client.initialize();
+      console.log("initialize:");
+      console.log(JSON.stringify(initResult, null, 2));
+
+      const tools = await client.listTools();
+      console.log("\ntools/list:");
+      console.log(JSON.stringify({ tools }, null, 2));
+    } catch (e) {
+      failed = true;
+      console.error(`Failed to inspect '${name}': ${e instanceof Error ? e.message : e}`);
+    }
+
+    await client.shutdown();
+    process.exit(failed ? 1 : 0);
+  });
+
 program
   .command("serve")
   .description("Start the bridge in stdio mode (for MCP clients)")
-  .action(async () => {
+  .option("-t, --tag <tag>", "Only connect servers with this tag")
+  .option("--only <names>", "Only connect these servers (comma-separated)")
+  .option("--exclude <names>", "Don't connect these servers (comma-separated)")
+  .option("--timeout <secs>", "Backend request timeout in seconds (per-server timeoutSecs still wins)")
+  .option("--keep-alive", "Reopen stdin when it closes instead of shutting down")
+  .option("--http-port <port>", "Also serve the daemon's HTTP endpoints on this port, sharing the same servers")
+  .action(
+    async (options: {
+      tag?: string;
+      only?: string;
+      exclude?: string;
+      timeout?: string;
+      keepAlive?: boolean;
+      httpPort?: string;
+    }) => {
+      const config = loadConfig();
+      applyTimeout(config, options.timeout);
+      // Report a missing fastify before any server is spawned
+      if (options.httpPort) await loadDaemon();
+      await runStdioServer(config, serverFilter(config, options), {
+        keepAlive: options.keepAlive,
+        httpPort: options.httpPort ? parseInt(options.httpPort, 10) : undefined,
+      });
+    }
+  );
+
+program
+  .command("request")
+  .description("Send one JSON-RPC request through the bridge and print the response")
+  .argument("<json>", `Request, e.g. '{"jsonrpc":"2.0","id":1,"method":"tools/list"}'`)
+  .option("-t, --tag <tag>", "Only connect servers with this tag")
+  .option("--timeout <secs>", "Backend request timeout in seconds (per-server timeoutSecs still wins)")
+  .action(async (json: string, options: { tag?: string; timeout?: string }) => {
     const config = loadConfig();
-    await runStdioServer(config);
+    applyTimeout(config, options.timeout);
+    const ok = await runOneShot(config, json, { tag: options.tag });
+    process.exit(ok ? 0 : 1);
+  });
+
+program
+  .command("call")
+  .description("Call one tool through the bridge and print the response")
+  .argument("<tool>", "Namespaced tool name, e.g. fs__read_file")
+  .argument("[json]", "Arguments as a JSON object, @file to read them from a file, or @- for stdin")
+  .option("--arg <key=value>", "Set one argument (JSON or string); key=@file reads it from a file", collect, [])
+  .option("--timeout <secs>", "Backend request timeout in seconds (per-server timeoutSecs still wins)")
+  .action(async (tool: string, json: string | undefined, options: { arg: string[]; timeout?: string }) => {
+    let args: Record<string, unknown>;
+    try {
+      args = buildCallArguments(json, options.arg);
+    } catch (e) {
+      console.error(e instanceof Error ? e.message : e);
+      process.exit(1);
+    }
+
+    const config = loadConfig();
+    applyTimeout(config, options.timeout);
+
+    // Only start the servers behind the tool's namespace (a server or a group)
+    const namespace = parseNamespacedTool(tool)?.mcp;
+    if (namespace === undefined) {
+      console.error(`Invalid tool name format: ${tool} (expected <server>__<tool>)`);
+      process.exit(1);
+    }
+    const only = config.servers.filter((s) => (s.group ?? s.name) === namespace).map((s) => s.name);
+    if (only.length === 0) {
+      console.error(`No server or group named '${namespace}' in the config`);
+      process.exit(1);
+    }
+
+    const request = { jsonrpc: "2.0", id: 1, method: "tools/call", params: { name: tool, arguments: args } };
+    const ok = await runOneShot(config, JSON.stringify(request), { only });
+    process.exit(ok ? 0 : 1);
   });
 
 program
   .command("daemon")
   .description("Start the bridge as an HTTP daemon")
   .option("-p, --port <port>", "Port to listen on", "3000")
-  .action(async (options: { port: string }) => {
+  .option("-t, --tag <tag>", "Only connect servers with this tag")
+  .option("--only <names>", "Only connect these servers (comma-separated)")
+  .option("--exclude <names>", "Don't connect these servers (comma-separated)")
+  .option("--timeout <secs>", "Backend request timeout in seconds (per-server timeoutSecs still wins)")
+  .option("--tls-cert <path>", "PEM certificate to serve HTTPS with (needs --tls-key)")
+  .option("--tls-key <path>", "PEM private key for --tls-cert")
+  .action(
+    async (options: {
+      port: string;
+      tag?: string;
+      only?: string;
+      exclude?: string;
+      timeout?: string;
+      tlsCert?: string;
+      tlsKey?: string;
+    }) => {
+      const config = loadConfig();
+      applyTimeout(config, options.timeout);
+      if (options.tlsCert) config.settings.tlsCert = options.tlsCert;
+      if (options.tlsKey) config.settings.tlsKey = options.tlsKey;
+      const { runDaemon } = await loadDaemon();
+      await runDaemon(config, parseInt(options.port, 10), serverFilter(config, options));
+    }
+  );
+
+program
+  .command("status")
+  .description("Show the state of each server in a running daemon")
+  .option("--url <url>", "Daemon URL (default: http://127.0.0.1:<daemonPort>)")
+  .action(async (options: { url?: string }) => {
+    const { settings } = loadConfig();
+    const url = options.url ?? `${settings.tlsCert ? "https" : "http"}://127.0.0.1:${settings.daemonPort}`;
+
+    try {
+      const health = await fetchDaemonHealth(url);
+      console.log(formatStatus(url, health));
+      process.exit(health.status === "ok" ? 0 : 1);
+    } catch (e) {
+      console.error(e instanceof Error ? e.message : e);
+      process.exit(1);
+    }
+  });
+
+program
+  .command("socket")
+  .description("Start the bridge on a Unix domain socket (for local clients)")
+  .argument("<path>", "Path of the socket file to create")
+  .option("-t, --tag <tag>", "Only connect servers with this tag")
+  .action(async (socketPath: string, options: { tag?: string }) => {
     const config = loadConfig();
-    await runDaemon(config, parseInt(options.port, 10));
+    await runSocketServer(config, socketPath, { tag: options.tag });
+  });
+
+program
+  .command("repl")
+  .description("Connect all servers and explore them interactively")
+  .option("-t, --tag <tag>", "Only connect servers with this tag")
+  .option("--timeout <secs>", "Backend request timeout in seconds (per-server timeoutSecs still wins)")
+  .action(async (options: { tag?: string; timeout?: string }) => {
+    const config = loadConfig();
+    applyTimeout(config, options.timeout);
+    await runRepl(config, { tag: options.tag });
+  });
+
+program
+  .command("stats")
+  .description("Show lifetime per-tool usage statistics")
+  .action(() => {
+    const tools = Object.entries(loadUsageStats().tools).sort(([, a], [, b]) => b.calls - a.calls);
+
+    if (tools.length === 0) {
+      console.log("No usage stats yet. Stats are recorded when tools are called via the bridge.");
+      return;
+    }
+
+    console.log("Tool usage:\n");
+    for (const [name, usage] of tools) {
+      const failures = usage.failures > 0 ? `, ${usage.failures} failed` : "";
+      console.log(`  ${name}: ${usage.calls} calls${failures} (last used ${usage.lastUsed})`);
+    }
   });
 
 program
@@ -145,4 +650,16 @@ program
     }
   });
 
+program
+  .command("completion")
+  .description("Print a shell completion script")
+  .argument("<shell>", `Shell to generate for (${COMPLETION_SHELLS.join(", ")})`)
+  .action((shell: string) => {
+    if (!COMPLETION_SHELLS.includes(shell as CompletionShell)) {
+      console.error(`Unknown shell '${shell}'. Use one of: ${COMPLETION_SHELLS.join(", ")}`);
+      process.exit(1);
+    }
+    process.stdout.write(generateCompletion(program, shell as CompletionShell));
+  });
+
 program.parse();
@@ -0,0 +1,451 @@
+import { describe, it, expect, afterEach, beforeEach, vi } from "vitest";
+import * as fs from "node:fs";
+import * as os from "node:os";
+import * as path from "node:path";
+import { fileURLToPath } from "node:url";
+import { StdioClient, findCommand, mergeExtraParams } from "./client.js";
+import { ErrorCodes, McpError, type McpServerConfig } from "./types.js";
+
+const MOCK_SERVER = fileURLToPath(new URL("../test/fixtures/mock-server.mjs", import.meta.url));
+
+function mockServer(name = "mock", env: Record<string, string> = {}): McpServerConfig {
+  return {
+    name,
+    command: process.execPath,
+    args: [MOCK_SERVER],
+    env,
+    enabled: true,
+  };
+}
+
+describe("StdioClient", () => {
+  let client: StdioClient | null = null;
+
+  afterEach(async () => {
+    await client?.shutdown();
+    client = null;
+  });
+
+  it("should not block requests behind a slow call", async () => {
+    client = new StdioClient(mockServer());
+    await client.start();
+    await client.initialize();
+
+    const completed: string[] = [];
+    const slow = client.callTool({ name: "echo", arguments: { label: "slow", delay: 300 } })
+      .then(() => completed.push("slow"));
+    const fast = client.callTool({ name: "echo", arguments: { label: "fast", delay: 10 } })
+      .then(() => completed.push("fast"));
+    const list = client.listTools().then(() => completed.push("list"));
+
+    await Promise.all([slow, fast, list]);
+
+    expect(completed[completed.length - 1]).toBe("slow");
+    expect(client.tools.map((t) => t.name)).toEqual(["echo"]);
+  });
+
+  it("should fail to start with a clear error when the command doesn't exist", async () => {
+    client = new StdioClient({ ...mockServer(), command: "mcp-central-no-such-command" });
+
+    await expect(client.start()).rejects.toThrow("command not found: mcp-central-no-such-command");
+    expect(client.isRunning()).toBe(false);
+  });
+
+  it("should find commands by path or on PATH", () => {
+    const binDir = path.dirname(process.execPath);
+    const nodeName = path.basename(process.execPath);
+
+    expect(findCommand(process.execPath)).toBe(process.execPath);
+    expect(findCommand(nodeName, binDir)).toBe(path.join(binDir, nodeName));
+    expect(findCommand("mcp-central-no-such-command", binDir)).toBeNull();
+    expect(findCommand("/nonexistent/mcp")).toBeNull();
+  });
+
+  it("should start a relative command from the server's cwd", async () => {
+    const dir = fs.mkdtempSync(path.join(os.tmpdir(), "mcp-central-cwd-"));
+    fs.writeFileSync(path.join(dir, "server.sh"), `#!/bin/sh\nexec "${process.execPath}" "${MOCK_SERVER}"\n`, {
+      mode: 0o755,
+    });
+
+    try {
+      expect(findCommand("./server.sh", undefined, dir)).toBe(path.join(dir, "server.sh"));
+      expect(findCommand("./server.sh")).toBeNull();
+
+      client = new StdioClient({ ...mockServer(), command: "./server.sh", args: [], cwd: dir });
+      await client.start();
+      await client.initialize();
+      expect((await client.listTools()).map((t) => t.name)).toEqual(["echo"]);
+    } finally {
+      fs.rmSync(dir, { recursive: true, force: true });
+    }
+  });
+
+  it("should leave command lookup to spawn on Windows", () => {
+    const platform = Object.getOwnPropertyDescriptor(process, "platform")!;
+    Object.defineProperty(process, "platform", { value: "win32" });
+    try {
+      expect(findCommand("npx", "C:\\Program Files\\nodejs")).toBe("npx");
+    } finally {
+      Object.defineProperty(process, "platform", platform);
+    }
+  });
+
+  it("should ping the backend and report latency", async () => {
+    client = new StdioClient(mockServer());
+    await client.start();
+    await client.initialize();
+
+    const ms = await client.ping();
+    expect(ms).toBeGreaterThanOrEqual(0);
+  });
+
+  it("should time out slow requests using the configured timeout", async () => {
+    client = new StdioClient(mockServer(), { timeoutSecs: 1 });
+    await client.start();
+    await client.initialize();
+
+    await expect(client.callTool({ name: "echo", arguments: { label: "slow", delay: 3000 } })).rejects.toThrow(
+      "Request timeout: tools/call"
+    );
+  });
+
+  it("should keep the backend's error code on a failed call", async () => {
+    client = new StdioClient(mockServer("mock", { MOCK_FAIL_CALLS: "1" }));
+    await client.start();
+    await client.initialize();
+
+    const call = client.callTool({ name: "echo", arguments: {} });
+
+    await expect(call).rejects.toBeInstanceOf(McpError);
+    await expect(call).rejects.toMatchObject({
+      code: ErrorCodes.INTERNAL_ERROR,
+      message: "tools/call failed: mock is failing",
+    });
+  });
+
+  it("should stop waiting for a cancelled call", async () => {
+    client = new StdioClient(mockServer());
+    await client.start();
+    await client.initialize();
+
+    const controller = new AbortController();
+    const call = client.callTool({ name: "echo", arguments: { label: "slow", delay: 5000 } }, controller.signal);
+    setTimeout(() => controller.abort(), 20);
+
+    await expect(call).rejects.toThrow("Request cancelled: tools/call");
+    expect(client.inFlight).toBe(0);
+  });
+
+  it("should prefer the server's own timeoutSecs", async () => {
+    client = new StdioClient({ ...mockServer(), timeoutSecs: 5 }, { timeoutSecs: 0.05 });
+    await client.start();
+    await client.initialize();
+
+    const result = await client.callTool({ name: "echo", arguments: { label: "ok", delay: 200 } });
+    expect(result.content).toEqual([{ type: "text", text: "ok" }]);
+  });
+
+  it("should merge the env file with explicit env taking precedence", async () => {
+    const dir = fs.mkdtempSync(path.join(os.tmpdir(), "mcp-central-env-"));
+    const envFile = path.join(dir, "server.env");
+    fs.writeFileSync(envFile, 'FROM_FILE="file value"\nOVERRIDDEN=file\n');
+
+    try {
+      client = new StdioClient({ ...mockServer("mock", { OVERRIDDEN: "config" }), envFile });
+      await client.start();
+      await client.initialize();
+
+      const result = await client.callTool({ name: "env", arguments: { keys: ["FROM_FILE", "OVERRIDDEN"] } });
+      expect(JSON.parse((result.content[0] as { text: string }).text)).toEqual({
+        FROM_FILE: "file value",
+        OVERRIDDEN: "config",
+      });
+    } finally {
+      fs.rmSync(dir, { recursive: true, force: true });
+    }
+  });
+
+  it("should append stderr to the server's log file", async () => {
+    const dir = fs.mkdtempSync(path.join(os.tmpdir(), "mcp-central-log-"));
+    const logFile = path.join(dir, "logs", "mock.log");
+    vi.spyOn(process.stderr, "write").mockImplementation(() => true);
+
+    try {
+      client = new StdioClient({ ...mockServer(), logFile });
+      await client.start();
+      await client.initialize();
+      await client.callTool({ name: "stderr", arguments: { text: "something went wrong" } });
+
+      await vi.waitFor(() => expect(fs.readFileSync(logFile, "utf-8")).toContain("something went wrong"));
+      expect(process.stderr.write).toHaveBeenCalledWith("[mock] something went wrong\n");
+    } finally {
+      vi.restoreAllMocks();
+      fs.rmSync(dir, { recursive: true, force: true });
+    }
+  });
+
+  it("should keep stdout log lines in the log file with tolerateStdoutLogs", async () => {
+    const dir = fs.mkdtempSync(path.join(os.tmpdir(), "mcp-central-log-"));
+    const logFile = path.join(dir, "mock.log");
+    const error = vi.spyOn(console, "error").mockImplementation(() => {});
+
+    try {
+      client = new StdioClient({
+        ...mockServer("mock", { MOCK_STDOUT_LOGS: "1" }),
+        logFile,
+        tolerateStdoutLogs: true,
+      });
+      await client.start();
+      await client.initialize();
+      const result = await client.callTool({ name: "echo", arguments: { label: "hi" } });
+
+      expect(result.content).toEqual([{ type: "text", text: "hi" }]);
+      await vi.waitFor(() => expect(fs.readFileSync(logFile, "utf-8")).toContain("INFO handling tools/call"));
+      expect(fs.readFileSync(logFile, "utf-8")).toContain("INFO handling initialize");
+      expect(error).not.toHaveBeenCalledWith("[mock] Ignoring non-JSON output:", expect.anything());
+    } finally {
+      vi.restoreAllMocks();
+      fs.rmSync(dir, { recursive: true, force: true });
+    }
+  });
+
+  it("should treat bracketed and unbalanced stdout lines as logs with tolerateStdoutLogs", async () => {
+    const dir = fs.mkdtempSync(path.join(os.tmpdir(), "mcp-central-log-"));
+    const logFile = path.join(dir, "mock.log");
+    const error = vi.spyOn(console, "error").mockImplementation(() => {});
+
+    try {
+      client = new StdioClient({
+        ...mockServer("mock", { MOCK_STDOUT_LOGS: "bracketed" }),
+        logFile,
+        tolerateStdoutLogs: true,
+      });
+      await client.start();
+      await client.initialize();
+      const result = await client.callTool({ name: "echo", arguments: { label: "hi" } });
+
+      expect(result.content).toEqual([{ type: "text", text: "hi" }]);
+      await vi.waitFor(() => expect(fs.readFileSync(logFile, "utf-8")).toContain("[WARN still handling tools/call"));
+      expect(fs.readFileSync(logFile, "utf-8")).toContain("[INFO] handling initialize");
+      expect(error).not.toHaveBeenCalledWith("[mock] Failed to parse response:", expect.anything());
+    } finally {
+      vi.restoreAllMocks();
+      fs.rmSync(dir, { recursive: true, force: true });
+    }
+  });
+
+  it("should warn about each non-JSON stdout line by default", async () => {
+    const error = vi.spyOn(console, "error").mockImplementation(() => {});
+
+    try {
+      client = new StdioClient(mockServer("mock", { MOCK_STDOUT_LOGS: "1" }));
+      await client.start();
+      await client.initialize();
+
+      await vi.waitFor(() =>
+        expect(error).toHaveBeenCalledWith("[mock] Ignoring non-JSON output:", "INFO handling initialize")
+      );
+    } finally {
+      vi.restoreAllMocks();
+    }
+  });
+
+  it("should handle messages written back to back on one line", async () => {
+    client = new StdioClient(mockServer("mock", { MOCK_FRAMING: "concat" }));
+    const notifications: string[] = [];
+    client.onNotification = (method) => notifications.push(method);
+    await client.start();
+    await client.initialize();
+
+    // The reply and the notification go out in the same write
+    await client.callTool({ name: "tools_changed", arguments: {} });
+    const result = await client.callTool({ name: "echo", arguments: { label: "hi" } });
+
+    expect(result.content).toEqual([{ type: "text", text: "hi" }]);
+    await vi.waitFor(() => expect(notifications).toEqual(["notifications/tools/list_changed"]));
+  });
+
+  it("should handle messages split across lines and reads", async () => {
+    client = new StdioClient(mockServer("mock", { MOCK_FRAMING: "split" }));
+    await client.start();
+    await client.initialize();
+
+    const tools = await client.listTools();
+    const result = await client.callTool({ name: "echo", arguments: { label: "line 1\nline 2 }" } });
+
+    expect(tools.map((t) => t.name)).toEqual(["echo"]);
+    expect(result.content).toEqual([{ type: "text", text: "line 1\nline 2 }" }]);
+  });
+
+  it("should send the server's protocolVersion in initialize", async () => {
+    client = new StdioClient({ ...mockServer(), protocolVersion: "2025-03-26" });
+    await client.start();
+    await client.initialize();
+
+    const result = await client.callTool({ name: "protocol", arguments: {} });
+
+    expect(result.content).toEqual([{ type: "text", text: "2025-03-26" }]);
+  });
+
+  it("should merge initParamsExtra into initialize without overriding standard fields", async () => {
+    client = new StdioClient({
+      ...mockServer("mock", { MOCK_TOOLS: "init_params" }),
+      initParamsExtra: {
+        vendor: { workspace: "acme" },
+        capabilities: { experimental: { indexing: {} } },
+        protocolVersion: "1999-01-01",
+      },
+    });
+    await client.start();
+    await client.initialize({ roots: { listChanged: true } });
+
+    const result = await client.callTool({ name: "init_params", arguments: {} });
+    const params = JSON.parse((result.content[0] as { text: string }).text);
+
+    expect(params).toEqual({
+      protocolVersion: "2024-11-05",
+      capabilities: { roots: { listChanged: true }, experimental: { indexing: {} } },
+      clientInfo: expect.any(Object),
+      vendor: { workspace: "acme" },
+    });
+  });
+
+  it("should report the paths of extra params that conflict with the base", () => {
+    const merged = mergeExtraParams(
+      { protocolVersion: "2024-11-05", capabilities: { roots: { listChanged: true } } },
+      { protocolVersion: "x", capabilities: { roots: { listChanged: false }, sampling: {} }, extra: 1 }
+    );
+
+    expect(merged.params).toEqual({
+      protocolVersion: "2024-11-05",
+      capabilities: { roots: { listChanged: true }, sampling: {} },
+      extra: 1,
+    });
+    expect(merged.conflicts).toEqual(["protocolVersion", "capabilities.roots.listChanged"]);
+  });
+
+  it("should report how the process ended when it dies", async () => {
+    client = new StdioClient(mockServer());
+    await client.start();
+    await client.initialize();
+
+    await expect(client.callTool({ name: "exit", arguments: { code: 3 } })).rejects.toThrow(
+      "MCP server 'mock' exited with code 3"
+    );
+    expect(client.exitReason).toBe("exited with code 3");
+    await expect(client.ping()).rejects.toThrow("MCP server 'mock' exited with code 3");
+  });
+
+  it("should report the signal that killed the process", async () => {
+    client = new StdioClient(mockServer());
+    await client.start();
+    await client.initialize();
+
+    await expect(client.callTool({ name: "exit", arguments: { signal: "SIGKILL" } })).rejects.toThrow(
+      "MCP server 'mock' was killed by SIGKILL"
+    );
+  });
+
+  it("should send methods without a typed wrapper and return the raw response", async () => {
+    client = new StdioClient(mockServer());
+    await client.start();
+    await client.initialize();
+
+    const echoed = await client.requestRaw("mock/echo", { answer: 42 });
+    const unknown = await client.requestRaw("vendor/unknown");
+
+    expect(echoed.result).toEqual({ params: { answer: 42 } });
+    expect(unknown.error).toEqual({ code: -32601, message: "Method not found: vendor/unknown" });
+  });
+
+  it("should keep the backend's request ids apart from ours when they overlap", async () => {
+    client = new StdioClient(mockServer("mock", { MOCK_TOOLS: "roots,echo", MOCK_MIRROR_IDS: "1" }));
+    client.onRequest = async (method) => {
+      expect(method).toBe("roots/list");
+      return { roots: [{ uri: "file:///tmp" }] };
+    };
+    await client.start();
+    await client.initialize();
+
+    // The backend asks for roots/list under the same id as our pending tools/call
+    const [roots, echo] = await Promise.all([
+      client.callTool({ name: "roots", arguments: {} }),
+      client.callTool({ name: "echo", arguments: { label: "ok", delay: 50 } }),
+    ]);
+
+    expect(roots.content).toEqual([{ type: "text", text: JSON.stringify({ roots: [{ uri: "file:///tmp" }] }) }]);
+    expect(echo.content).toEqual([{ type: "text", text: "ok" }]);
+  });
+
+  it("should SIGKILL a server that ignores SIGTERM on shutdown", async () => {
+    client = new StdioClient(mockServer("mock", { MOCK_IGNORE_TERM: "1" }));
+    await client.start();
+    await client.initialize();
+
+    expect(await client.shutdown(200)).toBe(true);
+    expect(client.exitReason).toBe("was killed by SIGKILL");
+  });
+
+  it("should not force-kill a server that exits on SIGTERM", async () => {
+    client = new StdioClient(mockServer());
+    await client.start();
+    await client.initialize();
+
+    expect(await client.shutdown(200)).toBe(false);
+    expect(client.exitReason).toBe("was killed by SIGTERM");
+  });
+
+  it("should kill a server that never answers initialize", async () => {
+    client = new StdioClient({ ...mockServer("mock", { MOCK_SILENT_INIT: "1" }), initTimeoutSecs: 0.2 });
+    await client.start();
+
+    await expect(client.initialize()).rejects.toThrow("MCP server 'mock' did not answer initialize within 0.2s");
+    expect(client.isRunning()).toBe(false);
+    await vi.waitFor(() => expect(client!.exitReason).toBe("was killed by SIGTERM"));
+  });
+
+  describe("environment inheritance", () => {
+    const readEnv = async (keys: string[]) => {
+      const result = await client!.callTool({ name: "env", arguments: { keys } });
+      return JSON.parse((result.content[0] as { text: string }).text);
+    };
+
+    beforeEach(() => {
+      process.env.MCP_CENTRAL_TEST_KEEP = "keep";
+      process.env.MCP_CENTRAL_TEST_DROP = "drop";
+    });
+
+    afterEach(() => {
+      delete process.env.MCP_CENTRAL_TEST_KEEP;
+      delete process.env.MCP_CENTRAL_TEST_DROP;
+    });
+
+    it("should inherit the whole parent environment by default", async () => {
+      client = new StdioClient(mockServer());
+      await client.start();
+      await client.initialize();
+
+      expect(await readEnv(["MCP_CENTRAL_TEST_KEEP", "MCP_CENTRAL_TEST_DROP"])).toEqual({
+        MCP_CENTRAL_TEST_KEEP: "keep",
+        MCP_CENTRAL_TEST_DROP: "drop",
+      });
+    });
+
+    it("should only pass allowlisted variables and explicit env with envClear", async () => {
+      client = new StdioClient({
+        ...mockServer("mock", { EXPLICIT: "yes" }),
+        envClear: true,
+        envInherit: ["MCP_CENTRAL_TEST_KEEP", "NOT_SET_ANYWHERE"],
+      });
+      await client.start();
+      await client.initialize();
+
+      expect(await readEnv(["MCP_CENTRAL_TEST_KEEP", "MCP_CENTRAL_TEST_DROP", "EXPLICIT", "NOT_SET_ANYWHERE"])).toEqual({
+        MCP_CENTRAL_TEST_KEEP: "keep",
+        MCP_CENTRAL_TEST_DROP: null,
+        EXPLICIT: "yes",
+        NOT_SET_ANYWHERE: null,
+      });
+    });
+  });
+});
@@ -1,15 +1,118 @@
 import { spawn, type ChildProcess } from "node:child_process";
+import * as fs from "node:fs";
+import * as path from "node:path";
 import * as readline from "node:readline";
+import { getConfigDir, loadEnvFile, resolveServerCwd } from "./config.js";
+import { JsonStreamParser } from "./jsonstream.js";
+import { appendServerLog } from "./logger.js";
+import { ErrorCodes, McpError, PROTOCOL_VERSION } from "./types.js";
 import type {
+  ClientCapabilities,
+  ClientInfo,
+  CompleteParams,
+  CompletionResult,
   McpServerConfig,
+  JsonRpcId,
+  PaginatedParams,
   JsonRpcRequest,
   JsonRpcResponse,
   InitializeResult,
+  ReadResourceResult,
+  RequestMeta,
+  Resource,
+  ResourcesListResult,
+  ResourceTemplate,
+  ResourceTemplatesListResult,
   Tool,
+  ToolsListResult,
   ToolCallParams,
   ToolCallResult,
 } from "./types.js";
 
+const DEFAULT_TIMEOUT_SECS = 30;
+const DEFAULT_LOG_FILE_MAX_BYTES = 10 * 1024 * 1024;
+const DEFAULT_SHUTDOWN_TIMEOUT_MS = 5000;
+const DEFAULT_CLIENT_INFO: ClientInfo = { name: "mcp-central", version: "0.1.0" };
+
+export interface StdioClientOptions {
+  // Used when the server config doesn't set its own timeoutSecs
+  timeoutSecs?: number;
+  // Identity sent in initialize
+  clientInfo?: ClientInfo;
+  // Used when the server config doesn't set its own initTimeoutSecs (default: the request timeout)
+  initTimeoutSecs?: number;
+  // Used when the server config doesn't set its own tolerateStdoutLogs
+  tolerateStdoutLogs?: boolean;
+  // Echo tolerated stdout log lines to stderr (settings.logLevel "debug")
+  debug?: boolean;
+}
+
+// Params for one page of a list request, or none when there's nothing to send
+function pageParams(cursor: string | undefined, meta: RequestMeta | undefined): PaginatedParams | undefined {
+  return cursor || meta ? { cursor, _meta: meta } : undefined;
+}
+
+// Where spawn would find a command: paths with a slash as given, bare names on PATH
+// (the system default when PATH is unset). Null when there's no such executable.
+// Relative paths, and relative PATH entries, are taken from cwd, the directory the child starts in.
+// Windows lookup (Path, PATHEXT, .cmd shims) is left to spawn, so there the command comes back as-is.
+export function findCommand(
+  command: string,
+  pathEnv: string | undefined = process.env.PATH,
+  cwd: string = process.cwd()
+): string | null {
+  if (process.platform === "win32") return command;
+
+  const isExecutable = (file: string) => {
+    try {
+      fs.accessSync(file, fs.constants.X_OK);
+      return fs.statSync(file).isFile();
+    } catch {
+      return false;
+    }
+  };
+
+  if (command.includes("/")) {
+    const resolved = path.resolve(cwd, command);
+    return isExecutable(resolved) ? resolved : null;
+  }
+  for (const dir of (pathEnv ?? "/usr/bin:/bin").split(path.delimiter)) {
+    const candidate = path.resolve(cwd, dir || ".", command);
+    if (isExecutable(candidate)) return candidate;
+  }
+  return null;
+}
+
+function commandNotFound(command: string): string {
+  return `command not found: ${command} (install it, or use an absolute path as the server's command)`;
+}
+
+function isPlainObject(value: unknown): value is Record<string, unknown> {
+  return typeof value === "object" && value !== null && !Array.isArray(value);
+}
+
+// Deep-merges extra into base without overriding it: objects present in both are merged, and
+// any other field base already sets keeps base's value and is listed in conflicts by its path
+export function mergeExtraParams(
+  base: Record<string, unknown>,
+  extra: Record<string, unknown>,
+  conflicts: string[] = [],
+  prefix = ""
+): { params: Record<string, unknown>; conflicts: string[] } {
+  const params = { ...base };
+  for (const [key, value] of Object.entries(extra)) {
+    const keyPath = `${prefix}${key}`;
+    if (!(key in params)) {
+      params[key] = value;
+    } else if (isPlainObject(params[key]) && isPlainObject(value)) {
+      params[key] = mergeExtraParams(params[key] as Record<string, unknown>, value, conflicts, `${keyPath}.`).params;
+    } else {
+      conflicts.push(keyPath);
+    }
+  }
+  return { params, conflicts };
+}
+
 type PendingRequest = {
   resolve: (response: JsonRpcResponse) => void;
   reject: (error: Error) => void;
@@ -19,54 +122,228 @@ export class StdioClient {
   readonly name: string;
   readonly config: McpServerConfig;
   private process: ChildProcess | null = null;
-  private pending = new Map<string | number, PendingRequest>();
+  // Ids of requests we send: integers counting up from 1, including requests relayed for the
+  // upstream client (e.g. catchAllServer), which get a fresh id rather than keeping theirs. A backend
+  // numbers its own requests to us independently and may reuse the same values, so messages are
+  // told apart by shape: one with a method is the backend's request (answered under its id and
+  // never looked up in pending), one without is a response to ours.
+  private pending = new Map<JsonRpcId, PendingRequest>();
   private nextId = 1;
   serverInfo: InitializeResult | null = null;
+  // How the process ended ("exited with code 1", "was killed by SIGKILL"), null while it runs
+  exitReason: string | null = null;
+  // When initialize completed (ms since epoch), null until then
+  connectedAt: number | null = null;
   tools: Tool[] = [];
+  // Tool call counters, used for load balancing
+  callCount = 0;
+  inFlight = 0;
+  // Called for notifications the backend sends us (no id)
+  onNotification: ((method: string, params: unknown) => void) | null = null;
+  // Called when the process exits without being asked to (not through shutdown)
+  onExit: ((reason: string) => void) | null = null;
+  // Answers requests the backend sends us (e.g. roots/list). Throw McpError to return a specific code.
+  onRequest: ((method: string, params: unknown) => Promise<unknown>) | null = null;
+
+  private timeoutMs: number;
+  private initTimeoutMs: number;
+  private clientInfo: ClientInfo;
+  private tolerateStdoutLogs: boolean;
+  private debug: boolean;
 
-  constructor(config: McpServerConfig) {
+  constructor(config: McpServerConfig, options: StdioClientOptions = {}) {
     this.name = config.name;
     this.config = config;
+    this.timeoutMs = (config.timeoutSecs ?? options.timeoutSecs ?? DEFAULT_TIMEOUT_SECS) * 1000;
+    const initTimeoutSecs = config.initTimeoutSecs ?? options.initTimeoutSecs;
+    this.initTimeoutMs = initTimeoutSecs !== undefined ? initTimeoutSecs * 1000 : this.timeoutMs;
+    this.clientInfo = options.clientInfo ?? DEFAULT_CLIENT_INFO;
+    this.tolerateStdoutLogs = config.tolerateStdoutLogs ?? options.tolerateStdoutLogs ?? false;
+    this.debug = options.debug ?? false;
   }
 
   async start(): Promise<void> {
+    const env = this.buildEnv();
+    const cwd = this.config.cwd ? resolveServerCwd(this.config.cwd) : undefined;
+    // Fail up front rather than with spawn's bare ENOENT
+    if (!findCommand(this.config.command, env.PATH, cwd)) {
+      throw new Error(commandNotFound(this.config.command));
+    }
+
     this.process = spawn(this.config.command, this.config.args, {
-      stdio: ["pipe", "pipe", "inherit"],
-      env: { ...process.env, ...this.config.env },
+      stdio: ["pipe", "pipe", this.config.logFile ? "pipe" : "inherit"],
+      env,
+      cwd,
     });
 
-    const rl = readline.createInterface({ input: this.process.stdout! });
+    if (this.config.logFile) {
+      this.captureStderr(path.resolve(getConfigDir(), this.config.logFile));
+    }
 
-    rl.on("line", (line) => {
-      if (!line.trim()) return;
-
-      try {
-        const response = JSON.parse(line) as JsonRpcResponse;
-        if (response.id !== undefined) {
-          const pending = this.pending.get(response.id);
-          if (pending) {
-            this.pending.delete(response.id);
-            pending.resolve(response);
-          }
-        }
-      } catch (e) {
-        console.error(`[${this.name}] Failed to parse response:`, line);
-      }
+    const parser = new JsonStreamParser(
+      (text) => this.handleMessage(text),
+      (line) => this.handleNonJsonOutput(line)
+    );
+    this.process.stdout!.setEncoding("utf-8");
+    this.process.stdout!.on("data", (chunk: string) => parser.push(chunk));
+
+    this.process.on("exit", (code, signal) => {
+      this.exitReason = signal ? `was killed by ${signal}` : `exited with code ${code}`;
+      console.error(`[${this.name}] Process ${this.exitReason}`);
+      // shutdown clears process before stopping it
+      if (this.process) this.onExit?.(this.exitReason);
+      this.rejectPending(new Error(`MCP server '${this.name}' ${this.exitReason}`));
     });
 
-    this.process.on("exit", (code) => {
-      console.error(`[${this.name}] Process exited with code ${code}`);
-      for (const pending of this.pending.values()) {
-        pending.reject(new Error("Process exited"));
+    // e.g. ENOENT when the command doesn't exist, on Windows where findCommand can't tell up front
+    this.process.on("error", (err) => {
+      console.error(`[${this.name}] Failed to start: ${err.message}`);
+      const message =
+        (err as NodeJS.ErrnoException).code === "ENOENT"
+          ? commandNotFound(this.config.command)
+          : `Failed to start ${this.config.command}: ${err.message}`;
+      this.rejectPending(new Error(message));
+    });
+
+    // Writes to a dead process fail with EPIPE; pending requests are rejected via exit/error above
+    this.process.stdin?.on("error", () => {});
+  }
+
+  private handleMessage(text: string): void {
+    let parsed: unknown;
+    try {
+      parsed = JSON.parse(text);
+    } catch {
+      // e.g. "[INFO] ready", which brackets make look like a value
+      if (this.tolerateStdoutLogs) this.handleNonJsonOutput(text);
+      else console.error(`[${this.name}] Failed to parse response:`, text);
+      return;
+    }
+
+    // Batches arrive as arrays
+    for (const message of Array.isArray(parsed) ? parsed : [parsed]) {
+      this.dispatch(message as JsonRpcResponse | JsonRpcRequest);
+    }
+  }
+
+  private dispatch(message: JsonRpcResponse | JsonRpcRequest): void {
+    if (!message || typeof message !== "object") return;
+
+    if ("method" in message) {
+      if (message.id === undefined) {
+        this.onNotification?.(message.method, message.params);
+      } else {
+        this.answerRequest(message);
+      }
+      return;
+    }
+
+    const response = message as JsonRpcResponse;
+    if (response.id !== undefined && response.id !== null) {
+      const pending = this.pending.get(response.id);
+      if (pending) {
+        this.pending.delete(response.id);
+        pending.resolve(response);
+      } else {
+        // Usually a late reply to a request that timed out or was cancelled
+        console.error(`[${this.name}] Ignoring response to unknown request id ${JSON.stringify(response.id)}`);
       }
-      this.pending.clear();
+    }
+  }
+
+  private rejectPending(error: Error): void {
+    for (const pending of this.pending.values()) {
+      pending.reject(error);
+    }
+    this.pending.clear();
+  }
+
+  // Tees the server's stderr to ours (tagged with its name) and to its log file
+  private captureStderr(logFile: string): void {
+    const rl = readline.createInterface({ input: this.process!.stderr! });
+
+    rl.on("line", (line) => {
+      process.stderr.write(`[${this.name}] ${line}\n`);
+      this.appendLog(logFile, line);
     });
   }
 
-  private async request(method: string, params?: unknown): Promise<JsonRpcResponse> {
+  private appendLog(logFile: string, line: string): void {
+    try {
+      appendServerLog(logFile, line, this.config.logFileMaxBytes ?? DEFAULT_LOG_FILE_MAX_BYTES);
+    } catch (e) {
+      console.error(`[${this.name}] Failed to write log file ${logFile}:`, e);
+    }
+  }
+
+  // Stdout that isn't JSON-RPC gets a warning per line, unless the server is known to log there
+  // (tolerateStdoutLogs): then it is kept like stderr in the log file, and echoed only when debugging
+  private handleNonJsonOutput(line: string): void {
+    if (!this.tolerateStdoutLogs) {
+      console.error(`[${this.name}] Ignoring non-JSON output:`, line);
+      return;
+    }
+    if (this.debug) process.stderr.write(`[${this.name}] ${line}\n`);
+    if (this.config.logFile) this.appendLog(path.resolve(getConfigDir(), this.config.logFile), line);
+  }
+
+  // Parent env (all of it, or only envInherit with envClear), then the env file, then explicit env
+  private buildEnv(): NodeJS.ProcessEnv {
+    const inherited: NodeJS.ProcessEnv = {};
+    if (this.config.envClear) {
+      for (const key of this.config.envInherit ?? []) {
+        if (process.env[key] !== undefined) inherited[key] = process.env[key];
+      }
+    } else {
+      Object.assign(inherited, process.env);
+    }
+
+    const fileEnv = this.config.envFile ? loadEnvFile(this.config.envFile) : {};
+    return { ...inherited, ...fileEnv, ...this.config.env };
+  }
+
+  isRunning(): boolean {
+    return this.process !== null && this.process.exitCode === null && this.process.signalCode === null;
+  }
+
+  private async answerRequest(request: JsonRpcRequest): Promise<void> {
+    let response: JsonRpcResponse;
+
+    try {
+      if (!this.onRequest) {
+        throw new McpError(ErrorCodes.METHOD_NOT_FOUND, `Method not found: ${request.method}`);
+      }
+      const result = await this.onRequest(request.method, request.params);
+      response = { jsonrpc: "2.0", id: request.id, result };
+    } catch (e) {
+      response = {
+        jsonrpc: "2.0",
+        id: request.id,
+        error: {
+          code: e instanceof McpError ? e.code : ErrorCodes.INTERNAL_ERROR,
+          message: e instanceof Error ? e.message : String(e),
+        },
+      };
+    }
+
+    this.process?.stdin?.write(JSON.stringify(response) + "\n");
+  }
+
+  private async request(
+    method: string,
+    params?: unknown,
+    signal?: AbortSignal,
+    timeoutMs = this.timeoutMs
+  ): Promise<JsonRpcResponse> {
+    if (this.exitReason) {
+      throw new Error(`MCP server '${this.name}' ${this.exitReason}`);
+    }
     if (!this.process?.stdin) {
       throw new Error("Process not started");
     }
+    if (signal?.aborted) {
+      throw new Error(`Request cancelled: ${method}`);
+    }
 
     const id = this.nextId++;
     const request: JsonRpcRequest = {
@@ -79,20 +356,39 @@ export class StdioClient {
     return new Promise((resolve, reject) => {
       this.pending.set(id, { resolve, reject });
 
+      // Stop waiting and tell the backend it can stop working on the request
+      signal?.addEventListener(
+        "abort",
+        () => {
+          if (this.pending.delete(id)) {
+            const reason = typeof signal.reason === "string" ? signal.reason : undefined;
+            this.notify("notifications/cancelled", { requestId: id, reason });
+            reject(new Error(`Request cancelled: ${method}`));
+          }
+        },
+        { once: true }
+      );
+
       const json = JSON.stringify(request);
       this.process!.stdin!.write(json + "\n");
 
-      // Timeout after 30s
       setTimeout(() => {
         if (this.pending.has(id)) {
           this.pending.delete(id);
           reject(new Error(`Request timeout: ${method}`));
         }
-      }, 30000);
+      }, timeoutMs);
     });
   }
 
-  private notify(method: string, params?: unknown): void {
+  // Sends any method and returns the response as the backend sent it, error included, for
+  // methods without a typed wrapper (e.g. vendor extensions). Timeouts, cancellation and a dead
+  // process still throw, as they do for the typed methods.
+  async requestRaw(method: string, params?: unknown, signal?: AbortSignal): Promise<JsonRpcResponse> {
+    return this.request(method, params, signal);
+  }
+
+  notify(method: string, params?: unknown): void {
     if (!this.process?.stdin) return;
 
     const request: JsonRpcRequest = {
@@ -104,48 +400,191 @@ export class StdioClient {
     this.process.stdin.write(JSON.stringify(request) + "\n");
   }
 
-  async initialize(): Promise<InitializeResult> {
-    const response = await this.request("initialize", {
-      protocolVersion: "2024-11-05",
-      capabilities: {},
-      clientInfo: { name: "mcp-central", version: "0.1.0" },
-    });
+  // Kills the process when the handshake fails, so a failed connect doesn't leave it running
+  async initialize(capabilities: ClientCapabilities = {}): Promise<InitializeResult> {
+    let params: Record<string, unknown> = {
+      protocolVersion: this.config.protocolVersion ?? PROTOCOL_VERSION,
+      capabilities,
+      clientInfo: this.clientInfo,
+    };
+    if (this.config.initParamsExtra) {
+      const merged = mergeExtraParams(params, this.config.initParamsExtra);
+      if (merged.conflicts.length > 0) {
+        console.error(`[${this.name}] Ignoring initParamsExtra fields the bridge sets: ${merged.conflicts.join(", ")}`);
+      }
+      params = merged.params;
+    }
+
+    let response: JsonRpcResponse;
+    try {
+      response = await this.request("initialize", params, undefined, this.initTimeoutMs);
+    } catch (e) {
+      await this.shutdown();
+      if (e instanceof Error && e.message.startsWith("Request timeout")) {
+        throw new Error(`MCP server '${this.name}' did not answer initialize within ${this.initTimeoutMs / 1000}s`);
+      }
+      throw e;
+    }
 
     if (response.error) {
+      await this.shutdown();
       throw new Error(`Initialize failed: ${response.error.message}`);
     }
 
     this.serverInfo = response.result as InitializeResult;
+    this.connectedAt = Date.now();
     this.notify("notifications/initialized");
 
     return this.serverInfo;
   }
 
   async listTools(): Promise<Tool[]> {
-    const response = await this.request("tools/list");
+    const tools: Tool[] = [];
+    let cursor: string | undefined;
+
+    // Follow nextCursor until the backend has returned every page
+    do {
+      const response = await this.request("tools/list", cursor ? { cursor } : undefined);
+
+      if (response.error) {
+        throw new Error(`tools/list failed: ${response.error.message}`);
+      }
+
+      const result = response.result as ToolsListResult;
+      tools.push(...result.tools);
+      cursor = result.nextCursor;
+    } while (cursor);
+
+    this.tools = tools;
+    return this.tools;
+  }
+
+  // Returns the round-trip time in milliseconds
+  async ping(timeoutMs?: number): Promise<number> {
+    const startTime = Date.now();
+    const response = await this.request("ping", undefined, undefined, timeoutMs);
 
     if (response.error) {
-      throw new Error(`tools/list failed: ${response.error.message}`);
+      throw new Error(`ping failed: ${response.error.message}`);
     }
 
-    const result = response.result as { tools: Tool[] };
-    this.tools = result.tools;
-    return this.tools;
+    return Date.now() - startTime;
+  }
+
+  async callTool(params: ToolCallParams, signal?: AbortSignal, timeoutMs?: number): Promise<ToolCallResult> {
+    this.callCount++;
+    this.inFlight++;
+
+    try {
+      const response = await this.request("tools/call", params, signal, timeoutMs);
+
+      // Keep the backend's code, so callers can tell e.g. invalid params from internal errors
+      if (response.error) {
+        throw new McpError(response.error.code, `tools/call failed: ${response.error.message}`);
+      }
+
+      return response.result as ToolCallResult;
+    } finally {
+      this.inFlight--;
+    }
+  }
+
+  async listResources(meta?: RequestMeta): Promise<Resource[]> {
+    const resources: Resource[] = [];
+    let cursor: string | undefined;
+
+    do {
+      const response = await this.request("resources/list", pageParams(cursor, meta));
+
+      if (response.error) {
+        throw new Error(`resources/list failed: ${response.error.message}`);
+      }
+
+      const result = response.result as ResourcesListResult;
+      resources.push(...result.resources);
+      cursor = result.nextCursor;
+    } while (cursor);
+
+    return resources;
   }
 
-  async callTool(params: ToolCallParams): Promise<ToolCallResult> {
-    const response = await this.request("tools/call", params);
+  async listResourceTemplates(meta?: RequestMeta): Promise<ResourceTemplate[]> {
+    const templates: ResourceTemplate[] = [];
+    let cursor: string | undefined;
+
+    do {
+      const response = await this.request("resources/templates/list", pageParams(cursor, meta));
+
+      if (response.error) {
+        throw new Error(`resources/templates/list failed: ${response.error.message}`);
+      }
+
+      const result = response.result as ResourceTemplatesListResult;
+      templates.push(...result.resourceTemplates);
+      cursor = result.nextCursor;
+    } while (cursor);
+
+    return templates;
+  }
+
+  async readResource(uri: string, meta?: RequestMeta): Promise<ReadResourceResult> {
+    const response = await this.request("resources/read", { uri, _meta: meta });
 
     if (response.error) {
-      throw new Error(`tools/call failed: ${response.error.message}`);
+      throw new Error(`resources/read failed: ${response.error.message}`);
     }
 
-    return response.result as ToolCallResult;
+    return response.result as ReadResourceResult;
   }
 
-  async shutdown(): Promise<void> {
+  async subscribeResource(uri: string): Promise<void> {
+    const response = await this.request("resources/subscribe", { uri });
+
+    if (response.error) {
+      throw new Error(`resources/subscribe failed: ${response.error.message}`);
+    }
+  }
+
+  async unsubscribeResource(uri: string): Promise<void> {
+    const response = await this.request("resources/unsubscribe", { uri });
+
+    if (response.error) {
+      throw new Error(`resources/unsubscribe failed: ${response.error.message}`);
+    }
+  }
+
+  async complete(params: CompleteParams): Promise<CompletionResult> {
+    const response = await this.request("completion/complete", params);
+
+    if (response.error) {
+      throw new Error(`completion/complete failed: ${response.error.message}`);
+    }
+
+    return response.result as CompletionResult;
+  }
+
+  // Asks the process to exit (SIGTERM) and SIGKILLs it if it's still running after timeoutMs.
+  // Returns true when it had to be killed.
+  async shutdown(timeoutMs = DEFAULT_SHUTDOWN_TIMEOUT_MS): Promise<boolean> {
     this.notify("notifications/cancelled");
-    this.process?.kill();
+    const child = this.process;
     this.process = null;
+    if (!child?.pid || child.exitCode !== null || child.signalCode !== null) return false;
+
+    const exited = new Promise<void>((resolve) => child.once("exit", () => resolve()));
+    child.kill();
+
+    let timer: NodeJS.Timeout | undefined;
+    const timedOut = new Promise<boolean>((resolve) => {
+      timer = setTimeout(() => resolve(true), timeoutMs);
+    });
+    const stuck = await Promise.race([exited.then(() => false), timedOut]);
+    clearTimeout(timer);
+    if (!stuck) return false;
+
+    console.error(`[${this.name}] Still running ${timeoutMs}ms after SIGTERM, killing it`);
+    child.kill("SIGKILL");
+    await exited;
+    return true;
   }
 }
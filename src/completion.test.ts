@@ -0,0 +1,34 @@
+import { describe, it, expect } from "vitest";
+import { Command } from "commander";
+import { COMPLETION_SHELLS, generateCompletion } from "./completion.js";
+
+function createProgram(): Command {
+  const program = new Command().name("mcp-central");
+  program.command("add").description("Add a new MCP server").option("-t, --tag <tag>", "Tag this server");
+  program.command("daemon").description("Start the bridge as an HTTP daemon").option("-p, --port <port>", "Port");
+  return program;
+}
+
+describe("completion", () => {
+  it.each(COMPLETION_SHELLS)("should include every subcommand and flag for %s", (shell) => {
+    const script = generateCompletion(createProgram(), shell);
+
+    for (const word of ["add", "daemon", "tag", "port"]) {
+      expect(script).toContain(word);
+    }
+  });
+
+  it("should register the bash completion function for the binary", () => {
+    const script = generateCompletion(createProgram(), "bash");
+
+    expect(script).toContain("complete -o default -F _mcp_central mcp-central");
+    expect(script).toContain("add) opts='-t --tag' ;;");
+  });
+
+  it("should escape quotes in descriptions", () => {
+    const program = createProgram();
+    program.command("rm").description("Remove a server's config");
+
+    expect(generateCompletion(program, "fish")).toContain(`-d 'Remove a server'\\''s config'`);
+  });
+});
@@ -0,0 +1,151 @@
+import type { Command } from "commander";
+
+export const COMPLETION_SHELLS = ["bash", "zsh", "fish", "powershell"] as const;
+export type CompletionShell = (typeof COMPLETION_SHELLS)[number];
+
+interface CommandSpec {
+  name: string;
+  description: string;
+  flags: { short?: string; long?: string; description: string }[];
+}
+
+// Flattens the commander program into the subcommands and flags we complete
+function describeCommands(program: Command): CommandSpec[] {
+  return program.commands.map((cmd) => ({
+    name: cmd.name(),
+    description: cmd.description(),
+    flags: cmd.options.map((opt) => ({ short: opt.short, long: opt.long, description: opt.description })),
+  }));
+}
+
+function flagNames(spec: CommandSpec): string[] {
+  return spec.flags.flatMap((f) => [f.short, f.long].filter((n): n is string => !!n));
+}
+
+// Single-quoted string literal for POSIX shells and fish
+function quote(text: string): string {
+  return `'${text.replace(/'/g, `'\\''`)}'`;
+}
+
+function functionName(bin: string): string {
+  return `_${bin.replace(/[^A-Za-z0-9]/g, "_")}`;
+}
+
+function bash(bin: string, specs: CommandSpec[]): string {
+  const fn = functionName(bin);
+  const cases = specs
+    .map((s) => `    ${s.name}) opts=${quote(flagNames(s).join(" "))} ;;`)
+    .join("\n");
+
+  return `${fn}() {
+  local cur="\${COMP_WORDS[COMP_CWORD]}"
+  if [ "$COMP_CWORD" -eq 1 ]; then
+    COMPREPLY=($(compgen -W ${quote(specs.map((s) => s.name).join(" "))} -- "$cur"))
+    return
+  fi
+
+  local opts=""
+  case "\${COMP_WORDS[1]}" in
+${cases}
+  esac
+  COMPREPLY=($(compgen -W "$opts" -- "$cur"))
+}
+complete -o default -F ${fn} ${bin}
+`;
+}
+
+function zsh(bin: string, specs: CommandSpec[]): string {
+  const fn = functionName(bin);
+  // _describe splits entries on the first unescaped colon
+  const entry = (name: string, description: string) => quote(`${name}:${description.replace(/:/g, "\\:")}`);
+
+  const commands = specs.map((s) => `    ${entry(s.name, s.description)}`).join("\n");
+  const cases = specs
+    .map((s) => {
+      const opts = s.flags.flatMap((f) =>
+        [f.short, f.long].filter((n): n is string => !!n).map((n) => entry(n, f.description))
+      );
+      return `    ${s.name}) opts=(${opts.join(" ")}) ;;`;
+    })
+    .join("\n");
+
+  return `#compdef ${bin}
+
+${fn}() {
+  local -a commands opts
+  commands=(
+${commands}
+  )
+
+  if (( CURRENT == 2 )); then
+    _describe 'command' commands
+    return
+  fi
+
+  case $words[2] in
+${cases}
+  esac
+  _describe 'option' opts || _files
+}
+
+${fn} "$@"
+`;
+}
+
+function fish(bin: string, specs: CommandSpec[]): string {
+  const lines = [`complete -c ${bin} -f`];
+
+  for (const s of specs) {
+    lines.push(`complete -c ${bin} -n __fish_use_subcommand -a ${s.name} -d ${quote(s.description)}`);
+    for (const f of s.flags) {
+      const short = f.short ? ` -s ${f.short.replace(/^-/, "")}` : "";
+      const long = f.long ? ` -l ${f.long.replace(/^--/, "")}` : "";
+      lines.push(`complete -c ${bin} -n '__fish_seen_subcommand_from ${s.name}'${short}${long} -d ${quote(f.description)}`);
+    }
+  }
+
+  return lines.join("\n") + "\n";
+}
+
+function powershell(bin: string, specs: CommandSpec[]): string {
+  const psQuote = (text: string) => `'${text.replace(/'/g, "''")}'`;
+  const table = specs
+    .map((s) => `    ${psQuote(s.name)} = @(${flagNames(s).map(psQuote).join(", ")})`)
+    .join("\n");
+
+  return `Register-ArgumentCompleter -Native -CommandName ${psQuote(bin)} -ScriptBlock {
+  param($wordToComplete, $commandAst, $cursorPosition)
+
+  $commands = @{
+${table}
+  }
+
+  $words = @($commandAst.CommandElements | ForEach-Object { $_.ToString() })
+  if ($words.Count -le 1 -or ($words.Count -eq 2 -and $wordToComplete)) {
+    $candidates = $commands.Keys
+  } else {
+    $candidates = $commands[$words[1]]
+  }
+
+  $candidates | Where-Object { $_ -like "$wordToComplete*" } | Sort-Object | ForEach-Object {
+    [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)
+  }
+}
+`;
+}
+
+export function generateCompletion(program: Command, shell: CompletionShell): string {
+  const bin = program.name();
+  const specs = describeCommands(program);
+
+  switch (shell) {
+    case "bash":
+      return bash(bin, specs);
+    case "zsh":
+      return zsh(bin, specs);
+    case "fish":
+      return fish(bin, specs);
+    case "powershell":
+      return powershell(bin, specs);
+  }
+}
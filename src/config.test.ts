@@ -1,5 +1,28 @@
-import { describe, it, expect } from "vitest";
-import { addServer, removeServer, getEnabledServers } from "./config.js";
+import { describe, it, expect, afterEach } from "vitest";
+import * as fs from "node:fs";
+import * as os from "node:os";
+import * as path from "node:path";
+import {
+  addServer,
+  removeServer,
+  renameServer,
+  setServerEnv,
+  unsetServerEnv,
+  updateServer,
+  setServersEnabled,
+  getEnabledServers,
+  unknownFilterNames,
+  sortByStartOrder,
+  parseEnvFile,
+  redactServer,
+  validateConfig,
+  getConfigDir,
+  loadConfig,
+  saveConfig,
+  updateConfig,
+  useStdinConfig,
+} from "./config.js";
+import { useTempConfigDir } from "../test/testing.js";
 import type { Config, McpServerConfig } from "./types.js";
 
 function createConfig(): Config {
@@ -33,6 +56,42 @@ describe("config", () => {
       addServer(config, createServer("test"));
       expect(() => addServer(config, createServer("test"))).toThrow("already exists");
     });
+
+    it("should reject an empty name", () => {
+      const config = createConfig();
+      expect(() => addServer(config, createServer(""))).toThrow("cannot be empty");
+    });
+
+    it("should reject a whitespace-only name", () => {
+      const config = createConfig();
+      expect(() => addServer(config, createServer("   "))).toThrow("cannot be empty");
+    });
+
+    it("should reject a name containing whitespace", () => {
+      const config = createConfig();
+      expect(() => addServer(config, createServer("my server"))).toThrow("whitespace");
+    });
+
+    it("should reject a name containing the namespace separator", () => {
+      const config = createConfig();
+      expect(() => addServer(config, createServer("git__hub"))).toThrow("cannot contain '__'");
+      expect(config.servers).toHaveLength(0);
+    });
+
+    it("should reject a group named like an ungrouped server, and the reverse", () => {
+      const config = createConfig();
+      addServer(config, createServer("fs"));
+      addServer(config, { ...createServer("git1"), group: "git" });
+
+      expect(() => addServer(config, { ...createServer("fs1"), group: "fs" })).toThrow(
+        "Group 'fs' shares the 'fs__' namespace with server 'fs'"
+      );
+      expect(() => addServer(config, createServer("git"))).toThrow(
+        "Server 'git' shares the 'git__' namespace with group 'git'"
+      );
+      addServer(config, { ...createServer("git2"), group: "git" });
+      expect(config.servers.map((s) => s.name)).toEqual(["fs", "git1", "git2"]);
+    });
   });
 
   describe("removeServer", () => {
@@ -50,7 +109,142 @@ describe("config", () => {
     });
   });
 
+  describe("renameServer", () => {
+    it("should rename a server", () => {
+      const config = createConfig();
+      addServer(config, createServer("old"));
+      renameServer(config, "old", "new");
+      expect(config.servers.map((s) => s.name)).toEqual(["new"]);
+    });
+
+    it("should throw if the old name is not found", () => {
+      const config = createConfig();
+      expect(() => renameServer(config, "missing", "new")).toThrow("not found");
+    });
+
+    it("should refuse a new name that a group already uses", () => {
+      const config = createConfig();
+      addServer(config, createServer("a"));
+      addServer(config, { ...createServer("b1"), group: "b" });
+
+      expect(() => renameServer(config, "a", "b")).toThrow("shares the 'b__' namespace with group 'b'");
+    });
+
+    it("should throw if the new name already exists", () => {
+      const config = createConfig();
+      addServer(config, createServer("a"));
+      addServer(config, createServer("b"));
+      expect(() => renameServer(config, "a", "b")).toThrow("already exists");
+    });
+
+    it("should reject an invalid new name", () => {
+      const config = createConfig();
+      addServer(config, createServer("a"));
+      expect(() => renameServer(config, "a", "a__b")).toThrow("cannot contain");
+      expect(config.servers[0].name).toBe("a");
+    });
+  });
+
+  describe("updateServer", () => {
+    it("should only update the provided fields", () => {
+      const config = createConfig();
+      addServer(config, createServer("test"));
+
+      updateServer(config, "test", { command: "node" });
+      expect(config.servers[0]).toMatchObject({ command: "node", args: ["hello"] });
+
+      updateServer(config, "test", { args: ["server.js"] });
+      expect(config.servers[0]).toMatchObject({ command: "node", args: ["server.js"] });
+    });
+
+    it("should clear args when given an empty list (edit --clear-args)", () => {
+      const config = createConfig();
+      addServer(config, createServer("test"));
+
+      updateServer(config, "test", { args: [] });
+      expect(config.servers[0]).toMatchObject({ command: "echo", args: [] });
+    });
+
+    it("should throw if server not found", () => {
+      const config = createConfig();
+      expect(() => updateServer(config, "missing", { command: "node" })).toThrow("not found");
+    });
+  });
+
+  describe("setServerEnv / unsetServerEnv", () => {
+    it("should set then unset an env var", () => {
+      const config = createConfig();
+      addServer(config, createServer("test"));
+
+      setServerEnv(config, "test", "TOKEN", "abc");
+      expect(config.servers[0].env).toEqual({ TOKEN: "abc" });
+
+      setServerEnv(config, "test", "TOKEN", "xyz");
+      expect(config.servers[0].env).toEqual({ TOKEN: "xyz" });
+
+      unsetServerEnv(config, "test", "TOKEN");
+      expect(config.servers[0].env).toEqual({});
+    });
+
+    it("should throw when unsetting a missing key unless ignored", () => {
+      const config = createConfig();
+      addServer(config, createServer("test"));
+
+      expect(() => unsetServerEnv(config, "test", "TOKEN")).toThrow("not set");
+      expect(() => unsetServerEnv(config, "test", "TOKEN", { ignoreMissing: true })).not.toThrow();
+    });
+
+    it("should throw if server not found", () => {
+      const config = createConfig();
+      expect(() => setServerEnv(config, "missing", "K", "V")).toThrow("not found");
+    });
+  });
+
+  describe("redactServer", () => {
+    it("should mask credential-like and secretEnv keys without touching the original", () => {
+      const server = {
+        ...createServer("test"),
+        env: { GITHUB_TOKEN: "ghp_abc", api_key: "k", DB_URL: "postgres://u:p@db", REGION: "eu" },
+        secretEnv: ["DB_URL"],
+      };
+
+      expect(redactServer(server).env).toEqual({
+        GITHUB_TOKEN: "***",
+        api_key: "***",
+        DB_URL: "***",
+        REGION: "eu",
+      });
+      expect(server.env.GITHUB_TOKEN).toBe("ghp_abc");
+    });
+  });
+
   describe("getEnabledServers", () => {
+    it("should keep only the named servers with only, and drop them with exclude", () => {
+      const config = createConfig();
+      addServer(config, createServer("a"));
+      addServer(config, createServer("b"));
+      addServer(config, { ...createServer("c"), enabled: false });
+
+      expect(getEnabledServers(config, { only: ["b", "c"] }).map((s) => s.name)).toEqual(["b"]);
+      expect(getEnabledServers(config, { exclude: ["a"] }).map((s) => s.name)).toEqual(["b"]);
+      expect(unknownFilterNames(config, { only: ["a", "typo"] })).toEqual(["typo"]);
+    });
+
+    it("should let MCP_CENTRAL_ENABLE_ONLY and MCP_CENTRAL_DISABLE override the enabled flags", () => {
+      const config = createConfig();
+      addServer(config, createServer("fs"));
+      addServer(config, createServer("github"));
+      addServer(config, { ...createServer("slack"), enabled: false });
+      const names = (env: NodeJS.ProcessEnv, filter = {}) =>
+        getEnabledServers(config, filter, env).map((s) => s.name);
+
+      expect(names({ MCP_CENTRAL_DISABLE: "github, slack" })).toEqual(["fs"]);
+      expect(names({ MCP_CENTRAL_ENABLE_ONLY: "slack" })).toEqual(["slack"]);
+      expect(names({ MCP_CENTRAL_ENABLE_ONLY: "fs,slack", MCP_CENTRAL_DISABLE: "fs" })).toEqual(["slack"]);
+      expect(names({ MCP_CENTRAL_ENABLE_ONLY: "fs,github" }, { exclude: ["fs"] })).toEqual(["github"]);
+      expect(config.servers.map((s) => s.enabled)).toEqual([true, true, false]);
+    });
+
     it("should return only enabled servers", () => {
       const config = createConfig();
       addServer(config, createServer("enabled1"));
@@ -61,5 +255,288 @@ describe("config", () => {
       expect(enabled).toHaveLength(2);
       expect(enabled.map((s) => s.name)).toEqual(["enabled1", "enabled2"]);
     });
+
+    it("should filter by tag when a tag is given", () => {
+      const config = createConfig();
+      addServer(config, { ...createServer("dev1"), tags: ["dev"] });
+      addServer(config, { ...createServer("prod1"), tags: ["prod"] });
+      addServer(config, { ...createServer("both"), tags: ["dev", "prod"] });
+      addServer(config, { ...createServer("untagged") });
+      addServer(config, { ...createServer("dev-disabled"), tags: ["dev"], enabled: false });
+
+      expect(getEnabledServers(config, { tag: "dev" }).map((s) => s.name)).toEqual(["dev1", "both"]);
+      expect(getEnabledServers(config, { tag: "prod" }).map((s) => s.name)).toEqual(["prod1", "both"]);
+      expect(getEnabledServers(config, { tag: "missing" })).toHaveLength(0);
+      expect(getEnabledServers(config)).toHaveLength(4);
+    });
+  });
+
+  describe("sortByStartOrder", () => {
+    it("should order by startOrder and keep config order for ties", () => {
+      const servers = [
+        { ...createServer("c"), startOrder: 2 },
+        createServer("a"),
+        { ...createServer("first"), startOrder: -1 },
+        createServer("b"),
+      ];
+
+      expect(sortByStartOrder(servers).map((s) => s.name)).toEqual(["first", "a", "b", "c"]);
+      expect(servers[0].name).toBe("c");
+    });
+  });
+
+  describe("parseEnvFile", () => {
+    it("should parse KEY=VALUE lines and skip comments and blanks", () => {
+      const env = parseEnvFile("# tokens\nTOKEN=abc\n\nexport REGION = us-east-1\nURL=http://x?a=b\n");
+      expect(env).toEqual({ TOKEN: "abc", REGION: "us-east-1", URL: "http://x?a=b" });
+    });
+
+    it("should unwrap quoted values", () => {
+      const env = parseEnvFile([
+        'DOUBLE="hello world"',
+        "SINGLE='it has # and spaces'",
+        'ESCAPED="line1\\nline2 \\"quoted\\""',
+        "RAW='no\\nescape'",
+        'EMPTY=""',
+      ].join("\n"));
+
+      expect(env).toEqual({
+        DOUBLE: "hello world",
+        SINGLE: "it has # and spaces",
+        ESCAPED: 'line1\nline2 "quoted"',
+        RAW: "no\\nescape",
+        EMPTY: "",
+      });
+    });
+
+    it("should strip trailing comments from unquoted values only", () => {
+      const env = parseEnvFile('A=value # comment\nB="value # kept"\nC=pass#word');
+      expect(env).toEqual({ A: "value", B: "value # kept", C: "pass#word" });
+    });
+
+    it("should ignore lines without a key", () => {
+      expect(parseEnvFile("=nokey\nnotanassignment\nOK=1")).toEqual({ OK: "1" });
+    });
+  });
+
+  describe("validateConfig", () => {
+    it("should accept a valid config", () => {
+      const config = createConfig();
+      config.servers.push(createServer("a"), createServer("b"));
+      expect(validateConfig(config)).toEqual([]);
+    });
+
+    it("should report duplicate names, bad names and empty commands", () => {
+      const config = createConfig();
+      config.servers.push(createServer("a"), createServer("a"), createServer("bad__name"), {
+        ...createServer("empty"),
+        command: " ",
+      });
+
+      const issues = validateConfig(config);
+
+      expect(issues.map((i) => i.server)).toEqual(["a", "bad__name", "empty"]);
+      expect(issues.every((i) => i.fatal)).toBe(true);
+    });
+
+    it("should report missing env files", () => {
+      const config = createConfig();
+      config.servers.push({ ...createServer("a"), envFile: "/nonexistent/mcp-central.env" });
+
+      const issues = validateConfig(config);
+
+      expect(issues).toHaveLength(1);
+      expect(issues[0].message).toContain("/nonexistent/mcp-central.env");
+    });
+
+    it("should report missing working directories", () => {
+      const config = createConfig();
+      config.servers.push(
+        { ...createServer("a"), cwd: os.tmpdir() },
+        { ...createServer("b"), cwd: "/nonexistent/dir" }
+      );
+
+      expect(validateConfig(config)).toEqual([
+        { server: "b", message: "Working directory not found: /nonexistent/dir", fatal: true },
+      ]);
+    });
+
+    it("should warn about unknown protocol versions", () => {
+      const config = createConfig();
+      config.servers.push(
+        { ...createServer("old"), protocolVersion: "2024-11-05" },
+        { ...createServer("typo"), protocolVersion: "2024-11-5" }
+      );
+
+      const issues = validateConfig(config);
+
+      expect(issues).toEqual([
+        expect.objectContaining({ server: "typo", fatal: false, message: expect.stringContaining("2024-11-5") }),
+      ]);
+    });
+
+    it("should reject a server name that collides with a group", () => {
+      const config = createConfig();
+      config.servers.push(createServer("fs"), { ...createServer("fs1"), group: "fs" });
+
+      const issues = validateConfig(config);
+
+      expect(issues).toEqual([{ server: "fs", message: expect.stringContaining("group 'fs'"), fatal: true }]);
+    });
+  });
+
+  describe("saveConfig / updateConfig", () => {
+    useTempConfigDir("mcp-central-config-");
+
+    it("should persist changes without leaving temp or lock files behind", () => {
+      saveConfig(createConfig());
+      updateConfig((config) => addServer(config, createServer("a")));
+
+      expect(loadConfig().servers.map((s) => s.name)).toEqual(["a"]);
+      expect(fs.readdirSync(getConfigDir())).toEqual(["config.json"]);
+    });
+
+    it("should load back exactly what was saved, as list --format json prints it", () => {
+      const config = createConfig();
+      config.servers.push(
+        { ...createServer("fs"), env: { ROOT: "/tmp" }, tags: ["local"], group: "files", timeoutSecs: 5 },
+        { ...createServer("git"), enabled: false, cwd: "repos", toolDescriptions: { status: "Repo status" } }
+      );
+
+      saveConfig(config);
+
+      expect(loadConfig()).toEqual(config);
+      expect(JSON.parse(JSON.stringify(loadConfig().servers, null, 2))).toEqual(config.servers);
+    });
+
+    it("should leave the config untouched and release the lock when a mutation fails", () => {
+      updateConfig((config) => addServer(config, createServer("a")));
+
+      expect(() => updateConfig((config) => addServer(config, createServer("a")))).toThrow("already exists");
+
+      expect(loadConfig().servers).toHaveLength(1);
+      expect(fs.readdirSync(getConfigDir())).toEqual(["config.json"]);
+    });
+
+    it("should break a stale lock left by a crashed process", () => {
+      fs.mkdirSync(getConfigDir(), { recursive: true });
+      const lockPath = path.join(getConfigDir(), "config.json.lock");
+      fs.writeFileSync(lockPath, "12345");
+      const old = new Date(Date.now() - 60_000);
+      fs.utimesSync(lockPath, old, old);
+
+      updateConfig((config) => addServer(config, createServer("a")));
+
+      expect(loadConfig().servers).toHaveLength(1);
+    });
+  });
+
+  describe("setServersEnabled", () => {
+    it("should toggle every server with a tag", () => {
+      const config = createConfig();
+      config.servers = [
+        { ...createServer("a"), tags: ["experimental"] },
+        { ...createServer("b"), tags: ["dev", "experimental"] },
+        { ...createServer("c"), tags: ["dev"] },
+      ];
+
+      const disabled = setServersEnabled(config, { tag: "experimental" }, false);
+
+      expect(disabled.map((s) => s.name)).toEqual(["a", "b"]);
+      expect(config.servers.map((s) => s.enabled)).toEqual([false, false, true]);
+
+      setServersEnabled(config, { tag: "experimental" }, true);
+      expect(config.servers.map((s) => s.enabled)).toEqual([true, true, true]);
+    });
+
+    it("should toggle one server by name", () => {
+      const config = createConfig();
+      config.servers = [createServer("a"), createServer("b")];
+
+      setServersEnabled(config, { name: "b" }, false);
+
+      expect(config.servers.map((s) => s.enabled)).toEqual([true, false]);
+    });
+
+    it("should throw when nothing matches", () => {
+      const config = createConfig();
+      config.servers = [{ ...createServer("a"), tags: ["dev"] }];
+
+      expect(() => setServersEnabled(config, { tag: "prod" }, false)).toThrow("No servers tagged 'prod'");
+      expect(() => setServersEnabled(config, { name: "missing" }, false)).toThrow("Server 'missing' not found");
+    });
+  });
+
+  describe("useStdinConfig", () => {
+    afterEach(() => {
+      useStdinConfig(null);
+    });
+
+    it("should load the config from stdin text and refuse to save it", () => {
+      useStdinConfig(JSON.stringify({ servers: [createServer("fs")] }));
+
+      const config = loadConfig();
+      expect(config.servers.map((s) => s.name)).toEqual(["fs"]);
+      expect(config.settings).toEqual({ logLevel: "info", daemonPort: 3000 });
+
+      expect(() => updateConfig((c) => addServer(c, createServer("db")))).toThrow(
+        "The config was read from stdin (--config-stdin), so changes can't be saved"
+      );
+      expect(() => saveConfig(config)).toThrow("changes can't be saved");
+    });
+
+    it("should reject malformed config", () => {
+      expect(() => useStdinConfig("{oops")).toThrow("Invalid config on stdin:");
+      expect(() => useStdinConfig('{"settings":{}}')).toThrow("expected a JSON object with a servers array");
+    });
+  });
+
+  describe("include", () => {
+    useTempConfigDir("mcp-central-include-");
+
+    const writeJson = (file: string, content: unknown) => {
+      fs.mkdirSync(getConfigDir(), { recursive: true });
+      fs.writeFileSync(path.join(getConfigDir(), file), JSON.stringify(content));
+    };
+
+    it("should merge servers from included files without saving them into config.json", () => {
+      writeJson("team.json", { servers: [createServer("github"), createServer("jira")] });
+      writeJson("config.json", { ...createConfig(), servers: [createServer("fs")], include: ["team.json"] });
+
+      expect(loadConfig().servers.map((s) => [s.name, s.includedFrom])).toEqual([
+        ["fs", undefined],
+        ["github", "team.json"],
+        ["jira", "team.json"],
+      ]);
+
+      updateConfig((config) => addServer(config, createServer("db")));
+
+      const saved = JSON.parse(fs.readFileSync(path.join(getConfigDir(), "config.json"), "utf-8"));
+      expect(saved.servers.map((s: McpServerConfig) => s.name)).toEqual(["fs", "db"]);
+      expect(loadConfig().servers).toHaveLength(4);
+    });
+
+    it("should refuse to edit included servers", () => {
+      writeJson("team.json", { servers: [createServer("github")] });
+      writeJson("config.json", { ...createConfig(), include: ["team.json"] });
+
+      expect(() => updateConfig((config) => removeServer(config, "github"))).toThrow(
+        "Server 'github' is defined in team.json, edit it there"
+      );
+    });
+
+    it("should fail when an included file is missing", () => {
+      writeJson("config.json", { ...createConfig(), include: ["missing.json"] });
+
+      expect(() => loadConfig()).toThrow("Failed to load included config");
+    });
+
+    it("should fail when a server name is defined in more than one file", () => {
+      writeJson("a.json", { servers: [createServer("github")] });
+      writeJson("b.json", { servers: [createServer("github")] });
+      writeJson("config.json", { ...createConfig(), include: ["a.json", "b.json"] });
+
+      expect(() => loadConfig()).toThrow("Server 'github' in b.json is already defined in a.json");
+    });
   });
 });
@@ -2,8 +2,13 @@ import * as fs from "node:fs";
 import * as path from "node:path";
 import * as os from "node:os";
 import type { Config, McpServerConfig } from "./types.js";
+import { KNOWN_PROTOCOL_VERSIONS, NAMESPACE_SEPARATOR } from "./types.js";
 
-function getConfigDir(): string {
+// MCP_CENTRAL_CONFIG_DIR moves everything (config, logs, stats, caches) elsewhere, e.g. for tests
+export function getConfigDir(): string {
+  if (process.env.MCP_CENTRAL_CONFIG_DIR) {
+    return path.resolve(process.env.MCP_CENTRAL_CONFIG_DIR);
+  }
   if (process.platform === "darwin") {
     return path.join(os.homedir(), "Library", "Application Support", "mcp-central");
   }
@@ -14,7 +19,56 @@ function getConfigPath(): string {
   return path.join(getConfigDir(), "config.json");
 }
 
+// Write to a temp file in the same directory then rename over the target,
+// so readers never observe a partially written file
+export function writeFileAtomic(filePath: string, content: string): void {
+  const tmpPath = `${filePath}.${process.pid}.tmp`;
+  fs.writeFileSync(tmpPath, content);
+  fs.renameSync(tmpPath, filePath);
+}
+
+// Config passed on stdin with --config-stdin, used instead of config.json for the whole run.
+// There is no file behind it, so saving is refused.
+let stdinConfig: Config | null = null;
+
+// Parses a whole config as given on stdin and makes loadConfig return it from now on
+// (null goes back to config.json)
+export function useStdinConfig(text: string | null): Config | null {
+  if (text === null) {
+    stdinConfig = null;
+    return null;
+  }
+
+  let parsed: unknown;
+  try {
+    parsed = JSON.parse(text);
+  } catch (e) {
+    throw new Error(`Invalid config on stdin: ${e instanceof Error ? e.message : e}`);
+  }
+  const servers = (parsed as { servers?: unknown } | null)?.servers;
+  if (typeof parsed !== "object" || parsed === null || Array.isArray(parsed) || !Array.isArray(servers)) {
+    throw new Error("Invalid config on stdin: expected a JSON object with a servers array");
+  }
+
+  const config = parsed as Config;
+  config.settings = { logLevel: "info", daemonPort: 3000, ...config.settings };
+  for (const include of config.include ?? []) {
+    mergeInclude(config, include);
+  }
+  stdinConfig = config;
+  return config;
+}
+
+function assertWritable(): void {
+  if (stdinConfig) {
+    throw new Error("The config was read from stdin (--config-stdin), so changes can't be saved");
+  }
+}
+
 export function loadConfig(): Config {
+  // A copy, so callers can change it freely as they can one read from disk
+  if (stdinConfig) return structuredClone(stdinConfig);
+
   const configPath = getConfigPath();
 
   if (!fs.existsSync(configPath)) {
@@ -25,30 +79,252 @@ export function loadConfig(): Config {
   }
 
   const content = fs.readFileSync(configPath, "utf-8");
-  return JSON.parse(content) as Config;
+  const config = JSON.parse(content) as Config;
+  for (const include of config.include ?? []) {
+    mergeInclude(config, include);
+  }
+  return config;
+}
+
+// Appends the servers of an included file (relative to the config dir), marked with
+// includedFrom so they aren't written back to config.json
+function mergeInclude(config: Config, include: string): void {
+  const includePath = path.resolve(getConfigDir(), include);
+
+  let included: { servers?: McpServerConfig[] };
+  try {
+    included = JSON.parse(fs.readFileSync(includePath, "utf-8"));
+  } catch (e) {
+    throw new Error(`Failed to load included config ${includePath}: ${e instanceof Error ? e.message : e}`);
+  }
+
+  for (const server of included.servers ?? []) {
+    const existing = config.servers.find((s) => s.name === server.name);
+    if (existing) {
+      throw new Error(
+        `Server '${server.name}' in ${include} is already defined in ${existing.includedFrom ?? "config.json"}`
+      );
+    }
+    config.servers.push({ ...server, includedFrom: include });
+  }
+}
+
+function writeConfig(config: Config): void {
+  fs.mkdirSync(getConfigDir(), { recursive: true });
+  const own = { ...config, servers: config.servers.filter((s) => !s.includedFrom) };
+  writeFileAtomic(getConfigPath(), JSON.stringify(own, null, 2));
 }
 
 export function saveConfig(config: Config): void {
-  const configDir = getConfigDir();
-  fs.mkdirSync(configDir, { recursive: true });
+  assertWritable();
+  withConfigLock(() => writeConfig(config));
+}
 
-  const configPath = getConfigPath();
-  fs.writeFileSync(configPath, JSON.stringify(config, null, 2));
+// Load, mutate and save the config while holding the lock, so concurrent
+// invocations can't overwrite each other's changes
+export function updateConfig<T>(mutate: (config: Config) => T): T {
+  assertWritable();
+  return withConfigLock(() => {
+    const config = loadConfig();
+    const result = mutate(config);
+    writeConfig(config);
+    return result;
+  });
+}
+
+// A lock left behind by a crashed process is broken after this long
+const LOCK_STALE_MS = 10_000;
+const LOCK_TIMEOUT_MS = 5_000;
+const LOCK_RETRY_MS = 25;
+
+// Advisory lock shared by every mcp-central process writing the config (or stats.json). The lock
+// file is created exclusively, so only one process can hold it at a time.
+export function withConfigLock<T>(fn: () => T): T {
+  fs.mkdirSync(getConfigDir(), { recursive: true });
+  const lockPath = `${getConfigPath()}.lock`;
+  const deadline = Date.now() + LOCK_TIMEOUT_MS;
+
+  for (;;) {
+    try {
+      fs.writeFileSync(lockPath, String(process.pid), { flag: "wx" });
+      break;
+    } catch (e) {
+      if ((e as NodeJS.ErrnoException).code !== "EEXIST") throw e;
+    }
+
+    try {
+      if (Date.now() - fs.statSync(lockPath).mtimeMs > LOCK_STALE_MS) {
+        fs.rmSync(lockPath, { force: true });
+        continue;
+      }
+    } catch {
+      // Released between our attempt and the stat, try again
+      continue;
+    }
+
+    if (Date.now() > deadline) {
+      throw new Error(`Timed out waiting for config lock ${lockPath}`);
+    }
+    Atomics.wait(new Int32Array(new SharedArrayBuffer(4)), 0, 0, LOCK_RETRY_MS);
+  }
+
+  try {
+    return fn();
+  } finally {
+    fs.rmSync(lockPath, { force: true });
+  }
+}
+
+// Parses KEY=VALUE lines. Blank lines and # comments are skipped, an optional
+// "export " prefix is allowed, and values may be wrapped in single or double quotes.
+export function parseEnvFile(content: string): Record<string, string> {
+  const env: Record<string, string> = {};
+
+  for (const rawLine of content.split(/\r?\n/)) {
+    let line = rawLine.trim();
+    if (!line || line.startsWith("#")) continue;
+    if (line.startsWith("export ")) line = line.slice("export ".length).trim();
+
+    const eq = line.indexOf("=");
+    if (eq <= 0) continue;
+
+    const key = line.slice(0, eq).trim();
+    let value = line.slice(eq + 1).trim();
+
+    const quote = value[0];
+    if ((quote === '"' || quote === "'") && value.length >= 2 && value.endsWith(quote)) {
+      value = value.slice(1, -1);
+      if (quote === '"') {
+        value = value.replace(/\\n/g, "\n").replace(/\\"/g, '"');
+      }
+    } else {
+      // Unquoted values may carry a trailing comment
+      const hash = value.indexOf(" #");
+      if (hash !== -1) value = value.slice(0, hash).trimEnd();
+    }
+
+    env[key] = value;
+  }
+
+  return env;
+}
+
+export function resolveEnvFilePath(envFile: string): string {
+  return path.resolve(getConfigDir(), envFile);
+}
+
+export function resolveServerCwd(cwd: string): string {
+  return path.resolve(getConfigDir(), cwd);
+}
+
+export function loadEnvFile(envFile: string): Record<string, string> {
+  return parseEnvFile(fs.readFileSync(resolveEnvFilePath(envFile), "utf-8"));
+}
+
+// Also used for group names, which become tool namespaces the same way
+export function validateServerName(name: string, label = "Server name"): void {
+  if (!name || !name.trim()) {
+    throw new Error(`${label} cannot be empty`);
+  }
+  if (name !== name.trim() || /\s/.test(name)) {
+    throw new Error(`${label} '${name}' cannot contain whitespace`);
+  }
+  // The separator would make namespaced tool names ambiguous to parse
+  if (name.includes(NAMESPACE_SEPARATOR)) {
+    throw new Error(`${label} '${name}' cannot contain '${NAMESPACE_SEPARATOR}'`);
+  }
+}
+
+// A server outside any group is namespaced by its name and a group member by its group, so a
+// group and an ungrouped server of the same name would claim the same tool namespace
+function namespaceClash(config: Config, server: McpServerConfig, self?: McpServerConfig): string | null {
+  const others = config.servers.filter((s) => s !== self);
+  if (server.group !== undefined) {
+    const clash = others.find((s) => !s.group && s.name === server.group);
+    return clash ? `Group '${server.group}' shares the '${server.group}__' namespace with server '${clash.name}'` : null;
+  }
+  return others.some((s) => s.group === server.name)
+    ? `Server '${server.name}' shares the '${server.name}__' namespace with group '${server.name}'`
+    : null;
 }
 
 export function addServer(config: Config, server: McpServerConfig): void {
+  validateServerName(server.name);
+  if (server.group !== undefined) {
+    validateServerName(server.group, "Group name");
+  }
   if (config.servers.some((s) => s.name === server.name)) {
     throw new Error(`Server '${server.name}' already exists`);
   }
+  const clash = namespaceClash(config, server);
+  if (clash) throw new Error(clash);
   config.servers.push(server);
 }
 
-export function removeServer(config: Config, name: string): McpServerConfig {
-  const idx = config.servers.findIndex((s) => s.name === name);
-  if (idx === -1) {
+// A server defined in config.json itself. Included ones are edited in their own file.
+function findOwnServer(config: Config, name: string): McpServerConfig {
+  const server = config.servers.find((s) => s.name === name);
+  if (!server) {
     throw new Error(`Server '${name}' not found`);
   }
-  return config.servers.splice(idx, 1)[0];
+  if (server.includedFrom) {
+    throw new Error(`Server '${name}' is defined in ${server.includedFrom}, edit it there`);
+  }
+  return server;
+}
+
+export function removeServer(config: Config, name: string): McpServerConfig {
+  const server = findOwnServer(config, name);
+  return config.servers.splice(config.servers.indexOf(server), 1)[0];
+}
+
+export function renameServer(config: Config, oldName: string, newName: string): McpServerConfig {
+  const server = findOwnServer(config, oldName);
+  validateServerName(newName);
+  if (config.servers.some((s) => s.name === newName)) {
+    throw new Error(`Server '${newName}' already exists`);
+  }
+  const clash = namespaceClash(config, { ...server, name: newName }, server);
+  if (clash) throw new Error(clash);
+  server.name = newName;
+  return server;
+}
+
+export function setServerEnv(config: Config, name: string, key: string, value: string): McpServerConfig {
+  const server = findOwnServer(config, name);
+  server.env[key] = value;
+  return server;
+}
+
+export function unsetServerEnv(
+  config: Config,
+  name: string,
+  key: string,
+  opts: { ignoreMissing?: boolean } = {}
+): McpServerConfig {
+  const server = findOwnServer(config, name);
+  if (!(key in server.env)) {
+    if (opts.ignoreMissing) return server;
+    throw new Error(`Env var '${key}' is not set on server '${name}'`);
+  }
+  delete server.env[key];
+  return server;
+}
+
+// Env keys whose values look like credentials, on top of a server's explicit secretEnv
+const SECRET_ENV_KEY = /TOKEN|KEY|SECRET|PASSWORD|CREDENTIAL/i;
+export const REDACTED = "***";
+
+export function isSecretEnvKey(server: McpServerConfig, key: string): boolean {
+  return server.secretEnv?.includes(key) || SECRET_ENV_KEY.test(key);
+}
+
+// Copy of a server for display, with secret env values masked. The child still gets the real values.
+export function redactServer(server: McpServerConfig): McpServerConfig {
+  const env = Object.fromEntries(
+    Object.entries(server.env).map(([key, value]) => [key, isSecretEnvKey(server, key) ? REDACTED : value])
+  );
+  return { ...server, env };
 }
 
 export function updateServer(
@@ -56,14 +332,138 @@ export function updateServer(
   name: string,
   updates: Partial<Omit<McpServerConfig, "name">>
 ): McpServerConfig {
-  const server = config.servers.find((s) => s.name === name);
-  if (!server) {
-    throw new Error(`Server '${name}' not found`);
-  }
+  const server = findOwnServer(config, name);
   Object.assign(server, updates);
   return server;
 }
 
-export function getEnabledServers(config: Config): McpServerConfig[] {
-  return config.servers.filter((s) => s.enabled);
+// Sets enabled on one server by name, or on every server carrying a tag, returning the servers
+// it was set on (whether or not they changed)
+export function setServersEnabled(
+  config: Config,
+  target: { name?: string; tag?: string },
+  enabled: boolean
+): McpServerConfig[] {
+  const servers = target.tag
+    ? config.servers.filter((s) => hasTag(s, target.tag!)).map((s) => findOwnServer(config, s.name))
+    : [findOwnServer(config, target.name ?? "")];
+  if (servers.length === 0) {
+    throw new Error(`No servers tagged '${target.tag}'`);
+  }
+
+  for (const server of servers) {
+    server.enabled = enabled;
+  }
+  return servers;
+}
+
+export interface ConfigIssue {
+  server: string;
+  message: string;
+  // Fatal issues stop the server (or the bridge) from starting; the rest are warnings
+  fatal: boolean;
+}
+
+// Structural checks that don't require connecting to any server. Tool names aren't
+// known yet, so only namespace collisions between servers and groups are reported.
+export function validateConfig(config: Config): ConfigIssue[] {
+  const issues: ConfigIssue[] = [];
+  const seen = new Set<string>();
+
+  for (const server of config.servers) {
+    const fatal = (message: string) => issues.push({ server: server.name, message, fatal: true });
+
+    try {
+      validateServerName(server.name);
+    } catch (e) {
+      fatal(e instanceof Error ? e.message : String(e));
+    }
+    if (seen.has(server.name)) {
+      fatal(`Duplicate server name '${server.name}'`);
+    }
+    seen.add(server.name);
+
+    if (server.group !== undefined) {
+      try {
+        validateServerName(server.group, "Group name");
+      } catch (e) {
+        fatal(e instanceof Error ? e.message : String(e));
+      }
+    }
+
+    if (!server.command || !server.command.trim()) {
+      fatal("Command is empty");
+    }
+
+    if (server.envFile && !fs.existsSync(resolveEnvFilePath(server.envFile))) {
+      fatal(`Env file not found: ${resolveEnvFilePath(server.envFile)}`);
+    }
+
+    if (server.cwd && !fs.statSync(resolveServerCwd(server.cwd), { throwIfNoEntry: false })?.isDirectory()) {
+      fatal(`Working directory not found: ${resolveServerCwd(server.cwd)}`);
+    }
+
+    if (server.protocolVersion && !KNOWN_PROTOCOL_VERSIONS.includes(server.protocolVersion)) {
+      issues.push({
+        server: server.name,
+        message: `Unknown protocolVersion '${server.protocolVersion}' (known: ${KNOWN_PROTOCOL_VERSIONS.join(", ")})`,
+        fatal: false,
+      });
+    }
+
+    // Reported once, on the ungrouped server: calls to the namespace would reach it and the group alike
+    const clash = server.group === undefined ? namespaceClash(config, server, server) : null;
+    if (clash) {
+      fatal(clash);
+    }
+  }
+
+  return issues;
+}
+
+export interface ServerFilter {
+  tag?: string;
+  // Server names to connect exclusively, or to leave out (one or the other)
+  only?: string[];
+  exclude?: string[];
+}
+
+export function hasTag(server: McpServerConfig, tag: string): boolean {
+  return server.tags?.includes(tag) ?? false;
+}
+
+const nameList = (list: string | undefined) => list?.split(",").map((n) => n.trim()).filter(Boolean);
+
+// The saved enabled flag, overridden by MCP_CENTRAL_ENABLE_ONLY (enable just these, even if
+// disabled) and then MCP_CENTRAL_DISABLE (disable these), both comma-separated server names
+export function isServerEnabled(server: McpServerConfig, env: NodeJS.ProcessEnv = process.env): boolean {
+  if (nameList(env.MCP_CENTRAL_DISABLE)?.includes(server.name)) return false;
+  const enableOnly = nameList(env.MCP_CENTRAL_ENABLE_ONLY);
+  return enableOnly ? enableOnly.includes(server.name) : server.enabled;
+}
+
+// --tag/--only/--exclude narrow the servers left enabled after the env overrides
+export function getEnabledServers(
+  config: Config,
+  filter: ServerFilter = {},
+  env: NodeJS.ProcessEnv = process.env
+): McpServerConfig[] {
+  return config.servers.filter((s) => {
+    if (!isServerEnabled(s, env)) return false;
+    if (filter.tag && !hasTag(s, filter.tag)) return false;
+    if (filter.only && !filter.only.includes(s.name)) return false;
+    if (filter.exclude?.includes(s.name)) return false;
+    return true;
+  });
+}
+
+// Names in only/exclude that match no server, typically typos
+export function unknownFilterNames(config: Config, filter: ServerFilter): string[] {
+  const names = new Set(config.servers.map((s) => s.name));
+  return [...(filter.only ?? []), ...(filter.exclude ?? [])].filter((name) => !names.has(name));
+}
+
+export function sortByStartOrder(servers: McpServerConfig[]): McpServerConfig[] {
+  // Array.prototype.sort is stable, so servers without an order keep config order
+  return [...servers].sort((a, b) => (a.startOrder ?? 0) - (b.startOrder ?? 0));
 }
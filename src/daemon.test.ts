@@ -0,0 +1,409 @@
+import { describe, it, expect, vi, afterEach } from "vitest";
+import * as crypto from "node:crypto";
+import * as http from "node:http";
+import type { AddressInfo } from "node:net";
+import * as zlib from "node:zlib";
+import { acceptsGzip, createDaemonApp, loadTlsOptions } from "./daemon.js";
+import { Router } from "./router.js";
+import type { McpManager } from "./manager.js";
+import type { JsonRpcRequest, Settings } from "./types.js";
+import { ErrorCodes } from "./types.js";
+import { decodeFrames, encodeFrame, Opcodes } from "./websocket.js";
+
+function createMockManager(toolCount = 1) {
+  const tools = Array.from({ length: toolCount }, (_, i) => ({
+    name: `test__tool_${i}`,
+    description: `Test tool number ${i}`,
+    inputSchema: {},
+  }));
+  return {
+    listAllTools: vi.fn().mockReturnValue(tools),
+    getConnectedMcps: vi.fn().mockReturnValue(["test"]),
+    healthCheckAll: vi.fn().mockResolvedValue({ test: { status: "healthy", latencyMs: 1 } }),
+    setClientCapabilities: vi.fn(),
+    setClientInfo: vi.fn(),
+    getCapabilities: vi.fn().mockReturnValue({ tools: {} }),
+    getInstructions: vi.fn().mockReturnValue({}),
+    onNotification: vi.fn().mockReturnValue(() => {}),
+    closeChannel: vi.fn(),
+  } as unknown as McpManager;
+}
+
+async function createApp(manager = createMockManager(), settings: Partial<Settings> = {}) {
+  return createDaemonApp(manager, new Router(manager, settings), settings);
+}
+
+interface TestWebSocket {
+  send: (message: unknown) => void;
+  received: unknown[];
+  close: () => void;
+}
+
+// Client side of the handshake, with masked frames from the daemon's own codec
+function openWebSocket(port: number, headers: Record<string, string> = {}): Promise<TestWebSocket> {
+  return new Promise((resolve, reject) => {
+    const request = http.request({
+      host: "127.0.0.1",
+      port,
+      path: "/ws",
+      headers: {
+        connection: "Upgrade",
+        upgrade: "websocket",
+        "sec-websocket-key": crypto.randomBytes(16).toString("base64"),
+        "sec-websocket-version": "13",
+        ...headers,
+      },
+    });
+
+    request.on("upgrade", (_response, socket, head) => {
+      const received: unknown[] = [];
+      let buffer = Buffer.alloc(0);
+      const receive = (chunk: Buffer) => {
+        const { frames, rest } = decodeFrames(Buffer.concat([buffer, chunk]));
+        buffer = Buffer.from(rest);
+        for (const frame of frames) {
+          if (frame.opcode === Opcodes.TEXT) received.push(JSON.parse(frame.payload.toString("utf-8")));
+        }
+      };
+      receive(head);
+      socket.on("data", receive);
+
+      resolve({
+        send: (message) =>
+          socket.write(encodeFrame(Opcodes.TEXT, Buffer.from(JSON.stringify(message)), crypto.randomBytes(4))),
+        received,
+        close: () => socket.destroy(),
+      });
+    });
+    request.on("response", (response) => reject(new Error(`Unexpected status ${response.statusCode}`)));
+    request.on("error", reject);
+    request.end();
+  });
+}
+
+describe("acceptsGzip", () => {
+  it("should honour q values and the * wildcard", () => {
+    expect(acceptsGzip("gzip, deflate, br")).toBe(true);
+    expect(acceptsGzip("GZIP;q=0.5")).toBe(true);
+    expect(acceptsGzip("gzip;q=0")).toBe(false);
+    expect(acceptsGzip("gzip; q=0.0, *")).toBe(false);
+    expect(acceptsGzip("*")).toBe(true);
+    expect(acceptsGzip("br, *;q=0")).toBe(false);
+    expect(acceptsGzip("x-gzip-ish")).toBe(false);
+    expect(acceptsGzip("")).toBe(false);
+  });
+});
+
+describe("daemon", () => {
+  describe("compression", () => {
+    it("should gzip large responses when the client accepts it", async () => {
+      const app = await createApp(createMockManager(100));
+
+      const response = await app.inject({
+        method: "GET",
+        url: "/tools",
+        headers: { "accept-encoding": "gzip" },
+      });
+
+      expect(response.headers["content-encoding"]).toBe("gzip");
+      const body = JSON.parse(zlib.gunzipSync(response.rawPayload).toString());
+      expect(body.tools).toHaveLength(100);
+    });
+
+    it("should not compress without Accept-Encoding", async () => {
+      const app = await createApp(createMockManager(100));
+
+      const response = await app.inject({ method: "GET", url: "/tools" });
+
+      expect(response.headers["content-encoding"]).toBeUndefined();
+      expect(response.json().tools).toHaveLength(100);
+    });
+
+    it("should not compress when the client refuses gzip with q=0", async () => {
+      const app = await createApp(createMockManager(100));
+
+      const response = await app.inject({
+        method: "GET",
+        url: "/tools",
+        headers: { "accept-encoding": "gzip;q=0, identity" },
+      });
+
+      expect(response.headers["content-encoding"]).toBeUndefined();
+      expect(response.json().tools).toHaveLength(100);
+    });
+
+    it("should not compress small responses", async () => {
+      const app = await createApp();
+
+      const response = await app.inject({
+        method: "GET",
+        url: "/health",
+        headers: { "accept-encoding": "gzip" },
+      });
+
+      expect(response.headers["content-encoding"]).toBeUndefined();
+      expect(response.json().status).toBe("ok");
+    });
+  });
+
+  describe("body limit", () => {
+    it("should reject oversized requests with 413 and a JSON-RPC error", async () => {
+      const app = await createApp(createMockManager(), { maxBodyBytes: 100 });
+
+      const response = await app.inject({
+        method: "POST",
+        url: "/mcp",
+        payload: {
+          jsonrpc: "2.0",
+          id: 1,
+          method: "tools/call",
+          params: { name: "test__tool", arguments: { blob: "a".repeat(200) } },
+        },
+      });
+
+      expect(response.statusCode).toBe(413);
+      expect(response.json().error.code).toBe(ErrorCodes.INVALID_REQUEST);
+    });
+
+    it("should accept requests under the limit", async () => {
+      const app = await createApp(createMockManager(), { maxBodyBytes: 1000 });
+
+      const response = await app.inject({
+        method: "POST",
+        url: "/mcp",
+        payload: { jsonrpc: "2.0", id: 1, method: "ping" },
+      });
+
+      expect(response.statusCode).toBe(200);
+      expect(response.json()).toEqual({ jsonrpc: "2.0", id: 1, result: {} });
+    });
+  });
+
+  describe("cors", () => {
+    const preflight = (app: Awaited<ReturnType<typeof createApp>>, origin: string) =>
+      app.inject({
+        method: "OPTIONS",
+        url: "/mcp",
+        headers: { origin, "access-control-request-method": "POST" },
+      });
+
+    it("should only allow localhost origins by default", async () => {
+      const app = await createApp();
+
+      const allowed = await preflight(app, "http://localhost:5173");
+      const rejected = await preflight(app, "https://evil.example.com");
+
+      expect(allowed.headers["access-control-allow-origin"]).toBe("http://localhost:5173");
+      expect(rejected.headers["access-control-allow-origin"]).toBeUndefined();
+    });
+
+    it("should only allow configured origins", async () => {
+      const app = await createApp(createMockManager(), { corsOrigins: ["https://app.example.com"] });
+
+      const allowed = await preflight(app, "https://app.example.com");
+      const rejected = await preflight(app, "http://localhost:5173");
+
+      expect(allowed.headers["access-control-allow-origin"]).toBe("https://app.example.com");
+      expect(rejected.headers["access-control-allow-origin"]).toBeUndefined();
+    });
+
+    it("should allow any origin with *", async () => {
+      const app = await createApp(createMockManager(), { corsOrigins: ["*"] });
+
+      const response = await preflight(app, "https://anywhere.example.com");
+
+      expect(response.headers["access-control-allow-origin"]).toBe("https://anywhere.example.com");
+    });
+  });
+
+  describe("tls", () => {
+    it("should serve plain HTTP when unconfigured", () => {
+      expect(loadTlsOptions({})).toBeNull();
+    });
+
+    it("should require both a certificate and a key", () => {
+      expect(() => loadTlsOptions({ tlsCert: "cert.pem" })).toThrow("both a certificate and a key");
+    });
+
+    it("should report unreadable files clearly", () => {
+      expect(() => loadTlsOptions({ tlsCert: "/nonexistent/cert.pem", tlsKey: "/nonexistent/key.pem" })).toThrow(
+        "Failed to read TLS certificate /nonexistent/cert.pem"
+      );
+    });
+  });
+
+  describe("/health", () => {
+    it("should include each server's health", async () => {
+      const app = await createApp();
+
+      const response = await app.inject({ method: "GET", url: "/health" });
+
+      expect(response.json()).toMatchObject({ status: "ok", servers: { test: { status: "healthy" } } });
+    });
+
+    it("should report degraded when a server is unresponsive", async () => {
+      const manager = createMockManager();
+      vi.mocked(manager.healthCheckAll).mockResolvedValue({
+        test: { status: "unresponsive", error: "Request timeout: ping" },
+      });
+      const app = await createApp(manager);
+
+      const response = await app.inject({ method: "GET", url: "/health" });
+
+      expect(response.statusCode).toBe(200);
+      expect(response.json().status).toBe("degraded");
+    });
+  });
+
+  describe("/mcp", () => {
+    it("should echo string ids unchanged", async () => {
+      const app = await createApp();
+
+      const response = await app.inject({
+        method: "POST",
+        url: "/mcp",
+        payload: { jsonrpc: "2.0", id: "42", method: "tools/list" },
+      });
+
+      expect(response.json().id).toBe("42");
+    });
+
+    it("should echo string ids unchanged on errors", async () => {
+      const app = await createApp();
+
+      const response = await app.inject({
+        method: "POST",
+        url: "/mcp",
+        payload: { jsonrpc: "2.0", id: "req-1", method: "unknown/method" },
+      });
+
+      expect(response.json()).toMatchObject({ id: "req-1", error: { code: ErrorCodes.METHOD_NOT_FOUND } });
+    });
+
+    it("should answer a request without a method with an invalid request error", async () => {
+      const app = await createApp();
+
+      const response = await app.inject({ method: "POST", url: "/mcp", payload: { jsonrpc: "2.0", id: 1 } });
+
+      expect(response.statusCode).toBe(200);
+      expect(response.json()).toEqual({
+        jsonrpc: "2.0",
+        id: 1,
+        error: { code: ErrorCodes.INVALID_REQUEST, message: "Invalid request: missing 'method'" },
+      });
+    });
+  });
+
+  describe("/api/servers", () => {
+    const secretEnv = { GITHUB_TOKEN: "ghp_abc", REGION: "eu" };
+    const redactedEnv = { GITHUB_TOKEN: "***", REGION: "eu" };
+
+    it("should mask secret env values when adding, listing, updating and removing", async () => {
+      const app = await createApp();
+
+      const added = await app.inject({
+        method: "POST",
+        url: "/api/servers",
+        payload: { name: "gh", command: "gh-mcp", env: secretEnv },
+      });
+      expect(added.json().server.env).toEqual(redactedEnv);
+
+      const listed = await app.inject({ method: "GET", url: "/api/servers" });
+      const listedServer = listed.json().servers.find((s: { name: string }) => s.name === "gh");
+      expect(listedServer.env).toEqual(redactedEnv);
+
+      const patched = await app.inject({ method: "PATCH", url: "/api/servers/gh", payload: { enabled: false } });
+      expect(patched.json().server.env).toEqual(redactedEnv);
+
+      const removed = await app.inject({ method: "DELETE", url: "/api/servers/gh" });
+      expect(removed.json().server.env).toEqual(redactedEnv);
+      expect(removed.body).not.toContain("ghp_abc");
+    });
+  });
+
+  describe("/ws", () => {
+    let app: Awaited<ReturnType<typeof createApp>> | null = null;
+    let ws: TestWebSocket | null = null;
+
+    afterEach(async () => {
+      ws?.close();
+      await app?.close();
+      ws = null;
+      app = null;
+    });
+
+    async function listen(manager = createMockManager(), settings: Partial<Settings> = {}) {
+      app = await createApp(manager, settings);
+      await app.listen({ port: 0, host: "127.0.0.1" });
+      return (app.server.address() as AddressInfo).port;
+    }
+
+    it("should initialize and list tools over a WebSocket", async () => {
+      ws = await openWebSocket(await listen());
+
+      ws.send({ jsonrpc: "2.0", id: 1, method: "initialize", params: { protocolVersion: "2024-11-05" } });
+      ws.send({ jsonrpc: "2.0", id: 2, method: "tools/list" });
+      await vi.waitFor(() => expect(ws!.received).toHaveLength(2));
+
+      expect(ws.received).toContainEqual(
+        expect.objectContaining({ id: 1, result: expect.objectContaining({ serverInfo: expect.any(Object) }) })
+      );
+      expect(ws.received).toContainEqual({
+        jsonrpc: "2.0",
+        id: 2,
+        result: { tools: [{ name: "test__tool_0", description: "Test tool number 0", inputSchema: {} }] },
+      });
+    });
+
+    it("should push notifications to the socket", async () => {
+      const manager = createMockManager();
+      let notify: (notification: JsonRpcRequest) => void = () => {};
+      vi.mocked(manager.onNotification).mockImplementation((listener) => {
+        notify = listener;
+        return () => {};
+      });
+      ws = await openWebSocket(await listen(manager));
+
+      await vi.waitFor(() => expect(manager.onNotification).toHaveBeenCalled());
+      notify({ jsonrpc: "2.0", method: "notifications/tools/list_changed" });
+
+      await vi.waitFor(() =>
+        expect(ws!.received).toEqual([{ jsonrpc: "2.0", method: "notifications/tools/list_changed" }])
+      );
+    });
+
+    it("should reject origins that aren't allowed", async () => {
+      const port = await listen();
+
+      await expect(openWebSocket(port, { origin: "https://evil.example.com" })).rejects.toThrow("403");
+    });
+  });
+
+  describe("access log", () => {
+    afterEach(() => {
+      vi.restoreAllMocks();
+    });
+
+    it("should log method, path, JSON-RPC method and status", async () => {
+      const log = vi.spyOn(console, "error").mockImplementation(() => {});
+      const app = await createApp();
+
+      await app.inject({
+        method: "POST",
+        url: "/mcp",
+        payload: { jsonrpc: "2.0", id: 1, method: "ping" },
+      });
+
+      expect(log).toHaveBeenCalledWith(expect.stringMatching(/^POST \/mcp ping 200 \d+ms$/));
+    });
+
+    it("should not log below info level", async () => {
+      const log = vi.spyOn(console, "error").mockImplementation(() => {});
+      const app = await createApp(createMockManager(), { logLevel: "warn" });
+
+      await app.inject({ method: "GET", url: "/health" });
+
+      expect(log).not.toHaveBeenCalled();
+    });
+  });
+});
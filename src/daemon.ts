@@ -1,13 +1,22 @@
-import Fastify from "fastify";
+import * as fs from "node:fs";
+import type { IncomingMessage } from "node:http";
+import type { Duplex } from "node:stream";
+import { promisify } from "node:util";
+import * as zlib from "node:zlib";
+import Fastify, { type FastifyInstance } from "fastify";
 import cors from "@fastify/cors";
-import type { Config, JsonRpcRequest } from "./types.js";
+import type { Config, JsonRpcRequest, Settings } from "./types.js";
+import { ErrorCodes } from "./types.js";
+import type { ServerFilter } from "./config.js";
 import { McpManager } from "./manager.js";
-import { Router } from "./router.js";
-import { registerApiRoutes } from "./api.js";
+import { Router, Session } from "./router.js";
+import { registerApiRoutes, startEventStream } from "./api.js";
+import { handleLine, invalidRequestResponse } from "./server.js";
+import { upgradeToWebSocket } from "./websocket.js";
 
-export async function runDaemon(config: Config, port: number): Promise<void> {
-  const manager = new McpManager();
-  await manager.connectAll(config);
+export async function runDaemon(config: Config, port: number, filter: ServerFilter = {}): Promise<void> {
+  const manager = new McpManager(config.settings);
+  await manager.connectAll(config, filter);
 
   const connected = manager.getConnectedMcps();
   if (connected.length === 0) {
@@ -16,41 +25,208 @@ export async function runDaemon(config: Config, port: number): Promise<void> {
     console.error(`Connected to ${connected.length} MCP servers: ${connected.join(", ")}`);
   }
 
-  const router = new Router(manager);
+  const router = new Router(manager, config.settings);
 
-  const app = Fastify({ logger: false });
+  let app: FastifyInstance;
+  try {
+    app = await createDaemonApp(manager, router, config.settings);
+  } catch (err) {
+    console.error(err instanceof Error ? err.message : err);
+    await manager.shutdownAll();
+    process.exit(1);
+  }
+
+  const scheme = config.settings.tlsCert ? "https" : "http";
+  try {
+    await app.listen({ port, host: "0.0.0.0" });
+    console.error(`MCP bridge daemon listening on ${scheme}://0.0.0.0:${port}`);
+    console.error("Endpoints: /health, /mcp (POST), /tools, /sse, /ws (WebSocket)");
+    console.error("API: /api/logs, /api/logs/stream, /api/servers, /api/servers/:name/reconnect, /api/stats, /api/reload");
+  } catch (err) {
+    console.error("Failed to start daemon:", err);
+    process.exit(1);
+  }
+}
+
+const LOCALHOST_ORIGIN = /^https?:\/\/(localhost|127\.0\.0\.1|\[::1\])(:\d+)?$/;
+// Bodies smaller than this aren't worth the CPU to compress
+const COMPRESSION_THRESHOLD = 1024;
+const DEFAULT_MAX_BODY_BYTES = 10 * 1024 * 1024;
+// Access log lines are written when settings.logLevel is one of these
+const ACCESS_LOG_LEVELS = new Set(["debug", "info"]);
+
+const gzip = promisify(zlib.gzip);
+
+// Whether an Accept-Encoding header allows gzip: listed (or covered by *) with a q above 0.
+// An explicit gzip entry wins over *, so "gzip;q=0, *" still refuses it.
+export function acceptsGzip(acceptEncoding: string): boolean {
+  let wildcard = false;
+  for (const entry of acceptEncoding.split(",")) {
+    const [coding, ...params] = entry.split(";").map((part) => part.trim().toLowerCase());
+    const q = params.find((param) => param.startsWith("q="));
+    const allowed = q === undefined || parseFloat(q.slice(2)) > 0;
+    if (coding === "gzip") return allowed;
+    if (coding === "*") wildcard = allowed;
+  }
+  return wildcard;
+}
+
+function isAllowedOrigin(origin: string, allowed: (string | RegExp)[]): boolean {
+  return allowed.some((o) => (typeof o === "string" ? o === "*" || o === origin : o.test(origin)));
+}
+
+// Reads the certificate and key for HTTPS, or returns null to serve plain HTTP
+export function loadTlsOptions(settings: Partial<Settings>): { cert: Buffer; key: Buffer } | null {
+  const { tlsCert, tlsKey } = settings;
+  if (!tlsCert && !tlsKey) return null;
+  if (!tlsCert || !tlsKey) {
+    throw new Error("TLS needs both a certificate and a key (tlsCert and tlsKey)");
+  }
+
+  const read = (label: string, file: string) => {
+    try {
+      return fs.readFileSync(file);
+    } catch (e) {
+      throw new Error(`Failed to read TLS ${label} ${file}: ${e instanceof Error ? e.message : e}`);
+    }
+  };
+  return { cert: read("certificate", tlsCert), key: read("key", tlsKey) };
+}
+
+export async function createDaemonApp(
+  manager: McpManager,
+  router: Router,
+  settings: Partial<Settings> = {}
+): Promise<FastifyInstance> {
+  const bodyLimit = settings.maxBodyBytes ?? DEFAULT_MAX_BODY_BYTES;
+  const tls = loadTlsOptions(settings);
+  const app = tls
+    ? (Fastify({ logger: false, bodyLimit, https: tls }) as unknown as FastifyInstance)
+    : Fastify({ logger: false, bodyLimit });
+
+  app.setErrorHandler((error, request, reply) => {
+    if (error.statusCode === 413) {
+      reply.status(413).send({
+        jsonrpc: "2.0",
+        id: null,
+        error: {
+          code: ErrorCodes.INVALID_REQUEST,
+          message: `Request body exceeds the ${bodyLimit} byte limit`,
+        },
+      });
+      return;
+    }
+    // Fall back to Fastify's default error handling
+    reply.send(error);
+  });
+
+  // Browsers may only call the daemon from the configured origins (default: local pages).
+  // "*" in corsOrigins allows any origin.
+  const corsOrigins: (string | RegExp)[] = settings.corsOrigins ?? [LOCALHOST_ORIGIN];
+  await app.register(cors, {
+    origin: corsOrigins.includes("*") ? true : corsOrigins,
+  });
 
-  // Enable CORS for browser access
-  await app.register(cors, { origin: true });
+  if (ACCESS_LOG_LEVELS.has(settings.logLevel ?? "info")) {
+    app.addHook("onResponse", async (request, reply) => {
+      const body = request.body as Partial<JsonRpcRequest> | undefined;
+      const rpcMethod = request.url === "/mcp" && body?.method ? ` ${body.method}` : "";
+      console.error(
+        `${request.method} ${request.url}${rpcMethod} ${reply.statusCode} ${Math.round(reply.elapsedTime)}ms`
+      );
+    });
+  }
+
+  // Gzip large responses (tool results with big text or base64 images) when the client accepts it.
+  // Streaming endpoints write to reply.raw directly and bypass this hook.
+  app.addHook("onSend", async (request, reply, payload) => {
+    if (typeof payload !== "string" && !Buffer.isBuffer(payload)) return payload;
+    if (reply.getHeader("content-encoding")) return payload;
+    if (Buffer.byteLength(payload) < COMPRESSION_THRESHOLD) return payload;
+
+    if (!acceptsGzip(String(request.headers["accept-encoding"] ?? ""))) return payload;
+
+    const compressed = await gzip(payload);
+    reply.header("content-encoding", "gzip");
+    reply.header("vary", "accept-encoding");
+    reply.removeHeader("content-length");
+    return compressed;
+  });
 
   // Register management API routes
   registerApiRoutes(app, manager);
 
   app.get("/health", async () => {
+    const servers = await manager.healthCheckAll();
+    const healthy = Object.values(servers).every((health) => health.status === "healthy");
     return {
-      status: "ok",
+      status: healthy ? "ok" : "degraded",
       service: "mcp-central",
       version: "0.1.0",
       connected: manager.getConnectedMcps(),
+      servers,
     };
   });
 
+  // POSTs carry no connection of their own, so they share one session: a cancel POSTed for an
+  // id aborts that id's calls from any HTTP client, but never a stdio or WebSocket client's
+  const httpSession = new Session();
   app.post("/mcp", async (request) => {
-    const jsonRpcRequest = request.body as JsonRpcRequest;
-    return router.handleRequest(jsonRpcRequest);
+    const invalid = invalidRequestResponse(request.body);
+    if (invalid) return invalid;
+
+    return router.handleRequest(request.body as JsonRpcRequest, httpSession);
   });
 
   app.get("/tools", async () => {
     return { tools: manager.listAllTools() };
   });
 
-  try {
-    await app.listen({ port, host: "0.0.0.0" });
-    console.error(`MCP bridge daemon listening on http://0.0.0.0:${port}`);
-    console.error("Endpoints: /health, /mcp (POST), /tools");
-    console.error("API: /api/logs, /api/logs/stream, /api/servers, /api/stats, /api/reload");
-  } catch (err) {
-    console.error("Failed to start daemon:", err);
-    process.exit(1);
-  }
+  // Server-sent stream of MCP notifications (tools/list_changed, resources/updated) for HTTP clients
+  app.get("/sse", async (request, reply) => {
+    startEventStream(reply);
+
+    reply.raw.write(`data: ${JSON.stringify({ type: "connected" })}\n\n`);
+
+    const stopNotifications = manager.onNotification((notification) => {
+      reply.raw.write(`event: message\ndata: ${JSON.stringify(notification)}\n\n`);
+    });
+
+    request.raw.on("close", stopNotifications);
+
+    // Keep connection open - don't return/resolve
+    return reply;
+  });
+
+  // Full-duplex MCP: each text frame is a JSON-RPC message, answered on the same socket along
+  // with notifications. Like unix socket connections, every WebSocket is its own session.
+  app.server.on("upgrade", (request: IncomingMessage, socket: Duplex, head: Buffer) => {
+    if (new URL(request.url ?? "/", "http://localhost").pathname !== "/ws") {
+      socket.end("HTTP/1.1 404 Not Found\r\nConnection: close\r\n\r\n");
+      return;
+    }
+    // Browsers don't apply CORS to WebSockets, so check the origin here
+    const origin = request.headers.origin;
+    if (origin && !isAllowedOrigin(origin, corsOrigins)) {
+      socket.end("HTTP/1.1 403 Forbidden\r\nConnection: close\r\n\r\n");
+      return;
+    }
+
+    const send = (notification: JsonRpcRequest) => session?.send(JSON.stringify(notification));
+    const mcpSession = new Session(false, send);
+    const session = upgradeToWebSocket(request, socket, head, bodyLimit, async (text) => {
+      const output = await handleLine(router, text, undefined, mcpSession);
+      if (output) session?.send(output);
+    });
+    if (!session) return;
+
+    const stopNotifications = manager.onNotification(send);
+    socket.on("close", () => {
+      stopNotifications();
+      mcpSession.cancelAll("Connection closed");
+      manager.closeChannel(send);
+    });
+  });
+
+  return app;
 }
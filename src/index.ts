@@ -4,6 +4,15 @@ export * from "./client.js";
 export * from "./manager.js";
 export * from "./router.js";
 export * from "./logger.js";
+export * from "./stats.js";
+export * from "./transforms.js";
 export { registerApiRoutes } from "./api.js";
 export { runStdioServer } from "./server.js";
-export { runDaemon } from "./daemon.js";
+export { runSocketServer } from "./socket.js";
+
+// daemon.js pulls in fastify, an optional dependency, so it's only loaded when these are called
+type DaemonModule = typeof import("./daemon.js");
+export const runDaemon: DaemonModule["runDaemon"] = async (...args) =>
+  (await import("./daemon.js")).runDaemon(...args);
+export const createDaemonApp: DaemonModule["createDaemonApp"] = async (...args) =>
+  (await import("./daemon.js")).createDaemonApp(...args);
@@ -0,0 +1,74 @@
+import { describe, it, expect } from "vitest";
+import { JsonStreamParser } from "./jsonstream.js";
+
+function collect(chunks: string[]): { values: unknown[]; garbage: string[] } {
+  const values: unknown[] = [];
+  const garbage: string[] = [];
+  const parser = new JsonStreamParser(
+    (text) => values.push(JSON.parse(text)),
+    (line) => garbage.push(line)
+  );
+  for (const chunk of chunks) parser.push(chunk);
+  return { values, garbage };
+}
+
+describe("JsonStreamParser", () => {
+  it("should parse one message per line", () => {
+    expect(collect(['{"id":1}\n{"id":2}\n']).values).toEqual([{ id: 1 }, { id: 2 }]);
+  });
+
+  it("should parse several messages on one line", () => {
+    expect(collect(['{"id":1}{"id":2} {"id":3}\n']).values).toEqual([{ id: 1 }, { id: 2 }, { id: 3 }]);
+  });
+
+  it("should parse a message split across lines and chunks", () => {
+    const { values } = collect(['{\n  "id": 1,\n  "result": {', '"a": [1, 2]', "}\n}", "\n"]);
+    expect(values).toEqual([{ id: 1, result: { a: [1, 2] } }]);
+  });
+
+  it("should ignore brackets and escaped quotes inside strings", () => {
+    const { values } = collect(['{"text":"} ] \\" {', '"}{"text":"\\\\"}']);
+    expect(values).toEqual([{ text: '} ] " {' }, { text: "\\" }]);
+  });
+
+  it("should parse batches as a single value", () => {
+    expect(collect(['[{"id":1},{"id":2}]\n']).values).toEqual([[{ id: 1 }, { id: 2 }]]);
+  });
+
+  it("should drop a log line with an unbalanced bracket once the next message starts", () => {
+    const { values, garbage } = collect(['[WARN retrying "db\n{"jsonrpc":"2.0","id":1}\n', '{"jsonrpc":"2.0","id":2}\n']);
+    expect(values).toEqual([
+      { jsonrpc: "2.0", id: 1 },
+      { jsonrpc: "2.0", id: 2 },
+    ]);
+    expect(garbage).toEqual(['[WARN retrying "db']);
+  });
+
+  it("should resync when the next message arrives in a later chunk", () => {
+    const { values, garbage } = collect(["[WARN retrying\n", "{", '"jsonrpc":"2.0","id":1}\n']);
+    expect(values).toEqual([{ jsonrpc: "2.0", id: 1 }]);
+    expect(garbage).toEqual(["[WARN retrying"]);
+  });
+
+  it("should not mistake batch elements on their own lines for a new message", () => {
+    const { values } = collect(['[\n{"jsonrpc":"2.0","id":1},\n{"jsonrpc":"2.0","id":2}\n]\n']);
+    expect(values).toEqual([
+      [
+        { jsonrpc: "2.0", id: 1 },
+        { jsonrpc: "2.0", id: 2 },
+      ],
+    ]);
+  });
+
+  it("should skip log lines that start with a bracketed value", () => {
+    const { values, garbage } = collect(["[INFO] ready\n", '{"id":1}\n[2024-01-01 12:00', ":00] started\n"]);
+    expect(values).toEqual([{ id: 1 }]);
+    expect(garbage).toEqual(["[INFO] ready", "[2024-01-01 12:00:00] started"]);
+  });
+
+  it("should skip non-JSON lines", () => {
+    const { values, garbage } = collect(["Starting server", '...\n{"id":1}\n']);
+    expect(values).toEqual([{ id: 1 }]);
+    expect(garbage).toEqual(["Starting server..."]);
+  });
+});
@@ -0,0 +1,140 @@
+// Splits a stream of text into top-level JSON values. Servers are supposed to write one
+// message per line, but some write several on one line or spread one over several lines.
+// Values are found by tracking bracket depth outside of strings, so newlines don't matter.
+export class JsonStreamParser {
+  private buffer = "";
+  // How far into buffer we've scanned, and the scanner state at that point
+  private scanned = 0;
+  private depth = 0;
+  private inString = false;
+  private escaped = false;
+
+  constructor(
+    private onValue: (text: string) => void,
+    // Called with stray non-JSON output (e.g. a startup banner), one line at a time
+    private onGarbage: (text: string) => void = () => {}
+  ) {}
+
+  push(chunk: string): void {
+    const buf = this.buffer + chunk;
+    let i = this.scanned;
+    // Everything before consumed has been emitted or dropped
+    let consumed = 0;
+
+    while (i < buf.length) {
+      const ch = buf[i];
+
+      if (this.depth === 0) {
+        if (ch === "{" || ch === "[") {
+          consumed = i;
+          this.depth = 1;
+        } else if (!/\s/.test(ch)) {
+          const end = buf.indexOf("\n", i);
+          // Wait for the rest of the line
+          if (end === -1) {
+            consumed = i;
+            i = buf.length;
+            break;
+          }
+          this.onGarbage(buf.slice(i, end).trim());
+          consumed = end + 1;
+          i = end + 1;
+          continue;
+        } else {
+          consumed = i + 1;
+        }
+        i++;
+        continue;
+      }
+
+      if (ch === "\n") {
+        const next = messageStartAt(buf, i + 1);
+        // Wait for the rest of the next line before deciding
+        if (next === "maybe") break;
+        if (next === "yes" && this.isUnbalanced(buf, consumed, i)) {
+          this.discard(buf.slice(consumed, i));
+          this.depth = 0;
+          this.inString = false;
+          this.escaped = false;
+          consumed = i + 1;
+          i++;
+          continue;
+        }
+      }
+
+      if (this.inString) {
+        if (this.escaped) this.escaped = false;
+        else if (ch === "\\") this.escaped = true;
+        else if (ch === '"') this.inString = false;
+      } else if (ch === '"') {
+        this.inString = true;
+      } else if (ch === "{" || ch === "[") {
+        this.depth++;
+      } else if (ch === "}" || ch === "]") {
+        this.depth--;
+        if (this.depth === 0) {
+          // Text after it on the same line makes this the start of a log line, e.g. "[INFO] ready"
+          const next = skipBlanks(buf, i + 1);
+          if (next < buf.length && !"\r\n{[".includes(buf[next])) {
+            const end = buf.indexOf("\n", next);
+            // Wait for the rest of the line, rescanning it from the start next time
+            if (end === -1) {
+              i = buf.length;
+              break;
+            }
+            this.onGarbage(buf.slice(consumed, end).trim());
+            consumed = end + 1;
+            i = end + 1;
+            continue;
+          }
+          this.onValue(buf.slice(consumed, i + 1));
+          consumed = i + 1;
+        }
+      }
+      i++;
+    }
+
+    // Keep the unfinished tail (a partial value or line) for the next chunk
+    this.buffer = buf.slice(consumed);
+    this.scanned = this.depth > 0 ? i - consumed : 0;
+  }
+
+  // A line starting a new JSON-RPC message inside an unfinished value means that value was
+  // really a log line with an unbalanced bracket (e.g. "[WARN retrying"), which would otherwise
+  // swallow everything after it. Batch elements start lines too, but after a "[" or ",".
+  private isUnbalanced(buf: string, start: number, newline: number): boolean {
+    // JSON strings can't hold a raw newline, so being in one here is never valid JSON
+    return this.inString || !/[[,]\s*$/.test(buf.slice(start, newline));
+  }
+
+  private discard(text: string): void {
+    for (const line of text.split("\n")) {
+      if (line.trim()) this.onGarbage(line.trim());
+    }
+  }
+}
+
+const MESSAGE_START = '"jsonrpc"';
+
+// Whether the text at start opens a JSON-RPC message ({"jsonrpc"...), or might once more arrives
+function messageStartAt(buf: string, start: number): "yes" | "no" | "maybe" {
+  let i = skipWhitespace(buf, start);
+  if (i === buf.length) return "maybe";
+  if (buf[i] !== "{") return "no";
+
+  i = skipWhitespace(buf, i + 1);
+  const text = buf.slice(i, i + MESSAGE_START.length);
+  if (text === MESSAGE_START) return "yes";
+  return MESSAGE_START.startsWith(text) ? "maybe" : "no";
+}
+
+function skipWhitespace(buf: string, i: number): number {
+  while (i < buf.length && /\s/.test(buf[i])) i++;
+  return i;
+}
+
+// Like skipWhitespace, without leaving the line
+function skipBlanks(buf: string, i: number): number {
+  while (buf[i] === " " || buf[i] === "\t") i++;
+  return i;
+}
@@ -1,5 +1,8 @@
 import { describe, it, expect } from "vitest";
-import { formatLogEntry, type LogEntry } from "./logger.js";
+import * as fs from "node:fs";
+import * as os from "node:os";
+import * as path from "node:path";
+import { appendServerLog, compactArgs, formatLogEntry, logToolCall, readLogs, type LogEntry } from "./logger.js";
 
 const createEntry = (overrides: Partial<LogEntry> = {}): LogEntry => ({
   timestamp: "2024-01-15T10:30:00.000Z",
@@ -50,4 +53,58 @@ describe("logger", () => {
       expect(output).toContain("2.5s");
     });
   });
+
+  describe("logToolCall", () => {
+    it("should shorten long string arguments in the usage log", () => {
+      const args = { path: "/tmp/a.png", data: "x".repeat(300 * 1024), nested: [{ text: "y".repeat(2000) }] };
+
+      logToolCall({ mcp: "fs", tool: "write_file", args, durationMs: 5, success: true });
+
+      expect(readLogs(1)[0].args).toEqual({
+        path: "/tmp/a.png",
+        data: `${"x".repeat(100)}… (307200 chars)`,
+        nested: [{ text: `${"y".repeat(100)}… (2000 chars)` }],
+      });
+      // The call itself still got the full arguments
+      expect(args.data).toHaveLength(300 * 1024);
+    });
+
+    it("should rotate an oversized log on a single append", async () => {
+      const logPath = path.join(process.env.MCP_CENTRAL_CONFIG_DIR!, "usage.log");
+      const line = JSON.stringify(createEntry());
+      fs.writeFileSync(logPath, `${line}\n`.repeat(6001));
+
+      logToolCall({ mcp: "fs", tool: "read_file", args: {}, durationMs: 1, success: true });
+      await new Promise((resolve) => setImmediate(resolve));
+
+      const entries = readLogs();
+      expect(entries).toHaveLength(5000);
+      expect(entries[4999].tool).toBe("read_file");
+    });
+
+    it("should leave short arguments alone", () => {
+      const args = { n: 1, flag: true, list: ["a", null], text: "z".repeat(1024) };
+      expect(compactArgs(args)).toEqual(args);
+    });
+  });
+
+  describe("appendServerLog", () => {
+    it("should create the directory and rotate once the file would exceed the max size", () => {
+      const dir = fs.mkdtempSync(path.join(os.tmpdir(), "mcp-central-serverlog-"));
+      const logFile = path.join(dir, "nested", "server.log");
+
+      try {
+        appendServerLog(logFile, "first", 80);
+        appendServerLog(logFile, "second", 80);
+        expect(fs.existsSync(`${logFile}.1`)).toBe(false);
+
+        appendServerLog(logFile, "third", 80);
+
+        expect(fs.readFileSync(`${logFile}.1`, "utf-8")).toMatch(/first\n.*second\n$/);
+        expect(fs.readFileSync(logFile, "utf-8")).toMatch(/^\S+ third\n$/);
+      } finally {
+        fs.rmSync(dir, { recursive: true, force: true });
+      }
+    });
+  });
 });
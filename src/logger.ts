@@ -1,7 +1,7 @@
 import * as fs from "node:fs";
 import * as path from "node:path";
-import * as os from "node:os";
 import * as readline from "node:readline";
+import { getConfigDir } from "./config.js";
 
 export interface LogEntry {
   timestamp: string;
@@ -18,31 +18,58 @@ const MAX_LOG_ENTRIES = 5000;
 // Rotate when we hit 6000 (trim back to 5000)
 const ROTATE_THRESHOLD = 6000;
 
-function getLogDir(): string {
-  if (process.platform === "darwin") {
-    return path.join(os.homedir(), "Library", "Application Support", "mcp-central");
-  }
-  return path.join(os.homedir(), ".config", "mcp-central");
+function getLogPath(): string {
+  return path.join(getConfigDir(), "usage.log");
 }
 
-function getLogPath(): string {
-  return path.join(getLogDir(), "usage.log");
+// Rotation re-reads the whole log, so after the first append of a process only check again
+// once the log has grown (or shrunk, rotated by another process) by this many bytes
+const ROTATE_CHECK_BYTES = 64 * 1024;
+// Log size at the last rotation check, per log path
+const sizeAtRotateCheck = new Map<string, number>();
+// Log dirs already created by this process, to skip a mkdir syscall per call
+const createdLogDirs = new Set<string>();
+
+// Longer string arguments (file contents, base64 images) are cut down in the usage log
+const MAX_LOGGED_STRING = 1024;
+const LOGGED_STRING_PREFIX = 100;
+
+// Copy of a call's arguments for the usage log, with long strings shortened, so a large
+// argument isn't serialized a second time on every call or copied into usage.log
+export function compactArgs<T>(value: T): T {
+  if (typeof value === "string" && value.length > MAX_LOGGED_STRING) {
+    return `${value.slice(0, LOGGED_STRING_PREFIX)}… (${value.length} chars)` as T;
+  }
+  if (Array.isArray(value)) return value.map(compactArgs) as T;
+  if (value && typeof value === "object") {
+    return Object.fromEntries(Object.entries(value).map(([key, v]) => [key, compactArgs(v)])) as T;
+  }
+  return value;
 }
 
 export function logToolCall(entry: Omit<LogEntry, "timestamp">): void {
   const logEntry: LogEntry = {
     timestamp: new Date().toISOString(),
     ...entry,
+    args: compactArgs(entry.args),
   };
 
-  const logDir = getLogDir();
-  fs.mkdirSync(logDir, { recursive: true });
+  const logDir = getConfigDir();
+  if (!createdLogDirs.has(logDir)) {
+    fs.mkdirSync(logDir, { recursive: true });
+    createdLogDirs.add(logDir);
+  }
 
   const logPath = getLogPath();
   fs.appendFileSync(logPath, JSON.stringify(logEntry) + "\n");
 
   // Check if rotation needed (do this async-ish, don't block)
-  setImmediate(() => rotateIfNeeded(logPath));
+  const size = fs.statSync(logPath).size;
+  const checkedAt = sizeAtRotateCheck.get(logPath);
+  if (checkedAt === undefined || Math.abs(size - checkedAt) >= ROTATE_CHECK_BYTES) {
+    sizeAtRotateCheck.set(logPath, size);
+    setImmediate(() => rotateIfNeeded(logPath));
+  }
 }
 
 function rotateIfNeeded(logPath: string): void {
@@ -62,6 +89,23 @@ function rotateIfNeeded(logPath: string): void {
   }
 }
 
+// Appends one line of a backend's stderr to its logFile, moving the file to
+// `<logFile>.1` first once it would grow past maxBytes
+export function appendServerLog(logFile: string, line: string, maxBytes: number): void {
+  fs.mkdirSync(path.dirname(logFile), { recursive: true });
+
+  const entry = `${new Date().toISOString()} ${line}\n`;
+  try {
+    if (fs.statSync(logFile).size + Buffer.byteLength(entry) > maxBytes) {
+      fs.renameSync(logFile, `${logFile}.1`);
+    }
+  } catch {
+    // No log file yet
+  }
+
+  fs.appendFileSync(logFile, entry);
+}
+
 export function readLogs(limit?: number): LogEntry[] {
   const logPath = getLogPath();
 
@@ -164,7 +208,7 @@ export function getStats(): LogStats {
 
 export async function watchLogs(onEntry: (entry: LogEntry) => void): Promise<() => void> {
   const logPath = getLogPath();
-  const logDir = getLogDir();
+  const logDir = getConfigDir();
 
   fs.mkdirSync(logDir, { recursive: true });
 
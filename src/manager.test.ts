@@ -0,0 +1,1019 @@
+import { describe, it, expect, afterEach, vi } from "vitest";
+import * as fs from "node:fs";
+import * as os from "node:os";
+import * as path from "node:path";
+import { fileURLToPath } from "node:url";
+import { StdioClient } from "./client.js";
+import { McpManager, reconnectDelayMs } from "./manager.js";
+import { useTempConfigDir } from "../test/testing.js";
+import { ToolCache } from "./toolcache.js";
+import { ErrorCodes } from "./types.js";
+import type { Config, McpServerConfig } from "./types.js";
+
+const MOCK_SERVER = fileURLToPath(new URL("../test/fixtures/mock-server.mjs", import.meta.url));
+
+function mockServer(name = "mock", env: Record<string, string> = {}): McpServerConfig {
+  return {
+    name,
+    command: process.execPath,
+    args: [MOCK_SERVER],
+    env: { MOCK_NAME: name, ...env },
+    enabled: true,
+  };
+}
+
+describe("McpManager", () => {
+  let manager: McpManager | null = null;
+
+  afterEach(async () => {
+    await manager?.shutdownAll();
+    manager = null;
+  });
+
+  describe("connect", () => {
+    it("should retry listing tools while a server reports none", async () => {
+      manager = new McpManager({ toolsReadyRetries: 3, toolsReadyDelayMs: 10 });
+      await manager.connect(mockServer("mock", { MOCK_EMPTY_LISTS: "1" }));
+
+      expect(manager.listAllTools().map((t) => t.name)).toEqual(["mock__echo"]);
+    });
+
+    it("should notify listeners that the tool list changed", async () => {
+      manager = new McpManager();
+      const notifications: unknown[] = [];
+      manager.onNotification((n) => notifications.push(n));
+
+      await manager.connect(mockServer());
+
+      expect(notifications).toEqual([{ jsonrpc: "2.0", method: "notifications/tools/list_changed" }]);
+    });
+
+    it("should notify listeners when a server's tools go away on disconnect", async () => {
+      manager = new McpManager();
+      await manager.connect(mockServer());
+      const notifications: unknown[] = [];
+      manager.onNotification((n) => notifications.push(n));
+
+      await manager.disconnect("mock");
+      await manager.disconnect("mock");
+
+      expect(manager.listAllTools()).toEqual([]);
+      expect(notifications).toEqual([{ jsonrpc: "2.0", method: "notifications/tools/list_changed" }]);
+    });
+
+    it("should not retry by default", async () => {
+      manager = new McpManager();
+      await manager.connect(mockServer("mock", { MOCK_EMPTY_LISTS: "1" }));
+
+      expect(manager.listAllTools()).toEqual([]);
+    });
+  });
+
+  describe("allowShortNames", () => {
+    it("should call a tool by its bare name when only one server has it", async () => {
+      manager = new McpManager({ allowShortNames: true });
+      await manager.connect(mockServer("fs", { MOCK_TOOLS: "read,echo" }));
+      await manager.connect(mockServer("git", { MOCK_TOOLS: "status,echo" }));
+
+      const result = await manager.callTool("status", { label: "clean" });
+
+      expect(result.content).toEqual([{ type: "text", text: "clean" }]);
+      await expect(manager.callTool("echo", {})).rejects.toThrow(
+        "Tool name 'echo' is ambiguous, use one of: fs__echo, git__echo"
+      );
+      await expect(manager.callTool("push", {})).rejects.toThrow("Tool 'push' not found");
+    });
+
+    it("should reject bare names by default", async () => {
+      manager = new McpManager();
+      await manager.connect(mockServer("git", { MOCK_TOOLS: "status" }));
+
+      await expect(manager.callTool("status", {})).rejects.toThrow("Invalid tool name format: status");
+    });
+  });
+
+  describe("maxDescriptionLength", () => {
+    it("should truncate only descriptions past the limit", async () => {
+      manager = new McpManager({ maxDescriptionLength: 12 });
+      await manager.connect(mockServer("fs", { MOCK_TOOLS: "read,readme" }));
+
+      expect(manager.listAllTools().map((t) => t.description)).toEqual(["[fs] fs read", "[fs] fs rea…"]);
+    });
+
+    it("should prefer the server's own limit", async () => {
+      manager = new McpManager({ maxDescriptionLength: 12 });
+      await manager.connect({ ...mockServer("fs", { MOCK_TOOLS: "read" }), maxDescriptionLength: 8 });
+
+      expect(manager.listAllTools()[0].description).toBe("[fs] fs…");
+    });
+  });
+
+  describe("toolDescriptions", () => {
+    it("should replace the descriptions of listed tools only", async () => {
+      manager = new McpManager();
+      await manager.connect({
+        ...mockServer("fs", { MOCK_TOOLS: "read,write" }),
+        toolDescriptions: { read: "Read a UTF-8 file by absolute path" },
+      });
+
+      expect(manager.listAllTools().map((t) => t.description)).toEqual([
+        "[fs] Read a UTF-8 file by absolute path",
+        "[fs] fs write",
+      ]);
+    });
+  });
+
+  describe("schema transforms", () => {
+    it("should run registered transforms on every exposed tool", async () => {
+      manager = new McpManager();
+      manager.addSchemaTransform((tool) => ({
+        ...tool,
+        inputSchema: { ...tool.inputSchema, properties: { requestId: { type: "string" } } },
+      }));
+      await manager.connect(mockServer("fs", { MOCK_TOOLS: "read" }));
+
+      expect(manager.listAllTools()).toEqual([
+        {
+          name: "fs__read",
+          description: "[fs] fs read",
+          inputSchema: { properties: { requestId: { type: "string" } } },
+        },
+      ]);
+      expect(manager.toolsByServer().fs[0].inputSchema).toEqual({});
+    });
+
+    it("should add provenance with schemaProvenance", async () => {
+      manager = new McpManager({ schemaProvenance: true });
+      await manager.connect(mockServer("fs", { MOCK_TOOLS: "read" }));
+
+      expect(manager.listAllTools()[0].inputSchema).toEqual({ "x-mcp-server": "fs" });
+    });
+  });
+
+  describe("connection stats", () => {
+    it("should count connects, failures, disconnects and reconnects", async () => {
+      manager = new McpManager();
+      await manager.connect(mockServer("fs"));
+      await manager.connect(mockServer("git"));
+      await expect(manager.connect({ ...mockServer("broken"), command: "/nonexistent/mcp" })).rejects.toThrow();
+      await manager.reconnect("fs");
+      await manager.disconnect("git");
+
+      expect(manager.getConnectionStats()).toEqual({
+        connectsTotal: 3,
+        connectFailuresTotal: 1,
+        disconnectsTotal: 2,
+        reconnectsTotal: 1,
+        connectedServers: 1,
+      });
+    });
+
+    it("should warn when a server keeps reconnecting", async () => {
+      manager = new McpManager();
+      await manager.connect(mockServer("fs"));
+      const warn = vi.spyOn(console, "error");
+
+      for (let i = 0; i < 6; i++) await manager.reconnect("fs");
+
+      expect(warn).toHaveBeenCalledWith("WARNING: fs has reconnected 6 times in the last 10 minutes");
+      warn.mockRestore();
+    });
+  });
+
+  describe("readyProbe", () => {
+    it("should not expose tools until the probe call succeeds", async () => {
+      manager = new McpManager();
+      const connecting = manager.connect({
+        ...mockServer("fs", { MOCK_FAIL_CALLS: "3" }),
+        readyProbe: { tool: "echo", intervalMs: 200 },
+      });
+
+      await new Promise((resolve) => setTimeout(resolve, 300));
+      expect(manager.listAllTools()).toEqual([]);
+
+      await connecting;
+      expect(manager.listAllTools().map((t) => t.name)).toEqual(["fs__echo"]);
+    });
+
+    it("should fail the connect when the probe keeps failing", async () => {
+      manager = new McpManager();
+
+      await expect(
+        manager.connect({
+          ...mockServer("fs", { MOCK_FAIL_CALLS: "100" }),
+          readyProbe: { tool: "echo", timeoutSecs: 0.3, intervalMs: 50 },
+        })
+      ).rejects.toThrow("fs not ready after 0.3s: readyProbe echo tools/call failed: echo is failing");
+      expect(manager.getConnectedMcps()).toEqual([]);
+    });
+
+    it("should give up on a probe call that outlasts the deadline", async () => {
+      manager = new McpManager();
+      const startedAt = Date.now();
+
+      await expect(
+        manager.connect({
+          ...mockServer("fs"),
+          readyProbe: { tool: "echo", arguments: { delay: 5000 }, timeoutSecs: 0.3 },
+        })
+      ).rejects.toThrow("fs not ready after 0.3s: readyProbe echo Request timeout: tools/call");
+      expect(Date.now() - startedAt).toBeLessThan(2000);
+    });
+  });
+
+  describe("servers sharing a command", () => {
+    it("should run each under its own namespace with its own process", async () => {
+      const dirs = ["a", "b"].map((suffix) => fs.mkdtempSync(path.join(os.tmpdir(), `mcp-central-${suffix}-`)));
+      try {
+        manager = new McpManager();
+        await manager.connect({ ...mockServer("docs", { MOCK_TOOLS: "cwd" }), cwd: dirs[0] });
+        await manager.connect({ ...mockServer("notes", { MOCK_TOOLS: "cwd" }), cwd: dirs[1] });
+
+        expect(manager.listAllTools().map((t) => t.name)).toEqual(["docs__cwd", "notes__cwd"]);
+        const cwd = async (tool: string) => {
+          const result = await manager!.callTool(tool, {});
+          return fs.realpathSync((result.content[0] as { text: string }).text);
+        };
+        expect(await cwd("docs__cwd")).toBe(fs.realpathSync(dirs[0]));
+        expect(await cwd("notes__cwd")).toBe(fs.realpathSync(dirs[1]));
+      } finally {
+        for (const dir of dirs) fs.rmSync(dir, { recursive: true, force: true });
+      }
+    });
+  });
+
+  describe("toolsByServer", () => {
+    it("should group backend tool names by server", async () => {
+      manager = new McpManager();
+      await manager.connect(mockServer("fs", { MOCK_TOOLS: "read,write" }));
+      await manager.connect(mockServer("git", { MOCK_TOOLS: "status" }));
+
+      const byServer = manager.toolsByServer();
+
+      expect(Object.keys(byServer)).toEqual(["fs", "git"]);
+      expect(byServer.fs.map((t) => t.name)).toEqual(["read", "write"]);
+      expect(byServer.git.map((t) => t.name)).toEqual(["status"]);
+    });
+  });
+
+  describe("lazy servers", () => {
+    useTempConfigDir("mcp-central-lazy-");
+    // Runs before the dir is removed, or the manager's final stats flush would recreate it
+    afterEach(async () => {
+      await manager?.shutdownAll();
+      manager = null;
+    });
+
+    const configWith = (server: McpServerConfig): Config => ({
+      settings: { logLevel: "info", daemonPort: 3000 },
+      servers: [server],
+    });
+
+    it("should not start a lazy server until one of its tools is called", async () => {
+      const server = { ...mockServer("lazy"), lazy: true };
+      new ToolCache().set(server, { tools: [{ name: "echo", description: "cached echo", inputSchema: {} }] });
+      manager = new McpManager();
+
+      await manager.connectAll(configWith(server));
+
+      expect(manager.getConnectedMcps()).toEqual([]);
+      expect(manager.getLazyMcps()).toEqual(["lazy"]);
+      expect(manager.listAllTools().map((t) => t.name)).toEqual(["lazy__echo"]);
+
+      const result = await manager.callTool("lazy__echo", { label: "woke up" });
+
+      expect(result.content).toEqual([{ type: "text", text: "woke up" }]);
+      expect(manager.getConnectedMcps()).toEqual(["lazy"]);
+      expect(manager.getLazyMcps()).toEqual([]);
+    });
+
+    it("should start a lazy server up front and cache its tools when nothing is cached", async () => {
+      const server = { ...mockServer("lazy"), lazy: true };
+      manager = new McpManager();
+
+      await manager.connectAll(configWith(server));
+
+      expect(manager.getConnectedMcps()).toEqual(["lazy"]);
+      expect(new ToolCache().get(server)?.tools.map((t) => t.name)).toEqual(["echo"]);
+    });
+
+    it("should ignore cached tools once the command changes", () => {
+      const server = { ...mockServer("lazy"), lazy: true };
+      new ToolCache().set(server, { tools: [{ name: "echo", inputSchema: {} }] });
+
+      expect(new ToolCache().get({ ...server, args: ["other.mjs"] })).toBeNull();
+    });
+  });
+
+  describe("schemaCache", () => {
+    useTempConfigDir("mcp-central-schema-");
+    // Runs before the dir is removed, or the manager's final stats flush would recreate it
+    afterEach(async () => {
+      await manager?.shutdownAll();
+      manager = null;
+    });
+
+    it("should list cached tools right away and connect in the background", async () => {
+      const server = mockServer("fs", { MOCK_TOOLS: "read,write" });
+      new ToolCache().set(server, { tools: [{ name: "read", inputSchema: {} }], capabilities: { tools: {} } });
+      manager = new McpManager({ schemaCache: true });
+      const changed = new Promise((resolve) => manager!.onNotification(resolve));
+
+      await manager.connectAll({ settings: { logLevel: "info", daemonPort: 3000 }, servers: [server] });
+
+      expect(manager.listAllTools().map((t) => t.name)).toEqual(["fs__read"]);
+      expect(manager.getCapabilities().tools).toBeDefined();
+
+      await changed;
+      expect(manager.getConnectedMcps()).toEqual(["fs"]);
+      expect(manager.listAllTools().map((t) => t.name)).toEqual(["fs__read", "fs__write"]);
+      expect(new ToolCache().get(server)?.tools.map((t) => t.name)).toEqual(["read", "write"]);
+    });
+
+    it("should refresh the cache when a backend reports its tools changed", async () => {
+      const server = mockServer("fs", { MOCK_TOOLS: "tools_changed" });
+      manager = new McpManager({ schemaCache: true });
+      await manager.connect(server);
+      new ToolCache().set(server, { tools: [] });
+
+      const changed = new Promise((resolve) => manager!.onNotification(resolve));
+      await manager.callTool("fs__tools_changed", {});
+      await changed;
+
+      expect(new ToolCache().get(server)?.tools.map((t) => t.name)).toEqual(["tools_changed"]);
+    });
+  });
+
+  describe("callTool", () => {
+    it("should pass content annotations through unchanged", async () => {
+      manager = new McpManager();
+      await manager.connect(mockServer());
+      const annotations = { audience: ["user"], priority: 0.8 };
+
+      const result = await manager.callTool("mock__echo", { label: "hi", annotations });
+
+      expect(result.content).toEqual([{ type: "text", text: "hi", annotations }]);
+    });
+  });
+
+  describe("namespaceErrorText", () => {
+    it("should rewrite the backend tool name in error results when enabled", async () => {
+      manager = new McpManager({ namespaceErrorText: true });
+      await manager.connect(mockServer("fs", { MOCK_TOOLS: "fail" }));
+
+      const result = await manager.callTool("fs__fail", { label: "fail failed: call fail with an absolute path" });
+
+      expect(result.content).toEqual([
+        { type: "text", text: "fs__fail failed: call fs__fail with an absolute path" },
+      ]);
+    });
+
+    it("should leave error text alone by default", async () => {
+      manager = new McpManager();
+      await manager.connect(mockServer("fs", { MOCK_TOOLS: "fail" }));
+
+      const result = await manager.callTool("fs__fail", { label: "fail failed" });
+
+      expect(result).toEqual({ content: [{ type: "text", text: "fail failed" }], isError: true });
+    });
+  });
+
+  describe("reconnect", () => {
+    it("should restart one server and return its tool count", async () => {
+      manager = new McpManager();
+      await manager.connect(mockServer("a"));
+      await manager.connect(mockServer("b", { MOCK_TOOLS: "one,two" }));
+
+      expect(await manager.reconnect("b")).toBe(2);
+      expect(manager.getConnectedMcps().sort()).toEqual(["a", "b"]);
+    });
+
+    it("should reject servers it has never seen", async () => {
+      manager = new McpManager();
+
+      await expect(manager.reconnect("missing")).rejects.toThrow("not found");
+    });
+  });
+
+  describe("healthCheck", () => {
+    it("should report a responsive server as healthy", async () => {
+      manager = new McpManager();
+      await manager.connect(mockServer());
+
+      expect(await manager.healthCheck("mock")).toMatchObject({ status: "healthy" });
+    });
+
+    it("should report a running server that stopped answering as unresponsive", async () => {
+      manager = new McpManager();
+      await manager.connect(mockServer("mock", { MOCK_TOOLS: "hang" }));
+      await manager.callTool("mock__hang", {});
+
+      expect(await manager.healthCheck("mock", 100)).toEqual({
+        status: "unresponsive",
+        error: "Request timeout: ping",
+        tools: 1,
+        uptimeSecs: 0,
+        restarts: 0,
+      });
+    });
+
+    it("should report a disconnected server as dead", async () => {
+      manager = new McpManager();
+      await manager.connect(mockServer());
+      await manager.disconnect("mock");
+
+      expect(await manager.healthCheck("mock")).toEqual({ status: "dead", error: "Not connected", restarts: 0 });
+      await expect(manager.healthCheck("missing")).rejects.toThrow("not found");
+    });
+
+    it("should report why a dead server's process ended", async () => {
+      manager = new McpManager();
+      await manager.connect(mockServer("mock", { MOCK_TOOLS: "exit" }));
+      await manager.callTool("mock__exit", { code: 2 }).catch(() => {});
+
+      expect(await manager.healthCheck("mock")).toEqual({
+        status: "dead",
+        error: "Process exited with code 2",
+        restarts: 0,
+        lastError: expect.stringContaining("exited with code 2"),
+        lastErrorAt: expect.any(String),
+      });
+    });
+
+    it("should keep the error a server last failed with", async () => {
+      manager = new McpManager();
+      await expect(manager.connect({ ...mockServer(), command: "mcp-central-no-such-command" })).rejects.toThrow();
+
+      const health = await manager.healthCheck("mock");
+
+      expect(health).toMatchObject({
+        status: "dead",
+        error: "Not connected",
+        lastError: expect.stringContaining("command not found: mcp-central-no-such-command"),
+      });
+      expect(Date.parse(health.lastErrorAt!)).toBeGreaterThan(Date.now() - 5000);
+    });
+
+    it("should count restarts and report uptime since the last one", async () => {
+      manager = new McpManager();
+      await manager.connect(mockServer());
+      await manager.reconnect("mock");
+      await manager.reconnect("mock");
+
+      expect(await manager.healthCheck("mock")).toMatchObject({ status: "healthy", uptimeSecs: 0, restarts: 2 });
+    });
+
+    it("should restart unresponsive servers every healthCheckSecs", async () => {
+      manager = new McpManager({ healthCheckSecs: 0.2 });
+      await manager.connectAll({
+        settings: { logLevel: "info", daemonPort: 3000 },
+        servers: [mockServer("mock", { MOCK_TOOLS: "hang" })],
+      });
+      await manager.callTool("mock__hang", {});
+      expect((await manager.healthCheck("mock", 100)).status).toBe("unresponsive");
+
+      await vi.waitFor(async () => expect((await manager!.healthCheck("mock", 100)).status).toBe("healthy"), {
+        timeout: 3000,
+        interval: 100,
+      });
+    });
+  });
+
+  describe("drain", () => {
+    it("should let a running call finish on the old process before swapping in an edited config", async () => {
+      const server = mockServer("mock", { MOCK_TOOLS: "echo" });
+      manager = new McpManager();
+      await manager.connect(server);
+
+      const running = manager.callTool("mock__echo", { label: "old", delay: 300 });
+      await new Promise((resolve) => setTimeout(resolve, 50));
+      const swapped = manager.reconnect("mock", { ...server, env: { ...server.env, MOCK_TOOLS: "echo,extra" } });
+      // Only the new process has this tool, so it must wait for the swap
+      const held = manager.callTool("mock__extra", { label: "new" });
+
+      expect((await running).content).toEqual([{ type: "text", text: "old" }]);
+      await swapped;
+      expect((await held).content).toEqual([{ type: "text", text: "new" }]);
+    });
+
+    it("should stop the old process after drainTimeoutMs", async () => {
+      const server = mockServer("mock", { MOCK_TOOLS: "echo" });
+      manager = new McpManager({ drainTimeoutMs: 100 });
+      await manager.connect(server);
+
+      const running = manager.callTool("mock__echo", { label: "old", delay: 2000 });
+      await new Promise((resolve) => setTimeout(resolve, 50));
+      await manager.reconnect("mock", { ...server, env: { ...server.env, MOCK_NAME: "renamed" } });
+
+      await expect(running).rejects.toThrow();
+    });
+  });
+
+  describe("reload", () => {
+    const settings = { logLevel: "info", daemonPort: 3000 };
+
+    it("should let calls in progress survive a reload", async () => {
+      const kept = mockServer("kept");
+      const edited = mockServer("edited");
+      manager = new McpManager();
+      await manager.connectAll({ settings, servers: [kept, edited, mockServer("dropped")] });
+
+      const onKept = manager.callTool("kept__echo", { label: "kept", delay: 300 });
+      const onEdited = manager.callTool("edited__echo", { label: "edited", delay: 300 });
+      await new Promise((resolve) => setTimeout(resolve, 50));
+
+      const result = await manager.reload({
+        settings,
+        servers: [kept, { ...edited, env: { ...edited.env, MOCK_TOOLS: "echo,extra" } }, mockServer("added")],
+      });
+
+      expect(result).toEqual({ added: ["added"], removed: ["dropped"], changed: ["edited"] });
+      expect((await onKept).content).toEqual([{ type: "text", text: "kept" }]);
+      expect((await onEdited).content).toEqual([{ type: "text", text: "edited" }]);
+      expect(manager.getConnectedMcps().sort()).toEqual(["added", "edited", "kept"]);
+      expect(manager.listAllTools().map((t) => t.name)).toContain("edited__extra");
+    });
+
+    it("should not restart servers whose config only changed key order", async () => {
+      const server = mockServer("mock");
+      manager = new McpManager();
+      await manager.connectAll({ settings, servers: [server] });
+      const reordered = Object.fromEntries(Object.entries(server).reverse()) as unknown as McpServerConfig;
+
+      expect(await manager.reload({ settings, servers: [reordered] })).toEqual({ added: [], removed: [], changed: [] });
+      expect((await manager.healthCheck("mock")).restarts).toBe(0);
+    });
+  });
+
+  describe("reconnect backoff", () => {
+    it("should double the delay per failure up to the ceiling", () => {
+      const settings = { reconnectInitialMs: 100, reconnectMaxMs: 1000, reconnectJitter: 0 };
+
+      expect([1, 2, 3, 4, 5, 6].map((failures) => reconnectDelayMs(failures, settings))).toEqual([
+        100, 200, 400, 800, 1000, 1000,
+      ]);
+    });
+
+    it("should only ever shorten the delay with jitter", () => {
+      const settings = { reconnectInitialMs: 100, reconnectMaxMs: 1000, reconnectJitter: 0.5 };
+
+      expect(reconnectDelayMs(10, settings, () => 0)).toBe(1000);
+      expect(reconnectDelayMs(10, settings, () => 1)).toBe(500);
+    });
+
+    it("should stop restarting after reconnectMaxAttempts failures", async () => {
+      manager = new McpManager({
+        healthCheckSecs: 0.1,
+        initTimeoutSecs: 0.1,
+        reconnectInitialMs: 10,
+        reconnectMaxAttempts: 2,
+      });
+      await manager.connectAll({
+        settings: { logLevel: "info", daemonPort: 3000 },
+        servers: [mockServer("mock", { MOCK_SILENT_INIT: "1" })],
+      });
+
+      await vi.waitFor(
+        async () => expect((await manager!.healthCheck("mock")).backoff).toEqual({ failures: 2, gaveUp: true }),
+        { timeout: 3000, interval: 100 }
+      );
+      const restarts = (await manager.healthCheck("mock")).restarts;
+      await new Promise((resolve) => setTimeout(resolve, 300));
+      expect((await manager.healthCheck("mock")).restarts).toBe(restarts);
+    });
+  });
+
+  describe("groups", () => {
+    it("should expose one merged tool set per group", async () => {
+      manager = new McpManager();
+      await manager.connect({ ...mockServer("fs1"), group: "fs" });
+      await manager.connect({ ...mockServer("fs2"), group: "fs" });
+
+      expect(manager.listAllTools().map((t) => t.name)).toEqual(["fs__echo"]);
+      expect(manager.findDuplicateTools().size).toBe(0);
+    });
+
+    it("should fail over to another member when the first fails", async () => {
+      manager = new McpManager();
+      await manager.connect({ ...mockServer("primary", { MOCK_FAIL_CALLS: "1" }), group: "fs" });
+      await manager.connect({ ...mockServer("secondary"), group: "fs" });
+
+      const result = await manager.callTool("fs__echo", { label: "ok" });
+
+      expect(result.content).toEqual([{ type: "text", text: "ok" }]);
+    });
+
+    it("should spread calls across members with round_robin", async () => {
+      manager = new McpManager({ groups: { fs: { loadBalance: "round_robin" } } });
+      await manager.connect({ ...mockServer("fs1"), group: "fs" });
+      await manager.connect({ ...mockServer("fs2"), group: "fs" });
+
+      for (let i = 0; i < 4; i++) {
+        await manager.callTool("fs__echo", {});
+      }
+
+      const counts = manager.getCallCounts();
+      expect(counts.fs1.calls).toBe(2);
+      expect(counts.fs2.calls).toBe(2);
+    });
+
+    it("should always use the first member with the default failover strategy", async () => {
+      manager = new McpManager();
+      await manager.connect({ ...mockServer("fs1"), group: "fs" });
+      await manager.connect({ ...mockServer("fs2"), group: "fs" });
+
+      for (let i = 0; i < 3; i++) {
+        await manager.callTool("fs__echo", {});
+      }
+
+      expect(manager.getCallCounts()).toEqual({
+        fs1: { calls: 3, inFlight: 0 },
+        fs2: { calls: 0, inFlight: 0 },
+      });
+    });
+
+    it("should throw the last error when every member fails", async () => {
+      manager = new McpManager();
+      await manager.connect({ ...mockServer("primary", { MOCK_FAIL_CALLS: "1" }), group: "fs" });
+      await manager.connect({ ...mockServer("secondary", { MOCK_FAIL_CALLS: "1" }), group: "fs" });
+
+      await expect(manager.callTool("fs__echo", {})).rejects.toThrow("secondary is failing");
+    });
+  });
+
+  describe("maxExposedTools", () => {
+    it("should keep the tools of higher-priority servers", async () => {
+      manager = new McpManager({ maxExposedTools: 3 });
+      const warn = vi.spyOn(console, "error");
+
+      await manager.connectAll({
+        settings: { logLevel: "info", daemonPort: 3000 },
+        servers: [
+          mockServer("low", { MOCK_TOOLS: "a,b" }),
+          { ...mockServer("high", { MOCK_TOOLS: "c,d" }), priority: 10 },
+          { ...mockServer("mid", { MOCK_TOOLS: "e" }), priority: 5 },
+        ],
+      });
+
+      expect(manager.listAllTools().map((t) => t.name)).toEqual(["high__c", "high__d", "mid__e"]);
+      expect(warn).toHaveBeenCalledWith(expect.stringContaining("not exposing 2 tools: low__a, low__b"));
+      warn.mockRestore();
+    });
+  });
+
+  describe("cacheableTools", () => {
+    it("should share one backend call between concurrent identical calls", async () => {
+      manager = new McpManager();
+      await manager.connect({ ...mockServer("fs", { MOCK_TOOLS: "count" }), cacheableTools: ["count"] });
+
+      const results = await Promise.all(
+        Array.from({ length: 5 }, () => manager!.callTool("fs__count", { delay: 100 }))
+      );
+      const texts = results.map((r) => (r.content[0] as { text: string }).text);
+
+      expect(texts).toEqual(["1", "1", "1", "1", "1"]);
+      // Done calls aren't reused, and other arguments get their own call
+      const [next, other] = await Promise.all([
+        manager.callTool("fs__count", { delay: 100 }),
+        manager.callTool("fs__count", { delay: 50 }),
+      ]);
+      expect([next.content[0], other.content[0]]).toEqual([
+        { type: "text", text: "2" },
+        { type: "text", text: "3" },
+      ]);
+    });
+
+    it("should share calls whose arguments differ only in key order", async () => {
+      manager = new McpManager();
+      await manager.connect({ ...mockServer("fs", { MOCK_TOOLS: "count" }), cacheableTools: ["count"] });
+
+      const results = await Promise.all([
+        manager.callTool("fs__count", { delay: 100, filter: { a: 1, b: 2 } }),
+        manager.callTool("fs__count", { filter: { b: 2, a: 1 }, delay: 100 }),
+      ]);
+
+      expect(results.map((r) => (r.content[0] as { text: string }).text)).toEqual(["1", "1"]);
+    });
+
+    it("should let one caller give up without cancelling the shared call for the others", async () => {
+      manager = new McpManager();
+      await manager.connect({ ...mockServer("fs", { MOCK_TOOLS: "count" }), cacheableTools: ["count"] });
+      const controller = new AbortController();
+
+      const abandoned = manager.callTool("fs__count", { delay: 100 }, controller.signal);
+      const waiting = manager.callTool("fs__count", { delay: 100 });
+      controller.abort();
+
+      await expect(abandoned).rejects.toThrow("Request cancelled");
+      expect((await waiting).content).toEqual([{ type: "text", text: "1" }]);
+    });
+
+    it("should not share calls to a tool the server doesn't list as cacheable", async () => {
+      manager = new McpManager();
+      await manager.connect(mockServer("fs", { MOCK_TOOLS: "count" }));
+
+      const results = await Promise.all([
+        manager.callTool("fs__count", { delay: 50 }),
+        manager.callTool("fs__count", { delay: 50 }),
+      ]);
+
+      expect(results.map((r) => (r.content[0] as { text: string }).text).sort()).toEqual(["1", "2"]);
+    });
+  });
+
+  describe("callRetries", () => {
+    it("should retry a failed call on the same server with callRetries", async () => {
+      manager = new McpManager();
+      await manager.connect({ ...mockServer("fs", { MOCK_FAIL_CALLS: "1" }), callRetries: 1, callRetryBackoffMs: 10 });
+
+      const result = await manager.callTool("fs__echo", { label: "ok" });
+
+      expect(result.content).toEqual([{ type: "text", text: "ok" }]);
+      expect(manager.getCallCounts().fs.calls).toBe(2);
+    });
+
+    it("should not retry error codes outside retryErrorCodes", async () => {
+      manager = new McpManager();
+      await manager.connect({
+        ...mockServer("fs", { MOCK_FAIL_CALLS: "1" }),
+        callRetries: 3,
+        callRetryBackoffMs: 10,
+        retryErrorCodes: [-32000],
+      });
+
+      await expect(manager.callTool("fs__echo", {})).rejects.toMatchObject({ code: ErrorCodes.INTERNAL_ERROR });
+      expect(manager.getCallCounts().fs.calls).toBe(1);
+    });
+
+    it("should only retry timed-out calls with retryTimeouts", async () => {
+      manager = new McpManager({ statsEnabled: false });
+      await manager.connect({ ...mockServer("fs"), timeoutSecs: 0.1, callRetries: 1, callRetryBackoffMs: 10 });
+
+      await expect(manager.callTool("fs__echo", { delay: 500 })).rejects.toThrow("Request timeout: tools/call");
+      expect(manager.getCallCounts().fs.calls).toBe(1);
+
+      await manager.connect({
+        ...mockServer("gh"),
+        timeoutSecs: 0.1,
+        callRetries: 1,
+        callRetryBackoffMs: 10,
+        retryTimeouts: true,
+      });
+
+      await expect(manager.callTool("gh__echo", { delay: 500 })).rejects.toThrow("Request timeout: tools/call");
+      expect(manager.getCallCounts().gh.calls).toBe(2);
+    });
+  });
+
+  describe("findDuplicateTools", () => {
+    it("should report tools exposed under the same name", async () => {
+      manager = new McpManager();
+      await manager.connect(mockServer("a", { MOCK_TOOLS: "echo,echo,other" }));
+      await manager.connect(mockServer("b", { MOCK_TOOLS: "echo" }));
+
+      expect(manager.findDuplicateTools()).toEqual(new Map([["a__echo", ["a/echo", "a/echo"]]]));
+    });
+
+    it("should fail connectAll in strict mode", async () => {
+      manager = new McpManager({ strictToolNames: true });
+      const config = {
+        settings: { logLevel: "info", daemonPort: 3000 },
+        servers: [mockServer("a", { MOCK_TOOLS: "echo,echo" })],
+      };
+
+      await expect(manager.connectAll(config)).rejects.toThrow("Duplicate tool names");
+    });
+
+    it("should refuse a reconnect that brings duplicate tools in strict mode", async () => {
+      manager = new McpManager({ strictToolNames: true });
+      await manager.connectAll({ settings: { logLevel: "info", daemonPort: 3000 }, servers: [mockServer("a")] });
+
+      await expect(manager.reconnect("a", mockServer("a", { MOCK_TOOLS: "echo,echo" }))).rejects.toThrow(
+        "Duplicate tool names"
+      );
+      expect(manager.getConnectedMcps()).toEqual([]);
+    });
+  });
+
+  describe("resources", () => {
+    it("should namespace resource URIs when listing and reading", async () => {
+      manager = new McpManager();
+      await manager.connect(mockServer("fs", { MOCK_RESOURCES: "file:///a.txt" }));
+      await manager.connect(mockServer("plain"));
+
+      const resources = await manager.listAllResources();
+      expect(resources).toEqual([{ uri: "fs__file:///a.txt", name: "file:///a.txt" }]);
+
+      const read = await manager.readResource("fs__file:///a.txt");
+      expect(read.contents).toEqual([{ uri: "fs__file:///a.txt", text: "contents of file:///a.txt" }]);
+    });
+
+    it("should relay resources/list_changed and list the backend's new resources", async () => {
+      manager = new McpManager();
+      await manager.connect(mockServer("fs", { MOCK_RESOURCES: "file:///a.txt", MOCK_TOOLS: "resources_changed" }));
+      expect(manager.getCapabilities().resources).toEqual({ subscribe: true, listChanged: true });
+
+      const changed = new Promise((resolve) => manager!.onNotification(resolve));
+      await manager.callTool("fs__resources_changed", { uri: "file:///b.txt" });
+
+      expect(await changed).toEqual({ jsonrpc: "2.0", method: "notifications/resources/list_changed" });
+      expect((await manager.listAllResources()).map((r) => r.uri)).toEqual(["fs__file:///a.txt", "fs__file:///b.txt"]);
+    });
+
+    it("should fan out resource template listing to servers with resources", async () => {
+      manager = new McpManager();
+      await manager.connect(mockServer("fs", { MOCK_TEMPLATES: "file:///{path}" }));
+      await manager.connect(mockServer("db", { MOCK_TEMPLATES: "db://{table}/{id}" }));
+      await manager.connect(mockServer("plain"));
+
+      const templates = await manager.listAllResourceTemplates();
+
+      expect(templates).toEqual([
+        { uriTemplate: "fs__file:///{path}", name: "file:///{path}" },
+        { uriTemplate: "db__db://{table}/{id}", name: "db://{table}/{id}" },
+      ]);
+    });
+
+    it("should relay progress for a list request carrying a progressToken", async () => {
+      manager = new McpManager();
+      await manager.connect(mockServer("fs", { MOCK_RESOURCES: "file:///a.txt" }));
+      const notifications: unknown[] = [];
+      manager.onNotification((n) => notifications.push(n));
+
+      await manager.listAllResources({ progressToken: "list-1" });
+      await manager.listAllResources();
+
+      expect(notifications).toEqual([
+        {
+          jsonrpc: "2.0",
+          method: "notifications/progress",
+          params: { progressToken: "list-1", progress: 1, total: 2 },
+        },
+        {
+          jsonrpc: "2.0",
+          method: "notifications/progress",
+          params: { progressToken: "list-1", progress: 2, total: 2 },
+        },
+      ]);
+    });
+
+    it("should relay progress only to the client that asked, under its own token", async () => {
+      manager = new McpManager();
+      await manager.connect(mockServer("fs", { MOCK_RESOURCES: "file:///a.txt" }));
+      const first: unknown[] = [];
+      const second: unknown[] = [];
+      const firstChannel = (n: unknown) => first.push(n);
+      const secondChannel = (n: unknown) => second.push(n);
+      manager.onNotification(firstChannel);
+      manager.onNotification(secondChannel);
+
+      // Both clients picked the same token
+      await Promise.all([
+        manager.listAllResources({ progressToken: 1 }, firstChannel),
+        manager.listAllResources({ progressToken: 1 }, secondChannel),
+      ]);
+
+      const progress = [
+        { jsonrpc: "2.0", method: "notifications/progress", params: { progressToken: 1, progress: 1, total: 2 } },
+        { jsonrpc: "2.0", method: "notifications/progress", params: { progressToken: 1, progress: 2, total: 2 } },
+      ];
+      expect(first).toEqual(progress);
+      expect(second).toEqual(progress);
+    });
+
+    it("should relay updates for subscribed resources with namespaced URIs", async () => {
+      manager = new McpManager();
+      await manager.connect(mockServer("fs", { MOCK_RESOURCES: "file:///a.txt" }));
+
+      const updated = new Promise((resolve) => manager!.onNotification(resolve));
+      await manager.subscribeResource("fs__file:///a.txt");
+
+      expect(await updated).toEqual({
+        jsonrpc: "2.0",
+        method: "notifications/resources/updated",
+        params: { uri: "fs__file:///a.txt" },
+      });
+    });
+
+    it("should send updates only to the client that subscribed", async () => {
+      manager = new McpManager();
+      await manager.connect(mockServer("fs", { MOCK_RESOURCES: "file:///a.txt" }));
+      const other: unknown[] = [];
+      manager.onNotification((n) => other.push(n));
+
+      const updated = new Promise((resolve) => {
+        const channel = (n: unknown) => resolve(n);
+        manager!.onNotification(channel);
+        void manager!.subscribeResource("fs__file:///a.txt", channel);
+      });
+
+      expect(await updated).toMatchObject({ method: "notifications/resources/updated" });
+      expect(other).toEqual([]);
+    });
+
+    it("should keep the backend subscribed until the last client unsubscribes", async () => {
+      manager = new McpManager();
+      await manager.connect(mockServer("fs", { MOCK_RESOURCES: "file:///a.txt" }));
+      const first = vi.fn();
+      const second = vi.fn();
+      manager.onNotification(first);
+      manager.onNotification(second);
+
+      await manager.subscribeResource("fs__file:///a.txt", first);
+      await vi.waitFor(() => expect(first).toHaveBeenCalledTimes(1));
+      // The backend answers each subscribe with an update, so a second one would reach both
+      await manager.subscribeResource("fs__file:///a.txt", second);
+      await new Promise((resolve) => setTimeout(resolve, 50));
+      expect(first).toHaveBeenCalledTimes(1);
+      expect(second).not.toHaveBeenCalled();
+
+      const unsubscribe = vi.spyOn(StdioClient.prototype, "unsubscribeResource");
+      await manager.unsubscribeResource("fs__file:///a.txt", first);
+      expect(unsubscribe).not.toHaveBeenCalled();
+      await manager.unsubscribeResource("fs__file:///a.txt", second);
+      expect(unsubscribe).toHaveBeenCalledWith("file:///a.txt");
+      unsubscribe.mockRestore();
+    });
+  });
+
+  describe("clientInfo", () => {
+    it("should send the configured client name and version in initialize", async () => {
+      manager = new McpManager({ clientName: "my-host", clientVersion: "2.0.0" });
+      await manager.connect(mockServer("mock", { MOCK_TOOLS: "client_info" }));
+
+      const result = await manager.callTool("mock__client_info", {});
+
+      expect(result.content).toEqual([{ type: "text", text: JSON.stringify({ name: "my-host", version: "2.0.0" }) }]);
+    });
+
+    it("should pass through the upstream client's clientInfo when enabled", async () => {
+      manager = new McpManager({ clientName: "my-host", forwardClientInfo: true });
+      manager.setClientInfo({ name: "claude-desktop", version: "1.2.3" });
+      await manager.connect(mockServer("mock", { MOCK_TOOLS: "client_info" }));
+
+      const result = await manager.callTool("mock__client_info", {});
+
+      expect(result.content).toEqual([
+        { type: "text", text: JSON.stringify({ name: "claude-desktop", version: "1.2.3" }) },
+      ]);
+    });
+  });
+
+  describe("roots", () => {
+    it("should answer backend roots/list by asking the upstream client", async () => {
+      manager = new McpManager({ forwardRoots: true });
+      manager.setUpstream(async (method) => ({
+        jsonrpc: "2.0",
+        id: "up-1",
+        result: method === "roots/list" ? { roots: [{ uri: "file:///project" }] } : {},
+      }));
+      manager.setClientCapabilities({ roots: { listChanged: true } });
+      await manager.connect(mockServer("fs", { MOCK_TOOLS: "roots" }));
+
+      const result = await manager.callTool("fs__roots", {});
+
+      expect(result.content).toEqual([{ type: "text", text: JSON.stringify({ roots: [{ uri: "file:///project" }] }) }]);
+    });
+
+    it("should return no roots when the upstream client doesn't support them", async () => {
+      manager = new McpManager({ forwardRoots: true });
+      await manager.connect(mockServer("fs", { MOCK_TOOLS: "roots" }));
+
+      const result = await manager.callTool("fs__roots", {});
+
+      expect(result.content).toEqual([{ type: "text", text: JSON.stringify({ roots: [] }) }]);
+    });
+  });
+
+  describe("complete", () => {
+    it("should reject a ref without a namespace", async () => {
+      manager = new McpManager();
+      await expect(
+        manager.complete({ ref: { type: "ref/prompt", name: "review" }, argument: { name: "a", value: "" } })
+      ).rejects.toMatchObject({ code: ErrorCodes.INVALID_PARAMS });
+    });
+
+    it("should reject an unknown server", async () => {
+      manager = new McpManager();
+      await expect(
+        manager.complete({ ref: { type: "ref/resource", uri: "nope__file:///x" }, argument: { name: "a", value: "" } })
+      ).rejects.toMatchObject({ code: ErrorCodes.METHOD_NOT_FOUND });
+    });
+  });
+
+  describe("callTool", () => {
+    it("should reject a malformed tool name with invalid params", async () => {
+      manager = new McpManager();
+      await expect(manager.callTool("no_separator", {})).rejects.toMatchObject({
+        code: ErrorCodes.INVALID_PARAMS,
+      });
+    });
+
+    it("should reject an unknown server with method not found", async () => {
+      manager = new McpManager();
+      await expect(manager.callTool("missing__tool", {})).rejects.toMatchObject({
+        code: ErrorCodes.METHOD_NOT_FOUND,
+      });
+    });
+  });
+});
@@ -1,106 +1,1246 @@
 import { StdioClient } from "./client.js";
-import type { Config, McpServerConfig, Tool, ToolCallResult } from "./types.js";
-import { namespaceTools, parseNamespacedTool } from "./types.js";
+import type {
+  ClientCapabilities,
+  ClientInfo,
+  CompleteParams,
+  CompletionRef,
+  CompletionResult,
+  Config,
+  ConnectionStats,
+  JsonRpcRequest,
+  JsonRpcResponse,
+  McpServerConfig,
+  ProgressToken,
+  ReadResourceResult,
+  ReadyProbe,
+  RequestMeta,
+  Resource,
+  ResourceParams,
+  ResourceTemplate,
+  ServerCapabilities,
+  ServerHealth,
+  Settings,
+  Tool,
+  ToolCallResult,
+} from "./types.js";
+import { ErrorCodes, KNOWN_PROTOCOL_VERSIONS, McpError, namespaceTools, parseNamespacedTool } from "./types.js";
 import { logToolCall } from "./logger.js";
+import { UsageStatsStore } from "./stats.js";
+import { initTracing, shutdownTracing, SpanKind, withSpan } from "./tracing.js";
+import { closeTranscript, openTranscript } from "./transcript.js";
+import { provenanceTransform, type SchemaTransform } from "./transforms.js";
+import { canonicalJson } from "./util.js";
+import { ToolCache, type CachedSchema } from "./toolcache.js";
+import { getEnabledServers, sortByStartOrder, type ServerFilter } from "./config.js";
+
+const HEALTH_CHECK_TIMEOUT_MS = 5000;
+const DEFAULT_RETRY_BACKOFF_MS = 200;
+const DEFAULT_RETRY_ERROR_CODES: number[] = [ErrorCodes.INTERNAL_ERROR];
+const DEFAULT_READY_TIMEOUT_SECS = 30;
+const DEFAULT_READY_INTERVAL_MS = 500;
+const DEFAULT_DRAIN_TIMEOUT_MS = 30_000;
+const DRAIN_POLL_MS = 50;
+// Warn about a server reconnected more than FLAP_RECONNECTS times within FLAP_WINDOW_MS
+const FLAP_RECONNECTS = 5;
+const FLAP_WINDOW_MS = 10 * 60 * 1000;
+const DEFAULT_RECONNECT_INITIAL_MS = 1000;
+const DEFAULT_RECONNECT_MAX_MS = 60_000;
+const DEFAULT_RECONNECT_JITTER = 0.2;
+
+function isTimeout(e: unknown): boolean {
+  return e instanceof Error && e.message.startsWith("Request timeout");
+}
+
+// Rejects like an aborted request once signal aborts, leaving promise itself running
+function untilAborted<T>(promise: Promise<T>, signal: AbortSignal | undefined, method: string): Promise<T> {
+  if (!signal) return promise;
+
+  return new Promise((resolve, reject) => {
+    const abort = () => reject(new Error(`Request cancelled: ${method}`));
+    if (signal.aborted) return abort();
+    signal.addEventListener("abort", abort, { once: true });
+    promise.then(resolve, reject).finally(() => signal.removeEventListener("abort", abort));
+  });
+}
+
+// Wait before the next restart after `failures` failed ones in a row: exponential up to
+// reconnectMaxMs, then shortened by up to reconnectJitter of itself so it never passes the cap
+export function reconnectDelayMs(
+  failures: number,
+  settings: Partial<Settings>,
+  random: () => number = Math.random
+): number {
+  const initial = settings.reconnectInitialMs ?? DEFAULT_RECONNECT_INITIAL_MS;
+  const max = settings.reconnectMaxMs ?? DEFAULT_RECONNECT_MAX_MS;
+  const jitter = settings.reconnectJitter ?? DEFAULT_RECONNECT_JITTER;
+  const delay = Math.min(initial * 2 ** Math.max(failures - 1, 0), max);
+  return Math.round(delay * (1 - jitter * random()));
+}
+
+// Cuts a description down to maxLength characters, the last being an ellipsis
+function truncateDescription(description: string, maxLength: number | undefined): string {
+  if (maxLength === undefined || description.length <= maxLength) return description;
+  return `${description.slice(0, Math.max(maxLength - 1, 0))}…`;
+}
+
+// Rewrites whole-word mentions of a tool's backend name in error text to its namespaced name
+function namespaceErrorText(result: ToolCallResult, toolName: string, namespacedName: string): ToolCallResult {
+  const escaped = toolName.replace(/[.*+?^${}()|[\]\\]/g, "\\$&");
+  const mention = new RegExp(`(?<!\\w)${escaped}(?!\\w)`, "g");
+
+  return {
+    ...result,
+    content: result.content.map((c) =>
+      c.type === "text" ? { ...c, text: c.text.replace(mention, () => namespacedName) } : c
+    ),
+  };
+}
+
+export type NotificationListener = (notification: JsonRpcRequest) => void;
+// Sends a request to the upstream client (the one connected to the bridge)
+export type UpstreamRequester = (method: string, params?: unknown) => Promise<JsonRpcResponse>;
 
 export class McpManager {
   private clients = new Map<string, StdioClient>();
+  // Config of every server we've tried to connect, so failed ones can be reconnected by name
+  private configs = new Map<string, McpServerConfig>();
+  // Servers not connected yet (lazy, or still starting with schemaCache) exposing what
+  // the tool cache remembers from a previous run
+  private cached = new Map<string, { config: McpServerConfig } & CachedSchema>();
+  private waking = new Map<string, Promise<void>>();
+  // Servers being swapped for a new process; calls for them wait until it's up
+  private swapping = new Map<string, Promise<void>>();
+  private toolCache = new ToolCache();
+  // Namespaced resource URIs upstream clients have subscribed to, with the channels (listeners) of
+  // the clients that did. The backend is subscribed while any of them is; undefined stands for a
+  // client without a channel of its own, whose updates are broadcast.
+  private subscriptions = new Map<string, Set<NotificationListener | undefined>>();
+  // Tokens the bridge sent backends in place of an upstream client's progressToken, for requests
+  // still out, with the client's own token and the channel to relay its progress on
+  private progressTokens = new Map<string, { token: ProgressToken; channel: NotificationListener | undefined }>();
+  private nextProgressToken = 1;
+  private notificationListeners = new Set<NotificationListener>();
+  private upstream: UpstreamRequester | null = null;
+  // Next starting member per group for round_robin
+  private roundRobin = new Map<string, number>();
+  private clientCapabilities: ClientCapabilities = {};
+  private upstreamClientInfo: ClientInfo | null = null;
+  private healthTimer: NodeJS.Timeout | null = null;
+  private checkingHealth = false;
+  // Tools last left out by maxExposedTools, comma-joined
+  private droppedTools = "";
+  private churn = { connects: 0, connectFailures: 0, disconnects: 0, reconnects: 0 };
+  // Recent reconnect times per server, for flap warnings
+  private reconnectTimes = new Map<string, number[]>();
+  private restarts = new Map<string, number>();
+  // Servers whose health check restarts keep failing
+  private backoff = new Map<string, { failures: number; nextAttemptAt: number; gaveUp: boolean }>();
+  // Latest failure per server, for health checks
+  private lastErrors = new Map<string, { message: string; time: number }>();
+  // In-flight calls to cacheableTools, by tool and arguments
+  private sharedCalls = new Map<string, Promise<ToolCallResult>>();
+  private schemaTransforms: SchemaTransform[] = [];
+
+  readonly stats: UsageStatsStore | null = null;
+
+  constructor(private settings: Partial<Settings> = {}) {
+    if (settings.statsEnabled ?? true) {
+      this.stats = new UsageStatsStore();
+      this.stats.start(settings.statsFlushSecs ?? 30);
+    }
+    initTracing(settings);
+    openTranscript(settings);
+    if (settings.schemaProvenance) this.addSchemaTransform(provenanceTransform);
+  }
+
+  // Runs transform on every tool listAllTools exposes, after the ones added before it
+  addSchemaTransform(transform: SchemaTransform): void {
+    this.schemaTransforms.push(transform);
+  }
+
+  async connectAll(config: Config, filter: ServerFilter = {}): Promise<void> {
+    const warming = await this.startServers(sortByStartOrder(getEnabledServers(config, filter)));
+
+    const lazy = this.getLazyMcps();
+    if (lazy.length > 0) {
+      console.error(`Lazy MCP servers (started on first use): ${lazy.join(", ")}`);
+    }
+
+    this.checkDuplicateTools();
+    this.warmUp(warming);
+
+    if (this.settings.healthCheckSecs) {
+      this.startHealthChecks(this.settings.healthCheckSecs);
+    }
+  }
+
+  // Applies an edited config without restarting what didn't change, so calls running on those
+  // servers carry on. Servers taken out (or disabled) are stopped, edited ones are swapped
+  // through reconnect, which lets their calls in flight drain, and new ones are connected.
+  async reload(config: Config): Promise<{ added: string[]; removed: string[]; changed: string[] }> {
+    const wanted = new Map(getEnabledServers(config).map((s) => [s.name, s]));
+    const added: string[] = [];
+    const removed: string[] = [];
+    const changed: string[] = [];
+
+    for (const name of [...this.configs.keys()]) {
+      if (wanted.has(name)) continue;
+      removed.push(name);
+      await this.forget(name);
+    }
+
+    const starting: McpServerConfig[] = [];
+    for (const serverConfig of sortByStartOrder([...wanted.values()])) {
+      const { name } = serverConfig;
+      const current = this.configs.get(name);
+      const running = this.clients.has(name) || this.cached.has(name) || this.waking.has(name);
+
+      if (!current) {
+        added.push(name);
+        starting.push(serverConfig);
+      } else if (canonicalJson(current) !== canonicalJson(serverConfig)) {
+        changed.push(name);
+        if (this.clients.has(name)) {
+          await this.reconnect(name, serverConfig).catch((e) => console.error(`Failed to reconnect ${name}:`, e));
+        } else {
+          // Lazy or never connected: start over from the new config
+          this.cached.delete(name);
+          starting.push(serverConfig);
+        }
+      } else if (!running) {
+        // Unchanged, but it failed to connect last time
+        starting.push(serverConfig);
+      }
+    }
+
+    const warming = await this.startServers(starting);
+    this.checkDuplicateTools();
+    this.warmUp(warming);
+
+    return { added, removed, changed };
+  }
+
+  // Stops a server and drops what the manager knows of it, for one taken out of the config
+  private async forget(name: string): Promise<void> {
+    const stopped = await this.stopClient(name);
+    const cached = this.cached.delete(name);
+    this.configs.delete(name);
+    if (stopped || cached) this.emitNotification("notifications/tools/list_changed");
+  }
+
+  // Connects servers in order, or lists them from the tool cache. Returns the cached ones to
+  // warm up in the background once the caller is done with the rest.
+  private async startServers(servers: McpServerConfig[]): Promise<McpServerConfig[]> {
+    const warming: McpServerConfig[] = [];
 
-  async connectAll(config: Config): Promise<void> {
-    const enabled = config.servers.filter((s) => s.enabled);
+    for (const serverConfig of servers) {
+      // Without a cache entry a lazy server is connected once, so it can be listed next time
+      const schema = serverConfig.lazy || this.settings.schemaCache ? this.toolCache.get(serverConfig) : null;
+      if (schema) {
+        this.configs.set(serverConfig.name, serverConfig);
+        this.cached.set(serverConfig.name, { config: serverConfig, ...schema });
+        if (!serverConfig.lazy) warming.push(serverConfig);
+        continue;
+      }
+
+      if (serverConfig.startDelayMs) {
+        await new Promise((resolve) => setTimeout(resolve, serverConfig.startDelayMs));
+      }
 
-    for (const serverConfig of enabled) {
       try {
         await this.connect(serverConfig);
       } catch (e) {
         console.error(`Failed to connect to ${serverConfig.name}:`, e);
       }
     }
+
+    return warming;
+  }
+
+  // Connects servers listed from the cache in the background, in start order. Calls to
+  // their tools wait for the connection; tools/list_changed fires as each one comes up.
+  private warmUp(servers: McpServerConfig[]): void {
+    let previous: Promise<void> = Promise.resolve();
+
+    for (const config of servers) {
+      const connecting = previous
+        .then(() => config.startDelayMs && new Promise((resolve) => setTimeout(resolve, config.startDelayMs)))
+        .then(() => this.connect(config))
+        .catch((e) => console.error(`Failed to connect to ${config.name}:`, e))
+        .finally(() => this.waking.delete(config.name));
+
+      this.waking.set(config.name, connecting);
+      previous = connecting;
+    }
+  }
+
+  // Every server whose tools are exposed: connected ones, plus those served from the tool cache
+  private toolSources(): [string, McpServerConfig, Tool[]][] {
+    return [
+      ...[...this.clients].map(([name, client]): [string, McpServerConfig, Tool[]] => [name, client.config, client.tools]),
+      ...[...this.cached].map(([name, entry]): [string, McpServerConfig, Tool[]] => [name, entry.config, entry.tools]),
+    ];
+  }
+
+  // Exposed tool name -> the "server/tool" pairs that map to it, for names claimed more than once
+  findDuplicateTools(): Map<string, string[]> {
+    const sources = new Map<string, string[]>();
+
+    for (const [mcpName, config, tools] of this.toolSources()) {
+      const group = config.group;
+      for (const tool of tools) {
+        const name = namespaceTools(group ?? mcpName, tool.name);
+        const source = group ? `group ${group}/${tool.name}` : `${mcpName}/${tool.name}`;
+        const existing = sources.get(name) ?? [];
+        // Group members are expected to share tools
+        if (group && existing.includes(source)) continue;
+        sources.set(name, [...existing, source]);
+      }
+    }
+
+    return new Map([...sources].filter(([, from]) => from.length > 1));
+  }
+
+  private checkDuplicateTools(): void {
+    const duplicates = this.findDuplicateTools();
+    if (duplicates.size === 0) return;
+
+    const lines = [...duplicates].map(([name, from]) => `  ${name} <- ${from.join(", ")}`);
+    const message = `Duplicate tool names, only one of each is reachable via tools/call:\n${lines.join("\n")}`;
+
+    if (this.settings.strictToolNames) {
+      throw new Error(message);
+    }
+    console.error(`WARNING: ${message}`);
   }
 
   async connect(config: McpServerConfig): Promise<void> {
+    try {
+      await this.startClient(config);
+      this.churn.connects++;
+    } catch (e) {
+      this.churn.connectFailures++;
+      this.recordError(config.name, e);
+      throw e;
+    }
+  }
+
+  private recordError(name: string, error: unknown): void {
+    this.lastErrors.set(name, { message: error instanceof Error ? error.message : String(error), time: Date.now() });
+  }
+
+  private async startClient(config: McpServerConfig): Promise<void> {
     console.error(`Connecting to MCP server: ${config.name}`);
+    if (config.protocolVersion && !KNOWN_PROTOCOL_VERSIONS.includes(config.protocolVersion)) {
+      console.error(`Warning: ${config.name} is pinned to unknown protocolVersion '${config.protocolVersion}'`);
+    }
+    this.configs.set(config.name, config);
 
-    const client = new StdioClient(config);
+    const client = new StdioClient(config, {
+      timeoutSecs: this.settings.requestTimeoutSecs,
+      initTimeoutSecs: this.settings.initTimeoutSecs,
+      clientInfo: this.getClientInfo(),
+      tolerateStdoutLogs: this.settings.tolerateStdoutLogs,
+      debug: this.settings.logLevel === "debug",
+    });
+    client.onNotification = (method, params) => this.handleBackendNotification(config.name, method, params);
+    client.onRequest = (method, params) => this.handleBackendRequest(config.name, method, params);
+    client.onExit = (reason) => this.recordError(config.name, `Process ${reason}`);
     await client.start();
 
-    const initResult = await client.initialize();
+    const initResult = await client.initialize(this.settings.forwardRoots ? { roots: { listChanged: true } } : {});
     console.error(`Connected to ${initResult.serverInfo.name} (${initResult.serverInfo.version})`);
 
-    const tools = await client.listTools();
-    console.error(`${config.name} provides ${tools.length} tools`);
+    let tools: Tool[];
+    try {
+      tools = await client.listTools();
+
+      // Some servers aren't ready to serve tools right after initialize
+      const retries = this.settings.toolsReadyRetries ?? 0;
+      for (let attempt = 1; tools.length === 0 && attempt <= retries; attempt++) {
+        await new Promise((resolve) => setTimeout(resolve, this.settings.toolsReadyDelayMs ?? 500));
+        tools = await client.listTools();
+      }
+
+      if (config.readyProbe) await this.waitUntilReady(client, config.readyProbe);
+    } catch (e) {
+      // Not tracked in clients yet, so nothing else would stop it
+      await client.shutdown();
+      throw e;
+    }
+
+    if (tools.length === 0) {
+      console.error(`${config.name} provides no tools - check that it is configured correctly`);
+    } else {
+      console.error(`${config.name} provides ${tools.length} tools`);
+    }
 
     this.clients.set(config.name, client);
+    this.cached.delete(config.name);
+    if (config.lazy || this.settings.schemaCache) {
+      this.toolCache.set(config, { tools, capabilities: initResult.capabilities });
+    }
+    // Lets long-lived clients (SSE, socket) refresh their tool cache after a reconnect or reload
+    this.emitNotification("notifications/tools/list_changed");
+  }
+
+  // Calls the probe tool until it returns a result that isn't isError, giving up at timeoutSecs
+  private async waitUntilReady(client: StdioClient, probe: ReadyProbe): Promise<void> {
+    const timeoutSecs = probe.timeoutSecs ?? DEFAULT_READY_TIMEOUT_SECS;
+    const deadline = Date.now() + timeoutSecs * 1000;
+
+    for (;;) {
+      let failure: string;
+      try {
+        // A hung probe gives up at the deadline rather than after the server's own call timeout
+        const params = { name: probe.tool, arguments: probe.arguments ?? {} };
+        const result = await client.callTool(params, undefined, Math.max(deadline - Date.now(), 1));
+        if (!result.isError) return;
+        failure = "returned an error result";
+      } catch (e) {
+        failure = e instanceof Error ? e.message : String(e);
+      }
+
+      if (Date.now() >= deadline) {
+        throw new Error(`${client.name} not ready after ${timeoutSecs}s: readyProbe ${probe.tool} ${failure}`);
+      }
+      await new Promise((resolve) => setTimeout(resolve, probe.intervalMs ?? DEFAULT_READY_INTERVAL_MS));
+    }
   }
 
   async disconnect(name: string): Promise<void> {
+    if (await this.stopClient(name)) {
+      // Its tools are gone, so clients holding on to the list shouldn't keep calling them
+      this.emitNotification("notifications/tools/list_changed");
+    }
+  }
+
+  // Returns false when the server wasn't connected
+  private async stopClient(name: string): Promise<boolean> {
     const client = this.clients.get(name);
-    if (client) {
-      await client.shutdown();
-      this.clients.delete(name);
+    if (!client) return false;
+
+    await client.shutdown(this.settings.shutdownTimeoutMs);
+    this.clients.delete(name);
+    this.churn.disconnects++;
+    for (const uri of this.subscriptions.keys()) {
+      if (parseNamespacedTool(uri)?.mcp === name) this.subscriptions.delete(uri);
+    }
+    return true;
+  }
+
+  // Restarts one server without touching the others. Pass a config to pick up edits,
+  // otherwise the one it was last connected with is reused. Returns the new tool count.
+  async reconnect(name: string, config?: McpServerConfig): Promise<number> {
+    const serverConfig = config ?? this.configs.get(name);
+    if (!serverConfig) {
+      throw new McpError(ErrorCodes.INVALID_PARAMS, `MCP server '${name}' not found`);
+    }
+
+    this.churn.reconnects++;
+    this.restarts.set(name, (this.restarts.get(name) ?? 0) + 1);
+    this.warnIfFlapping(name);
+
+    const client = this.clients.get(name);
+    const edited = config !== undefined && canonicalJson(config) !== canonicalJson(client?.config);
+    const swap = (async () => {
+      // Only a replacement drains: a plain restart is usually for a wedged server whose calls won't finish
+      if (edited && client?.isRunning() && client.inFlight > 0) await this.drain(name, client);
+      const stopped = await this.stopClient(name);
+      try {
+        await this.connect(serverConfig);
+        // New tools (e.g. from an edited config) may collide, which strictToolNames doesn't allow
+        this.checkDuplicateTools();
+      } catch (e) {
+        const rejected = await this.stopClient(name);
+        // A successful connect announces the new tool list; a failed one leaves the server without any
+        if (stopped || rejected) this.emitNotification("notifications/tools/list_changed");
+        throw e;
+      }
+    })();
+    // Waiting callers find out about a failed connect from the missing server instead
+    this.swapping.set(name, swap.catch(() => {}));
+    try {
+      await swap;
+    } finally {
+      this.swapping.delete(name);
+    }
+    // Up again, so the next failure starts the backoff over (and a manual reconnect un-gives up)
+    this.backoff.delete(name);
+    return this.clients.get(name)?.tools.length ?? 0;
+  }
+
+  // Waits up to drainTimeoutMs for the calls running on a server's old process to finish
+  private async drain(name: string, client: StdioClient): Promise<void> {
+    const timeoutMs = this.settings.drainTimeoutMs ?? DEFAULT_DRAIN_TIMEOUT_MS;
+    console.error(`Draining ${name}: waiting up to ${timeoutMs}ms for ${client.inFlight} calls in flight`);
+
+    const deadline = Date.now() + timeoutMs;
+    while (client.inFlight > 0 && client.isRunning() && Date.now() < deadline) {
+      await new Promise((resolve) => setTimeout(resolve, DRAIN_POLL_MS));
+    }
+
+    if (client.inFlight > 0 && client.isRunning()) {
+      console.error(`Drain of ${name} timed out, stopping it with ${client.inFlight} calls still running`);
+    } else {
+      console.error(`Drained ${name}`);
+    }
+  }
+
+  private warnIfFlapping(name: string): void {
+    const now = Date.now();
+    const recent = [...(this.reconnectTimes.get(name) ?? []), now].filter((t) => now - t < FLAP_WINDOW_MS);
+    this.reconnectTimes.set(name, recent);
+
+    if (recent.length > FLAP_RECONNECTS) {
+      console.error(
+        `WARNING: ${name} has reconnected ${recent.length} times in the last ${FLAP_WINDOW_MS / 60000} minutes`
+      );
     }
   }
 
+  getConnectionStats(): ConnectionStats {
+    return {
+      connectsTotal: this.churn.connects,
+      connectFailuresTotal: this.churn.connectFailures,
+      disconnectsTotal: this.churn.disconnects,
+      reconnectsTotal: this.churn.reconnects,
+      connectedServers: this.clients.size,
+    };
+  }
+
   listAllTools(): Tool[] {
-    const allTools: Tool[] = [];
+    const allTools: { tool: Tool; priority: number }[] = [];
+    const seen = new Set<string>();
 
-    for (const [mcpName, client] of this.clients) {
-      for (const tool of client.tools) {
-        allTools.push({
-          name: namespaceTools(mcpName, tool.name),
-          description: tool.description ? `[${mcpName}] ${tool.description}` : undefined,
+    for (const [mcpName, config, tools] of this.toolSources()) {
+      const namespace = config.group ?? mcpName;
+      for (const tool of tools) {
+        const name = namespaceTools(namespace, tool.name);
+        // Members of a group expose one merged tool set
+        if (config.group && seen.has(name)) continue;
+        seen.add(name);
+
+        const maxLength = config.maxDescriptionLength ?? this.settings.maxDescriptionLength;
+        const description = config.toolDescriptions?.[tool.name] ?? tool.description;
+        const exposed: Tool = {
+          name,
+          description: description ? truncateDescription(`[${namespace}] ${description}`, maxLength) : undefined,
           inputSchema: tool.inputSchema,
+        };
+        allTools.push({
+          tool: this.schemaTransforms.reduce((t, transform) => transform(t, { server: mcpName, config }), exposed),
+          priority: config.priority ?? 0,
         });
       }
     }
 
-    return allTools;
+    const max = this.settings.maxExposedTools;
+    if (max === undefined) return allTools.map(({ tool }) => tool);
+
+    // Higher priority first; the sort is stable, so ties keep config order
+    const ranked = allTools.sort((a, b) => b.priority - a.priority).map(({ tool }) => tool);
+    this.warnDroppedTools(ranked.slice(max).map((t) => t.name));
+    return ranked.slice(0, max);
+  }
+
+  // Each server's tools under their backend names, from the lists fetched at connect (or cached)
+  toolsByServer(): Record<string, Tool[]> {
+    const byServer: Record<string, Tool[]> = {};
+    for (const [mcpName, , tools] of this.toolSources()) {
+      byServer[mcpName] = tools;
+    }
+    return byServer;
+  }
+
+  // Tool lists are rebuilt on every request, so only warn when the dropped set changes
+  private warnDroppedTools(dropped: string[]): void {
+    const key = dropped.join(",");
+    if (key === this.droppedTools) return;
+    this.droppedTools = key;
+
+    if (dropped.length > 0) {
+      console.error(
+        `WARNING: maxExposedTools is ${this.settings.maxExposedTools}, not exposing ${dropped.length} tools: ${dropped.join(", ")}`
+      );
+    }
+  }
+
+  // Clients serving a tool namespace: a single server, or every member of a group.
+  // Running members come first so a dead primary doesn't cost a failed attempt.
+  private membersFor(namespace: string): [string, StdioClient][] {
+    return [...this.clients]
+      .filter(([name, client]) => (client.config.group ?? name) === namespace)
+      .sort(([, a], [, b]) => Number(b.isRunning()) - Number(a.isRunning()));
+  }
+
+  // Union of backend capabilities, limited to what the bridge can actually route
+  getCapabilities(): ServerCapabilities {
+    const capabilities: ServerCapabilities = {};
+
+    const sources = [
+      ...[...this.clients.values()].map((client) => ({ tools: client.tools, capabilities: client.serverInfo?.capabilities })),
+      ...this.cached.values(),
+    ];
+
+    for (const source of sources) {
+      if (source.capabilities?.tools || source.tools.length > 0) {
+        capabilities.tools = { listChanged: true };
+      }
+      const resources = source.capabilities?.resources;
+      if (resources) {
+        capabilities.resources = {
+          subscribe: capabilities.resources?.subscribe || resources.subscribe || false,
+          listChanged: capabilities.resources?.listChanged || resources.listChanged || false,
+        };
+      }
+      if (source.capabilities?.completions) {
+        capabilities.completions = {};
+      }
+    }
+
+    return capabilities;
+  }
+
+  getInstructions(): Record<string, string> {
+    const instructions: Record<string, string> = {};
+
+    for (const [mcpName, client] of this.clients) {
+      const text = client.serverInfo?.instructions;
+      if (text) {
+        instructions[mcpName] = text;
+      }
+    }
+
+    return instructions;
   }
 
-  async callTool(namespacedName: string, args: Record<string, unknown>): Promise<ToolCallResult> {
-    const parsed = parseNamespacedTool(namespacedName);
+  async callTool(
+    namespacedName: string,
+    args: Record<string, unknown>,
+    signal?: AbortSignal,
+    meta?: RequestMeta,
+    channel?: NotificationListener
+  ): Promise<ToolCallResult> {
+    let parsed = parseNamespacedTool(namespacedName);
+    if (!parsed && this.settings.allowShortNames) {
+      parsed = this.resolveShortName(namespacedName);
+      namespacedName = namespaceTools(parsed.mcp, parsed.tool);
+    }
     if (!parsed) {
-      throw new Error(`Invalid tool name format: ${namespacedName}`);
+      throw new McpError(ErrorCodes.INVALID_PARAMS, `Invalid tool name format: ${namespacedName}`);
+    }
+
+    await this.wakeLazy(parsed.mcp);
+    await this.waitForSwaps(parsed.mcp);
+
+    const members = this.membersFor(parsed.mcp);
+    if (members.length === 0) {
+      throw new McpError(ErrorCodes.METHOD_NOT_FOUND, `MCP server '${parsed.mcp}' not connected`);
+    }
+
+    const candidates = members.filter(([, client]) => client.tools.some((t) => t.name === parsed.tool));
+    if (candidates.length === 0) {
+      throw new McpError(ErrorCodes.METHOD_NOT_FOUND, `Tool '${parsed.tool}' not found on MCP server '${parsed.mcp}'`);
+    }
+
+    const tool = parsed.tool;
+    if (!candidates.some(([, client]) => client.config.cacheableTools?.includes(tool))) {
+      return this.callCandidates(parsed.mcp, tool, namespacedName, candidates, args, signal, meta, channel);
+    }
+
+    // Identical calls to a cacheable tool already in flight share its result. The shared call
+    // isn't tied to any one caller's signal, so a caller giving up only stops its own wait.
+    const key = `${namespacedName}\n${canonicalJson(args)}`;
+    let shared = this.sharedCalls.get(key);
+    if (!shared) {
+      shared = this.callCandidates(
+        parsed.mcp,
+        tool,
+        namespacedName,
+        candidates,
+        args,
+        undefined,
+        meta,
+        channel
+      ).finally(() => this.sharedCalls.delete(key));
+      this.sharedCalls.set(key, shared);
+    }
+    return untilAborted(shared, signal, "tools/call");
+  }
+
+  // Fail over across group members on transport/protocol errors. isError results are returned as-is.
+  private async callCandidates(
+    namespace: string,
+    tool: string,
+    namespacedName: string,
+    candidates: [string, StdioClient][],
+    args: Record<string, unknown>,
+    signal: AbortSignal | undefined,
+    meta: RequestMeta | undefined,
+    channel: NotificationListener | undefined
+  ): Promise<ToolCallResult> {
+    let lastError: unknown;
+    for (const [mcpName, client] of this.balance(namespace, candidates)) {
+      try {
+        return await this.withProgress(meta, channel, (meta) =>
+          this.callWithRetries(mcpName, client, tool, namespacedName, args, signal, meta)
+        );
+      } catch (e) {
+        lastError = e;
+        if (signal?.aborted) break;
+        this.recordError(mcpName, e);
+        if (candidates.length > 1) {
+          console.error(`${mcpName} failed to call ${tool}, trying the next member of '${namespace}':`, e);
+        }
+      }
+    }
+    throw lastError;
+  }
+
+  // Finds the one namespace with a tool of this backend name (allowShortNames)
+  private resolveShortName(toolName: string): { mcp: string; tool: string } {
+    const namespaces = new Set<string>();
+    for (const [mcpName, config, tools] of this.toolSources()) {
+      if (tools.some((t) => t.name === toolName)) namespaces.add(config.group ?? mcpName);
+    }
+
+    if (namespaces.size === 0) {
+      throw new McpError(ErrorCodes.METHOD_NOT_FOUND, `Tool '${toolName}' not found`);
+    }
+    if (namespaces.size > 1) {
+      const names = [...namespaces].map((ns) => namespaceTools(ns, toolName)).join(", ");
+      throw new McpError(ErrorCodes.INVALID_PARAMS, `Tool name '${toolName}' is ambiguous, use one of: ${names}`);
+    }
+    return { mcp: [...namespaces][0], tool: toolName };
+  }
+
+  // Starts (or waits for) the cached servers serving a namespace, sharing the connect between concurrent calls
+  private async wakeLazy(namespace: string): Promise<void> {
+    const sleeping = [...this.cached.values()].filter(({ config }) => (config.group ?? config.name) === namespace);
+
+    await Promise.all(
+      sleeping.map(({ config }) => {
+        let waking = this.waking.get(config.name);
+        if (!waking) {
+          waking = this.connect(config).finally(() => this.waking.delete(config.name));
+          this.waking.set(config.name, waking);
+        }
+        return waking;
+      })
+    );
+  }
+
+  // Holds a call while a server in its namespace is being replaced, so it reaches the new process
+  private async waitForSwaps(namespace: string): Promise<void> {
+    const swaps = [...this.swapping]
+      .filter(([name]) => (this.configs.get(name)?.group ?? name) === namespace)
+      .map(([, swap]) => swap);
+    await Promise.all(swaps);
+  }
+
+  // Orders group members according to the group's load balancing strategy
+  private balance(group: string, members: [string, StdioClient][]): [string, StdioClient][] {
+    if (members.length < 2) return members;
+
+    const rotate = (start: number) => [...members.slice(start), ...members.slice(0, start)];
+
+    switch (this.settings.groups?.[group]?.loadBalance ?? "failover") {
+      case "round_robin": {
+        const next = this.roundRobin.get(group) ?? 0;
+        this.roundRobin.set(group, next + 1);
+        return rotate(next % members.length);
+      }
+      case "random":
+        return rotate(Math.floor(Math.random() * members.length));
+      case "least_busy":
+        return [...members].sort(([, a], [, b]) => a.inFlight - b.inFlight);
+      default:
+        return members;
+    }
+  }
+
+  // Per-server tool call counters
+  getCallCounts(): Record<string, { calls: number; inFlight: number }> {
+    const counts: Record<string, { calls: number; inFlight: number }> = {};
+    for (const [mcpName, client] of this.clients) {
+      counts[mcpName] = { calls: client.callCount, inFlight: client.inFlight };
+    }
+    return counts;
+  }
+
+  // Retries calls that didn't reach the tool, per the server's callRetries. isError results
+  // are the tool's answer and come back from callOnClient without throwing, so never retry.
+  // A timed-out call may still have run, so it is only retried with retryTimeouts.
+  private async callWithRetries(
+    mcpName: string,
+    client: StdioClient,
+    toolName: string,
+    namespacedName: string,
+    args: Record<string, unknown>,
+    signal?: AbortSignal,
+    meta?: RequestMeta
+  ): Promise<ToolCallResult> {
+    const retries = client.config.callRetries ?? 0;
+    const backoffMs = client.config.callRetryBackoffMs ?? DEFAULT_RETRY_BACKOFF_MS;
+    const retryCodes = client.config.retryErrorCodes ?? DEFAULT_RETRY_ERROR_CODES;
+
+    for (let attempt = 0; ; attempt++) {
+      try {
+        return await this.callOnClient(mcpName, client, toolName, namespacedName, args, signal, meta);
+      } catch (e) {
+        const retryable =
+          e instanceof McpError
+            ? e.code !== ErrorCodes.INVALID_PARAMS && retryCodes.includes(e.code)
+            : !isTimeout(e) || client.config.retryTimeouts === true;
+        if (attempt >= retries || !retryable || signal?.aborted) throw e;
+
+        const delay = backoffMs * 2 ** attempt;
+        console.error(
+          `${mcpName} failed to call ${toolName}, retrying in ${delay}ms (${attempt + 1}/${retries}):`,
+          e instanceof Error ? e.message : e
+        );
+        await new Promise((resolve) => setTimeout(resolve, delay));
+      }
+    }
+  }
+
+  private async callOnClient(
+    mcpName: string,
+    client: StdioClient,
+    toolName: string,
+    namespacedName: string,
+    args: Record<string, unknown>,
+    signal?: AbortSignal,
+    meta?: RequestMeta
+  ): Promise<ToolCallResult> {
+    const attributes = { "mcp.server": mcpName, "mcp.tool": toolName };
+
+    return withSpan(`tools/call ${namespacedName}`, SpanKind.CLIENT, attributes, async (span) => {
+      const startTime = Date.now();
+      let success = true;
+      let error: string | undefined;
+
+      try {
+        const result = await client.callTool({ name: toolName, arguments: args, _meta: meta }, signal);
+        success = !result.isError;
+        if (result.isError && this.settings.namespaceErrorText) {
+          return namespaceErrorText(result, toolName, namespacedName);
+        }
+        return result;
+      } catch (e) {
+        success = false;
+        error = e instanceof Error ? e.message : String(e);
+        throw e;
+      } finally {
+        const durationMs = Date.now() - startTime;
+        if (span) {
+          span.attributes["mcp.latency_ms"] = durationMs;
+          if (!success) span.error = error ?? "Tool returned an error";
+        }
+        this.stats?.record(namespacedName, success);
+        logToolCall({
+          mcp: mcpName,
+          tool: toolName,
+          args,
+          durationMs,
+          success,
+          error,
+        });
+      }
+    });
+  }
+
+  // Prompt names and resource URIs are namespaced like tools ({mcp}__{name}).
+  // Resolves one to its client and the backend's original name.
+  private resolve(namespaced: string | undefined): { mcp: string; client: StdioClient; name: string } {
+    const parsed = namespaced ? parseNamespacedTool(namespaced) : null;
+    if (!parsed) {
+      throw new McpError(ErrorCodes.INVALID_PARAMS, `Invalid namespaced name: ${namespaced}`);
     }
 
     const client = this.clients.get(parsed.mcp);
     if (!client) {
-      throw new Error(`MCP server '${parsed.mcp}' not connected`);
+      throw new McpError(ErrorCodes.METHOD_NOT_FOUND, `MCP server '${parsed.mcp}' not connected`);
+    }
+
+    return { mcp: parsed.mcp, client, name: parsed.tool };
+  }
+
+  async complete(params: CompleteParams): Promise<CompletionResult> {
+    const ref = params.ref;
+    const { client, name } = this.resolve(ref.type === "ref/prompt" ? ref.name : ref.uri);
+
+    const backendRef: CompletionRef =
+      ref.type === "ref/prompt" ? { type: "ref/prompt", name } : { type: "ref/resource", uri: name };
+    return client.complete({ ...params, ref: backendRef });
+  }
+
+  // Passes a request the bridge doesn't route itself to one server untouched (catchAllServer)
+  async forwardRequest(name: string, method: string, params?: unknown): Promise<JsonRpcResponse> {
+    const sleeping = this.cached.get(name);
+    if (sleeping) await this.wakeLazy(sleeping.config.group ?? name);
+
+    const client = this.clients.get(name);
+    if (!client) {
+      throw new McpError(ErrorCodes.METHOD_NOT_FOUND, `MCP server '${name}' not connected`);
     }
+    return client.requestRaw(method, params);
+  }
+
+  forwardNotification(name: string, method: string, params?: unknown): void {
+    this.clients.get(name)?.notify(method, params);
+  }
+
+  async listAllResources(meta?: RequestMeta, channel?: NotificationListener): Promise<Resource[]> {
+    const perServer = await this.withProgress(meta, channel, (meta) =>
+      Promise.all(
+        [...this.clients]
+          .filter(([, client]) => client.serverInfo?.capabilities?.resources)
+          .map(async ([mcpName, client]) => {
+            try {
+              const resources = await client.listResources(meta);
+              return resources.map((r) => ({ ...r, uri: namespaceTools(mcpName, r.uri) }));
+            } catch (e) {
+              console.error(`Failed to list resources from ${mcpName}:`, e);
+              return [];
+            }
+          })
+      )
+    );
+
+    return perServer.flat();
+  }
+
+  // Templates are namespaced like resource URIs, so expanded URIs route back to their server
+  async listAllResourceTemplates(meta?: RequestMeta, channel?: NotificationListener): Promise<ResourceTemplate[]> {
+    const perServer = await this.withProgress(meta, channel, (meta) =>
+      Promise.all(
+        [...this.clients]
+          .filter(([, client]) => client.serverInfo?.capabilities?.resources)
+          .map(async ([mcpName, client]) => {
+            try {
+              const templates = await client.listResourceTemplates(meta);
+              return templates.map((t) => ({ ...t, uriTemplate: namespaceTools(mcpName, t.uriTemplate) }));
+            } catch (e) {
+              console.error(`Failed to list resource templates from ${mcpName}:`, e);
+              return [];
+            }
+          })
+      )
+    );
+
+    return perServer.flat();
+  }
+
+  async readResource(uri: string, meta?: RequestMeta, channel?: NotificationListener): Promise<ReadResourceResult> {
+    const { mcp, client, name } = this.resolve(uri);
+    const result = await this.withProgress(meta, channel, (meta) => client.readResource(name, meta));
 
-    const startTime = Date.now();
-    let success = true;
-    let error: string | undefined;
+    return {
+      ...result,
+      contents: result.contents.map((c) => ({ ...c, uri: namespaceTools(mcp, c.uri) })),
+    };
+  }
+
+  // channel is the subscribing client's listener, so only it is sent the updates
+  async subscribeResource(uri: string, channel?: NotificationListener): Promise<void> {
+    const { client, name } = this.resolve(uri);
+    const subscribers = this.subscriptions.get(uri);
+    if (subscribers) {
+      subscribers.add(channel);
+      return;
+    }
+
+    await client.subscribeResource(name);
+    const added = this.subscriptions.get(uri) ?? new Set();
+    added.add(channel);
+    this.subscriptions.set(uri, added);
+  }
 
+  // The backend is only unsubscribed once no client is subscribed any more
+  async unsubscribeResource(uri: string, channel?: NotificationListener): Promise<void> {
+    const { client, name } = this.resolve(uri);
+    const subscribers = this.subscriptions.get(uri);
+    if (!subscribers?.delete(channel) || subscribers.size > 0) return;
+
+    this.subscriptions.delete(uri);
+    await client.unsubscribeResource(name);
+  }
+
+  // Forgets a client's channel when its connection closes, dropping its subscriptions
+  closeChannel(channel: NotificationListener): void {
+    for (const uri of [...this.subscriptions.keys()]) {
+      this.unsubscribeResource(uri, channel).catch((e) => console.error(`Failed to unsubscribe from ${uri}:`, e));
+    }
+  }
+
+  // Listen for notifications to relay to upstream clients. Returns a function that removes the listener.
+  onNotification(listener: NotificationListener): () => void {
+    this.notificationListeners.add(listener);
+    return () => this.notificationListeners.delete(listener);
+  }
+
+  private emitNotification(method: string, params?: unknown): void {
+    const notification: JsonRpcRequest = { jsonrpc: "2.0", method, params };
+    for (const listener of this.notificationListeners) {
+      listener(notification);
+    }
+  }
+
+  // Sends to the given channels that are still listening, or to everyone if one is undefined
+  private notifyChannels(channels: Iterable<NotificationListener | undefined>, method: string, params?: unknown): void {
+    const targets = [...channels];
+    if (targets.includes(undefined)) {
+      this.emitNotification(method, params);
+      return;
+    }
+
+    const notification: JsonRpcRequest = { jsonrpc: "2.0", method, params };
+    for (const channel of targets) {
+      if (channel && this.notificationListeners.has(channel)) channel(notification);
+    }
+  }
+
+  setUpstream(upstream: UpstreamRequester | null): void {
+    this.upstream = upstream;
+  }
+
+  // Called when the upstream client initializes, so reverse requests know what it supports
+  setClientCapabilities(capabilities: ClientCapabilities): void {
+    this.clientCapabilities = capabilities;
+    if (capabilities.roots) {
+      // Backends may have fetched an empty root list before the client connected
+      this.notifyBackends("notifications/roots/list_changed");
+    }
+  }
+
+  // Called when the upstream client initializes. Only affects backends connected afterwards.
+  setClientInfo(clientInfo: ClientInfo | undefined): void {
+    this.upstreamClientInfo = clientInfo ?? null;
+  }
+
+  private getClientInfo(): ClientInfo {
+    if (this.settings.forwardClientInfo && this.upstreamClientInfo) {
+      return this.upstreamClientInfo;
+    }
+    return {
+      name: this.settings.clientName ?? "mcp-central",
+      version: this.settings.clientVersion ?? "0.1.0",
+    };
+  }
+
+  notifyBackends(method: string, params?: unknown): void {
+    for (const client of this.clients.values()) {
+      client.notify(method, params);
+    }
+  }
+
+  private async handleBackendRequest(mcpName: string, method: string, params: unknown): Promise<unknown> {
+    switch (method) {
+      case "roots/list":
+        return this.listUpstreamRoots();
+      case "ping":
+        return {};
+      default:
+        throw new McpError(ErrorCodes.METHOD_NOT_FOUND, `Method not found: ${method} (from ${mcpName})`);
+    }
+  }
+
+  private async listUpstreamRoots(): Promise<unknown> {
+    if (!this.settings.forwardRoots || !this.clientCapabilities.roots || !this.upstream) {
+      return { roots: [] };
+    }
+
+    const response = await this.upstream("roots/list");
+    if (response.error) {
+      throw new McpError(response.error.code, response.error.message);
+    }
+    return response.result;
+  }
+
+  private handleBackendNotification(mcpName: string, method: string, params: unknown): void {
+    switch (method) {
+      case "notifications/tools/list_changed":
+        this.refreshTools(mcpName).catch((e) => console.error(`Failed to refresh tools from ${mcpName}:`, e));
+        break;
+      // Resource lists are fetched from the backends on every resources/list, so there is no
+      // cache to refresh; clients just need to know to ask again. (Prompts aren't aggregated yet.)
+      case "notifications/resources/list_changed":
+        this.emitNotification(method);
+        break;
+      case "notifications/progress": {
+        const token = (params as { progressToken?: ProgressToken } | undefined)?.progressToken;
+        const route = typeof token === "string" ? this.progressTokens.get(token) : undefined;
+        if (route) {
+          this.notifyChannels([route.channel], method, { ...(params as object), progressToken: route.token });
+        }
+        break;
+      }
+      case "notifications/resources/updated": {
+        const uri = namespaceTools(mcpName, (params as ResourceParams).uri);
+        const subscribers = this.subscriptions.get(uri);
+        if (subscribers) {
+          this.notifyChannels(subscribers, method, { ...(params as object), uri });
+        }
+        break;
+      }
+    }
+  }
+
+  // Backends are sent a progressToken of the bridge's own in place of the upstream client's, so
+  // clients picking the same token can't get each other's progress. While the request is out,
+  // progress reported for it goes back to the client that asked, under its own token.
+  private async withProgress<T>(
+    meta: RequestMeta | undefined,
+    channel: NotificationListener | undefined,
+    call: (meta: RequestMeta | undefined) => Promise<T>
+  ): Promise<T> {
+    const token = meta?.progressToken;
+    if (token === undefined) return call(meta);
+
+    const bridgeToken = `mcp-central-progress-${this.nextProgressToken++}`;
+    this.progressTokens.set(bridgeToken, { token, channel });
     try {
-      const result = await client.callTool({ name: parsed.tool, arguments: args });
-      success = !result.isError;
-      return result;
+      return await call({ ...meta, progressToken: bridgeToken });
+    } finally {
+      this.progressTokens.delete(bridgeToken);
+    }
+  }
+
+  // Re-lists a backend's tools after it reports a change, updating the cache and our clients
+  private async refreshTools(mcpName: string): Promise<void> {
+    const client = this.clients.get(mcpName);
+    if (!client) return;
+
+    const tools = await client.listTools();
+    if (client.config.lazy || this.settings.schemaCache) {
+      this.toolCache.set(client.config, { tools, capabilities: client.serverInfo?.capabilities });
+    }
+    this.emitNotification("notifications/tools/list_changed");
+  }
+
+  async ping(name: string): Promise<number> {
+    const client = this.clients.get(name);
+    if (!client) {
+      throw new Error(`MCP server '${name}' not connected`);
+    }
+    return client.ping();
+  }
+
+  // Process liveness alone misses a backend that is running but wedged, so this also pings it
+  async healthCheck(name: string, timeoutMs = HEALTH_CHECK_TIMEOUT_MS): Promise<ServerHealth> {
+    const client = this.clients.get(name);
+    const failure = this.lastErrors.get(name);
+    const backoff = this.backoff.get(name);
+    const history = {
+      restarts: this.restarts.get(name) ?? 0,
+      ...(failure && { lastError: failure.message, lastErrorAt: new Date(failure.time).toISOString() }),
+      ...(backoff && {
+        backoff: {
+          failures: backoff.failures,
+          nextAttemptAt: backoff.gaveUp ? undefined : new Date(backoff.nextAttemptAt).toISOString(),
+          gaveUp: backoff.gaveUp,
+        },
+      }),
+    };
+    if (!client) {
+      if (!this.configs.has(name)) {
+        throw new McpError(ErrorCodes.INVALID_PARAMS, `MCP server '${name}' not found`);
+      }
+      return { status: "dead", error: "Not connected", ...history };
+    }
+    if (!client.isRunning()) {
+      return { status: "dead", error: `Process ${client.exitReason ?? "exited"}`, ...history };
+    }
+
+    const uptimeSecs = client.connectedAt ? Math.floor((Date.now() - client.connectedAt) / 1000) : undefined;
+    const details = { tools: client.tools.length, uptimeSecs, ...history };
+    try {
+      return { status: "healthy", latencyMs: await client.ping(timeoutMs), ...details };
     } catch (e) {
-      success = false;
-      error = e instanceof Error ? e.message : String(e);
-      throw e;
+      return { status: "unresponsive", error: e instanceof Error ? e.message : String(e), ...details };
+    }
+  }
+
+  // Every server we've started or tried to, leaving out lazy ones that haven't been needed yet
+  async healthCheckAll(timeoutMs?: number): Promise<Record<string, ServerHealth>> {
+    const names = [...this.configs.keys()].filter((name) => !this.cached.has(name));
+    const results = await Promise.all(names.map((name) => this.healthCheck(name, timeoutMs)));
+    return Object.fromEntries(names.map((name, i) => [name, results[i]]));
+  }
+
+  private startHealthChecks(intervalSecs: number): void {
+    const intervalMs = intervalSecs * 1000;
+    // A check shouldn't outlast its interval
+    const timeoutMs = Math.min(intervalMs, HEALTH_CHECK_TIMEOUT_MS);
+    this.healthTimer = setInterval(() => this.restartUnhealthy(timeoutMs), intervalMs);
+    // Don't keep the process alive just for health checks
+    this.healthTimer.unref();
+  }
+
+  private async restartUnhealthy(timeoutMs: number): Promise<void> {
+    // A slow round (restarts included) shouldn't overlap the next one
+    if (this.checkingHealth) return;
+    this.checkingHealth = true;
+
+    try {
+      const health = await this.healthCheckAll(timeoutMs);
+      for (const [name, { status, error }] of Object.entries(health)) {
+        if (status === "healthy" || this.waking.has(name)) continue;
+        const backoff = this.backoff.get(name);
+        if (backoff && (backoff.gaveUp || Date.now() < backoff.nextAttemptAt)) continue;
+
+        console.error(`${name} is ${status} (${error}), restarting`);
+        try {
+          await this.reconnect(name);
+        } catch (e) {
+          console.error(`Failed to restart ${name}:`, e);
+          this.backOff(name);
+        }
+      }
     } finally {
-      logToolCall({
-        mcp: parsed.mcp,
-        tool: parsed.tool,
-        args,
-        durationMs: Date.now() - startTime,
-        success,
-        error,
-      });
+      this.checkingHealth = false;
     }
   }
 
+  private backOff(name: string): void {
+    const failures = (this.backoff.get(name)?.failures ?? 0) + 1;
+    const maxAttempts = this.settings.reconnectMaxAttempts;
+    if (maxAttempts !== undefined && failures >= maxAttempts) {
+      console.error(`Giving up on ${name} after ${failures} failed restarts; reconnect it by hand to retry`);
+      this.backoff.set(name, { failures, nextAttemptAt: 0, gaveUp: true });
+      return;
+    }
+
+    const delayMs = reconnectDelayMs(failures, this.settings);
+    this.backoff.set(name, { failures, nextAttemptAt: Date.now() + delayMs, gaveUp: false });
+  }
+
   getConnectedMcps(): string[] {
     return Array.from(this.clients.keys());
   }
 
+  getLazyMcps(): string[] {
+    return [...this.cached.values()].filter(({ config }) => config.lazy).map(({ config }) => config.name);
+  }
+
   async shutdownAll(): Promise<void> {
-    for (const [name, client] of this.clients) {
-      console.error(`Shutting down ${name}`);
-      await client.shutdown();
+    if (this.healthTimer) {
+      clearInterval(this.healthTimer);
+      this.healthTimer = null;
     }
+    // In parallel, so a server that has to be killed only delays exit by one timeout
+    await Promise.all(
+      [...this.clients].map(([name, client]) => {
+        console.error(`Shutting down ${name}`);
+        return client.shutdown(this.settings.shutdownTimeoutMs);
+      })
+    );
     this.clients.clear();
+    this.cached.clear();
+    this.stats?.flush();
+    await shutdownTracing();
+    await closeTranscript();
   }
 }
@@ -0,0 +1,61 @@
+import { describe, it, expect, vi } from "vitest";
+import { runReplCommand } from "./repl.js";
+import { Router } from "./router.js";
+import type { McpManager } from "./manager.js";
+import type { Config } from "./types.js";
+
+function createMockManager() {
+  return {
+    listAllTools: vi.fn().mockReturnValue([{ name: "fs__read", description: "[fs] Read a file", inputSchema: {} }]),
+    callTool: vi.fn().mockResolvedValue({ content: [{ type: "text", text: "hello" }] }),
+    getConnectedMcps: vi.fn().mockReturnValue(["fs"]),
+    ping: vi.fn().mockResolvedValue(3),
+    reconnect: vi.fn().mockResolvedValue(2),
+  } as unknown as McpManager;
+}
+
+const config: Config = {
+  settings: { logLevel: "info", daemonPort: 3000 },
+  servers: [{ name: "fs", command: "echo", args: [], env: {}, enabled: true }],
+};
+
+function run(line: string, manager = createMockManager()) {
+  return runReplCommand(line, config, manager, new Router(manager));
+}
+
+describe("repl", () => {
+  it("should list tools", async () => {
+    expect(await run("list")).toBe("  fs__read - [fs] Read a file");
+  });
+
+  it("should call a tool with JSON arguments", async () => {
+    const manager = createMockManager();
+
+    const output = await run(`call fs__read {"path": "/tmp/a"}`, manager);
+
+    expect(manager.callTool).toHaveBeenCalledWith("fs__read", { path: "/tmp/a" }, expect.any(AbortSignal), undefined);
+    expect(JSON.parse(output)).toEqual({ content: [{ type: "text", text: "hello" }] });
+  });
+
+  it("should reject invalid JSON arguments", async () => {
+    const manager = createMockManager();
+
+    expect(await run("call fs__read {nope", manager)).toMatch(/^Invalid JSON arguments/);
+    expect(manager.callTool).not.toHaveBeenCalled();
+  });
+
+  it("should ping a server", async () => {
+    expect(await run("ping fs")).toBe("fs: ok (3ms)");
+  });
+
+  it("should reconnect a configured server", async () => {
+    const manager = createMockManager();
+
+    expect(await run("reconnect fs", manager)).toBe("Reconnected fs (2 tools)");
+    expect(manager.reconnect).toHaveBeenCalledWith("fs", config.servers[0]);
+  });
+
+  it("should report unknown commands", async () => {
+    expect(await run("frobnicate")).toMatch(/^Unknown command 'frobnicate'/);
+  });
+});
@@ -0,0 +1,166 @@
+import * as fs from "node:fs";
+import * as path from "node:path";
+import * as readline from "node:readline";
+import type { Config } from "./types.js";
+import { getConfigDir, type ServerFilter } from "./config.js";
+import { McpManager } from "./manager.js";
+import { Router } from "./router.js";
+
+const HISTORY_SIZE = 500;
+
+const HELP = `Commands:
+  list                     List aggregated tools
+  call <tool> [json]       Call a tool with JSON arguments, e.g. call fs__read_file {"path":"/tmp/a"}
+  ping <server>            Ping a connected server
+  reconnect <server>       Restart a server and re-list its tools
+  servers                  List connected servers
+  help                     Show this help
+  exit                     Shut down backends and quit`;
+
+function getHistoryPath(): string {
+  return path.join(getConfigDir(), "repl_history");
+}
+
+function loadHistory(): string[] {
+  try {
+    // readline wants the most recent entry first
+    return fs.readFileSync(getHistoryPath(), "utf-8").split("\n").filter(Boolean).reverse();
+  } catch {
+    return [];
+  }
+}
+
+function saveHistory(history: string[]): void {
+  try {
+    fs.mkdirSync(getConfigDir(), { recursive: true });
+    fs.writeFileSync(getHistoryPath(), [...history].reverse().join("\n") + "\n");
+  } catch (e) {
+    console.error("Failed to save REPL history:", e);
+  }
+}
+
+// Runs one REPL line and returns the text to print (empty for blank lines)
+export async function runReplCommand(line: string, config: Config, manager: McpManager, router: Router): Promise<string> {
+  const trimmed = line.trim();
+  if (!trimmed) return "";
+
+  const space = trimmed.indexOf(" ");
+  const command = space === -1 ? trimmed : trimmed.slice(0, space);
+  const rest = space === -1 ? "" : trimmed.slice(space + 1).trim();
+
+  switch (command) {
+    case "help":
+      return HELP;
+
+    case "list": {
+      const tools = manager.listAllTools();
+      if (tools.length === 0) return "No tools available";
+      return tools.map((t) => `  ${t.name}${t.description ? ` - ${t.description}` : ""}`).join("\n");
+    }
+
+    case "servers": {
+      const connected = manager.getConnectedMcps();
+      return connected.length === 0 ? "No MCP servers connected" : connected.map((name) => `  ${name}`).join("\n");
+    }
+
+    case "call": {
+      const argStart = rest.indexOf(" ");
+      const name = argStart === -1 ? rest : rest.slice(0, argStart);
+      const json = argStart === -1 ? "" : rest.slice(argStart + 1).trim();
+      if (!name) return "Usage: call <tool> [json]";
+
+      let args: unknown = {};
+      if (json) {
+        try {
+          args = JSON.parse(json);
+        } catch (e) {
+          return `Invalid JSON arguments: ${e instanceof Error ? e.message : e}`;
+        }
+      }
+
+      const response = await router.handleRequest({
+        jsonrpc: "2.0",
+        id: 1,
+        method: "tools/call",
+        params: { name, arguments: args },
+      });
+      if (response.error) {
+        return `Error ${response.error.code}: ${response.error.message}`;
+      }
+      return JSON.stringify(response.result, null, 2);
+    }
+
+    case "ping": {
+      if (!rest) return "Usage: ping <server>";
+      try {
+        return `${rest}: ok (${await manager.ping(rest)}ms)`;
+      } catch (e) {
+        return `${rest}: failed - ${e instanceof Error ? e.message : e}`;
+      }
+    }
+
+    case "reconnect": {
+      if (!rest) return "Usage: reconnect <server>";
+      const server = config.servers.find((s) => s.name === rest);
+      if (!server) return `Server '${rest}' not found`;
+
+      try {
+        const tools = await manager.reconnect(rest, server);
+        return `Reconnected ${rest} (${tools} tools)`;
+      } catch (e) {
+        return `Failed to reconnect ${rest}: ${e instanceof Error ? e.message : e}`;
+      }
+    }
+
+    default:
+      return `Unknown command '${command}'. Type 'help' for a list of commands.`;
+  }
+}
+
+export async function runRepl(config: Config, filter: ServerFilter = {}): Promise<void> {
+  const manager = new McpManager(config.settings);
+  await manager.connectAll(config, filter);
+
+  const connected = manager.getConnectedMcps();
+  console.error(`Connected to ${connected.length} MCP servers: ${connected.join(", ")}`);
+
+  const router = new Router(manager, config.settings);
+
+  // Keep the latest history around, rl.history is gone by the time "close" fires
+  let history = loadHistory();
+
+  const rl = readline.createInterface({
+    input: process.stdin,
+    output: process.stdout,
+    prompt: "mcp-central> ",
+    history,
+    historySize: HISTORY_SIZE,
+  });
+
+  rl.on("history", (lines) => {
+    history = lines;
+  });
+
+  console.log("Type 'help' for a list of commands.");
+  rl.prompt();
+
+  rl.on("line", async (line) => {
+    if (line.trim() === "exit" || line.trim() === "quit") {
+      rl.close();
+      return;
+    }
+
+    // Don't interleave the prompt with output from a slow call
+    rl.pause();
+    const output = await runReplCommand(line, config, manager, router);
+    if (output) console.log(output);
+    rl.resume();
+    rl.prompt();
+  });
+
+  rl.on("close", async () => {
+    saveHistory(history);
+    await manager.shutdownAll();
+    process.exit(0);
+  });
+}
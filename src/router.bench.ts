@@ -0,0 +1,46 @@
+import { bench, describe, beforeAll, afterAll } from "vitest";
+import { fileURLToPath } from "node:url";
+import { McpManager } from "./manager.js";
+import { Router } from "./router.js";
+
+const MOCK_SERVER = fileURLToPath(new URL("../test/fixtures/mock-server.mjs", import.meta.url));
+
+// Round trip of a tools/call through the router, manager and a mock stdio backend
+describe("tools/call", () => {
+  let manager: McpManager;
+  let router: Router;
+
+  beforeAll(async () => {
+    manager = new McpManager();
+    await manager.connect({
+      name: "mock",
+      command: process.execPath,
+      args: [MOCK_SERVER],
+      env: {},
+      enabled: true,
+    });
+    router = new Router(manager);
+  });
+
+  afterAll(async () => {
+    await manager.shutdownAll();
+  });
+
+  const call = (label: string) =>
+    router.handleRequest({
+      jsonrpc: "2.0",
+      id: 1,
+      method: "tools/call",
+      params: { name: "mock__echo", arguments: { label } },
+    });
+
+  const large = "x".repeat(256 * 1024);
+
+  bench("small arguments", async () => {
+    await call("hello");
+  });
+
+  bench("256 KiB arguments", async () => {
+    await call(large);
+  });
+});
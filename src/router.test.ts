@@ -1,13 +1,27 @@
 import { describe, it, expect, vi } from "vitest";
-import { Router } from "./router.js";
+import { Router, Session, filterCapabilities } from "./router.js";
 import type { McpManager } from "./manager.js";
-import { ErrorCodes } from "./types.js";
+import { ErrorCodes, McpError } from "./types.js";
 
 function createMockManager(tools = [{ name: "test__tool", description: "A test tool", inputSchema: {} }]) {
   return {
     listAllTools: vi.fn().mockReturnValue(tools),
     callTool: vi.fn().mockResolvedValue({ content: [{ type: "text", text: "result" }] }),
     getConnectedMcps: vi.fn().mockReturnValue(["test"]),
+    getInstructions: vi.fn().mockReturnValue({}),
+    setClientCapabilities: vi.fn(),
+    setClientInfo: vi.fn(),
+    notifyBackends: vi.fn(),
+    getCapabilities: vi.fn().mockReturnValue({ tools: { listChanged: false } }),
+    complete: vi.fn().mockResolvedValue({ completion: { values: ["main", "master"] } }),
+    listAllResources: vi.fn().mockResolvedValue([{ uri: "fs__file:///a.txt", name: "a.txt" }]),
+    listAllResourceTemplates: vi.fn().mockResolvedValue([{ uriTemplate: "fs__file:///{path}", name: "file" }]),
+    readResource: vi.fn().mockResolvedValue({ contents: [{ uri: "fs__file:///a.txt", text: "hi" }] }),
+    subscribeResource: vi.fn().mockResolvedValue(undefined),
+    ping: vi.fn().mockResolvedValue(1),
+    reconnect: vi.fn().mockResolvedValue(3),
+    forwardRequest: vi.fn().mockResolvedValue({ jsonrpc: "2.0", id: 7, result: { vendor: true } }),
+    forwardNotification: vi.fn(),
   } as unknown as McpManager;
 }
 
@@ -27,6 +41,76 @@ describe("router", () => {
         serverInfo: { name: "mcp-central" },
       });
     });
+
+    it("should record the client's capabilities", async () => {
+      const manager = createMockManager();
+      const router = new Router(manager);
+
+      await router.handleRequest({
+        jsonrpc: "2.0",
+        id: 1,
+        method: "initialize",
+        params: { capabilities: { roots: { listChanged: true } } },
+      });
+
+      expect(manager.setClientCapabilities).toHaveBeenCalledWith({ roots: { listChanged: true } });
+    });
+
+    it("should keep other sessions' client state to themselves", async () => {
+      const manager = createMockManager();
+      const router = new Router(manager);
+      const session = new Session();
+
+      await router.handleRequest(
+        {
+          jsonrpc: "2.0",
+          id: 1,
+          method: "initialize",
+          params: { capabilities: { sampling: {} }, clientInfo: { name: "browser", version: "1.0" } },
+        },
+        session
+      );
+
+      expect(session.clientCapabilities).toEqual({ sampling: {} });
+      expect(session.clientInfo).toEqual({ name: "browser", version: "1.0" });
+      expect(manager.setClientCapabilities).not.toHaveBeenCalled();
+      expect(manager.setClientInfo).not.toHaveBeenCalled();
+    });
+
+    it("should advertise only capabilities backends provide", async () => {
+      const manager = createMockManager();
+      vi.mocked(manager.getCapabilities).mockReturnValue({});
+      const router = new Router(manager);
+
+      const response = await router.handleRequest({
+        jsonrpc: "2.0",
+        id: 1,
+        method: "initialize",
+        params: {},
+      });
+
+      expect((response.result as { capabilities: object }).capabilities).toEqual({});
+    });
+
+    it("should merge backend instructions prefixed by server name", async () => {
+      const manager = createMockManager();
+      vi.mocked(manager.getInstructions).mockReturnValue({
+        github: "Use create_issue for bugs.",
+        fs: "Paths are relative to /tmp.",
+      });
+      const router = new Router(manager);
+
+      const response = await router.handleRequest({
+        jsonrpc: "2.0",
+        id: 1,
+        method: "initialize",
+        params: {},
+      });
+
+      expect((response.result as { instructions: string }).instructions).toBe(
+        "[github] Use create_issue for bugs.\n\n[fs] Paths are relative to /tmp."
+      );
+    });
   });
 
   describe("tools/list", () => {
@@ -45,6 +129,39 @@ describe("router", () => {
         tools: [{ name: "test__tool", description: "A test tool", inputSchema: {} }],
       });
     });
+
+    it("should paginate when a page size is configured", async () => {
+      const tools = ["a", "b", "c"].map((n) => ({ name: `test__${n}`, description: n, inputSchema: {} }));
+      const router = new Router(createMockManager(tools), { toolsPageSize: 2 });
+
+      const first = await router.handleRequest({ jsonrpc: "2.0", id: 1, method: "tools/list" });
+      const firstPage = first.result as { tools: { name: string }[]; nextCursor?: string };
+      expect(firstPage.tools.map((t) => t.name)).toEqual(["test__a", "test__b"]);
+      expect(firstPage.nextCursor).toBeDefined();
+
+      const second = await router.handleRequest({
+        jsonrpc: "2.0",
+        id: 2,
+        method: "tools/list",
+        params: { cursor: firstPage.nextCursor },
+      });
+      const secondPage = second.result as { tools: { name: string }[]; nextCursor?: string };
+      expect(secondPage.tools.map((t) => t.name)).toEqual(["test__c"]);
+      expect(secondPage.nextCursor).toBeUndefined();
+    });
+
+    it("should reject an invalid cursor", async () => {
+      const router = new Router(createMockManager(), { toolsPageSize: 2 });
+
+      const response = await router.handleRequest({
+        jsonrpc: "2.0",
+        id: 1,
+        method: "tools/list",
+        params: { cursor: "not-a-cursor" },
+      });
+
+      expect(response.error?.code).toBe(ErrorCodes.INVALID_PARAMS);
+    });
   });
 
   describe("tools/call", () => {
@@ -59,7 +176,13 @@ describe("router", () => {
         params: { name: "test__tool", arguments: { foo: "bar" } },
       });
 
-      expect(manager.callTool).toHaveBeenCalledWith("test__tool", { foo: "bar" });
+      expect(manager.callTool).toHaveBeenCalledWith(
+        "test__tool",
+        { foo: "bar" },
+        expect.any(AbortSignal),
+        undefined,
+        undefined
+      );
       expect(response.result).toEqual({ content: [{ type: "text", text: "result" }] });
     });
 
@@ -75,6 +198,463 @@ describe("router", () => {
 
       expect(response.error?.code).toBe(ErrorCodes.INVALID_PARAMS);
     });
+
+    it.each([["a string", "foo"], ["an array", [1, 2]], ["null", null]])(
+      "should reject %s as arguments instead of calling with none",
+      async (_, args) => {
+        const manager = createMockManager();
+        const router = new Router(manager);
+
+        const response = await router.handleRequest({
+          jsonrpc: "2.0",
+          id: 1,
+          method: "tools/call",
+          params: { name: "test__tool", arguments: args },
+        });
+
+        expect(response.error?.code).toBe(ErrorCodes.INVALID_PARAMS);
+        expect(manager.callTool).not.toHaveBeenCalled();
+      }
+    );
+
+    it("should default missing arguments to an empty object", async () => {
+      const manager = createMockManager();
+      const router = new Router(manager);
+
+      await router.handleRequest({ jsonrpc: "2.0", id: 1, method: "tools/call", params: { name: "test__tool" } });
+
+      expect(manager.callTool).toHaveBeenCalledWith("test__tool", {}, expect.any(AbortSignal), undefined, undefined);
+    });
+
+    it("should abort the call when the client cancels it", async () => {
+      const manager = createMockManager();
+      vi.mocked(manager.callTool).mockImplementation(
+        (_name, _args, signal) =>
+          new Promise((_, reject) => signal?.addEventListener("abort", () => reject(new Error("Request cancelled"))))
+      );
+      const router = new Router(manager);
+
+      const pending = router.handleRequest({
+        jsonrpc: "2.0",
+        id: "slow",
+        method: "tools/call",
+        params: { name: "test__tool" },
+      });
+      await router.handleRequest({
+        jsonrpc: "2.0",
+        method: "notifications/cancelled",
+        params: { requestId: "slow", reason: "user gave up" },
+      });
+
+      expect((await pending).error?.message).toBe("Request cancelled");
+      const signal = vi.mocked(manager.callTool).mock.calls[0][2];
+      expect(signal?.reason).toBe("user gave up");
+    });
+
+    it("should only cancel calls from the session that sent the cancel", async () => {
+      const manager = createMockManager();
+      vi.mocked(manager.callTool).mockImplementation(
+        (_name, _args, signal) =>
+          new Promise((resolve, reject) => {
+            signal?.addEventListener("abort", () => reject(new Error("Request cancelled")));
+            setTimeout(() => resolve({ content: [] }), 50);
+          })
+      );
+      const router = new Router(manager);
+      const other = new Session();
+      const call = { jsonrpc: "2.0" as const, id: 1, method: "tools/call", params: { name: "test__tool" } };
+
+      const mine = router.handleRequest(call);
+      const theirs = router.handleRequest(call, other);
+      await router.handleRequest({ jsonrpc: "2.0", method: "notifications/cancelled", params: { requestId: 1 } });
+
+      expect((await mine).error?.message).toBe("Request cancelled");
+      expect((await theirs).result).toEqual({ content: [] });
+    });
+
+    it("should keep a reused id cancellable until every call with it finishes", async () => {
+      const manager = createMockManager();
+      let finishFirst: () => void = () => {};
+      vi.mocked(manager.callTool)
+        .mockImplementationOnce(() => new Promise((resolve) => (finishFirst = () => resolve({ content: [] }))))
+        .mockImplementationOnce(
+          (_name, _args, signal) =>
+            new Promise((_, reject) => signal?.addEventListener("abort", () => reject(new Error("Request cancelled"))))
+        );
+      const router = new Router(manager);
+      const call = { jsonrpc: "2.0" as const, id: 1, method: "tools/call", params: { name: "test__tool" } };
+
+      const first = router.handleRequest(call);
+      const second = router.handleRequest(call);
+      finishFirst();
+      await first;
+      await router.handleRequest({ jsonrpc: "2.0", method: "notifications/cancelled", params: { requestId: 1 } });
+
+      expect((await second).error?.message).toBe("Request cancelled");
+    });
+
+    it("should pass isError results through by default", async () => {
+      const manager = createMockManager();
+      const errorResult = { content: [{ type: "text", text: "Repo not found" }], isError: true };
+      vi.mocked(manager.callTool).mockResolvedValue(errorResult as never);
+      const router = new Router(manager);
+
+      const response = await router.handleRequest({
+        jsonrpc: "2.0",
+        id: 1,
+        method: "tools/call",
+        params: { name: "test__tool" },
+      });
+
+      expect(response.error).toBeUndefined();
+      expect(response.result).toEqual(errorResult);
+    });
+
+    it("should convert isError results to JSON-RPC errors when configured", async () => {
+      const manager = createMockManager();
+      vi.mocked(manager.callTool).mockResolvedValue({
+        content: [
+          { type: "text", text: "Repo not found" },
+          { type: "text", text: "Check the owner" },
+        ],
+        isError: true,
+      } as never);
+      const router = new Router(manager, { errorContentAsJsonRpcError: true });
+
+      const response = await router.handleRequest({
+        jsonrpc: "2.0",
+        id: 1,
+        method: "tools/call",
+        params: { name: "test__tool" },
+      });
+
+      expect(response.result).toBeUndefined();
+      expect(response.error?.code).toBe(ErrorCodes.INTERNAL_ERROR);
+      expect(response.error?.message).toBe("Repo not found\nCheck the owner");
+    });
+
+    it("should pass through typed error codes from the manager", async () => {
+      const manager = createMockManager();
+      vi.mocked(manager.callTool).mockRejectedValue(
+        new McpError(ErrorCodes.METHOD_NOT_FOUND, "MCP server 'nope' not connected")
+      );
+      const router = new Router(manager);
+
+      const response = await router.handleRequest({
+        jsonrpc: "2.0",
+        id: 1,
+        method: "tools/call",
+        params: { name: "nope__tool" },
+      });
+
+      expect(response.error?.code).toBe(ErrorCodes.METHOD_NOT_FOUND);
+    });
+
+    it("should map other failures to internal error", async () => {
+      const manager = createMockManager();
+      vi.mocked(manager.callTool).mockRejectedValue(new Error("Process exited"));
+      const router = new Router(manager);
+
+      const response = await router.handleRequest({
+        jsonrpc: "2.0",
+        id: 1,
+        method: "tools/call",
+        params: { name: "test__tool" },
+      });
+
+      expect(response.error?.code).toBe(ErrorCodes.INTERNAL_ERROR);
+    });
+  });
+
+  describe("resources", () => {
+    it("should list aggregated resources", async () => {
+      const router = new Router(createMockManager());
+
+      const response = await router.handleRequest({ jsonrpc: "2.0", id: 1, method: "resources/list" });
+
+      expect(response.result).toEqual({ resources: [{ uri: "fs__file:///a.txt", name: "a.txt" }] });
+    });
+
+    it("should pass the progressToken of list requests on", async () => {
+      const manager = createMockManager();
+      const router = new Router(manager);
+
+      await router.handleRequest({
+        jsonrpc: "2.0",
+        id: 1,
+        method: "resources/list",
+        params: { _meta: { progressToken: 7 } },
+      });
+
+      expect(manager.listAllResources).toHaveBeenCalledWith({ progressToken: 7 }, undefined);
+    });
+
+    it("should list aggregated resource templates", async () => {
+      const router = new Router(createMockManager());
+
+      const response = await router.handleRequest({ jsonrpc: "2.0", id: 1, method: "resources/templates/list" });
+
+      expect(response.result).toEqual({ resourceTemplates: [{ uriTemplate: "fs__file:///{path}", name: "file" }] });
+    });
+
+    it("should read a resource by namespaced URI", async () => {
+      const manager = createMockManager();
+      const router = new Router(manager);
+
+      const response = await router.handleRequest({
+        jsonrpc: "2.0",
+        id: 1,
+        method: "resources/read",
+        params: { uri: "fs__file:///a.txt" },
+      });
+
+      expect(manager.readResource).toHaveBeenCalledWith("fs__file:///a.txt", undefined, undefined);
+      expect(response.result).toEqual({ contents: [{ uri: "fs__file:///a.txt", text: "hi" }] });
+    });
+
+    it("should subscribe to a resource", async () => {
+      const manager = createMockManager();
+      const router = new Router(manager);
+
+      const response = await router.handleRequest({
+        jsonrpc: "2.0",
+        id: 1,
+        method: "resources/subscribe",
+        params: { uri: "fs__file:///a.txt" },
+      });
+
+      expect(manager.subscribeResource).toHaveBeenCalledWith("fs__file:///a.txt", undefined);
+      expect(response.result).toEqual({});
+    });
+
+    it("should error if uri is missing", async () => {
+      const router = new Router(createMockManager());
+
+      const response = await router.handleRequest({
+        jsonrpc: "2.0",
+        id: 1,
+        method: "resources/read",
+        params: {},
+      });
+
+      expect(response.error?.code).toBe(ErrorCodes.INVALID_PARAMS);
+    });
+  });
+
+  describe("completion/complete", () => {
+    it("should forward to the manager", async () => {
+      const manager = createMockManager();
+      const router = new Router(manager);
+      const params = {
+        ref: { type: "ref/prompt", name: "github__review" },
+        argument: { name: "branch", value: "ma" },
+      };
+
+      const response = await router.handleRequest({
+        jsonrpc: "2.0",
+        id: 1,
+        method: "completion/complete",
+        params,
+      });
+
+      expect(manager.complete).toHaveBeenCalledWith(params);
+      expect(response.result).toEqual({ completion: { values: ["main", "master"] } });
+    });
+
+    it("should error if ref is missing", async () => {
+      const router = new Router(createMockManager());
+
+      const response = await router.handleRequest({
+        jsonrpc: "2.0",
+        id: 1,
+        method: "completion/complete",
+        params: { argument: { name: "branch", value: "ma" } },
+      });
+
+      expect(response.error?.code).toBe(ErrorCodes.INVALID_PARAMS);
+    });
+  });
+
+  describe("notifications/roots/list_changed", () => {
+    it("should propagate to backends when forwarding roots", async () => {
+      const manager = createMockManager();
+      const router = new Router(manager, { forwardRoots: true });
+
+      await router.handleRequest({ jsonrpc: "2.0", method: "notifications/roots/list_changed" });
+
+      expect(manager.notifyBackends).toHaveBeenCalledWith("notifications/roots/list_changed");
+    });
+
+    it("should be ignored by default", async () => {
+      const manager = createMockManager();
+      const router = new Router(manager);
+
+      await router.handleRequest({ jsonrpc: "2.0", method: "notifications/roots/list_changed" });
+
+      expect(manager.notifyBackends).not.toHaveBeenCalled();
+    });
+  });
+
+  describe("ping", () => {
+    it("should answer locally by default", async () => {
+      const manager = createMockManager();
+      const router = new Router(manager);
+
+      const response = await router.handleRequest({ jsonrpc: "2.0", id: 1, method: "ping" });
+
+      expect(response).toEqual({ jsonrpc: "2.0", id: 1, result: {} });
+      expect(manager.ping).not.toHaveBeenCalled();
+    });
+
+    it("should succeed with any mode when one backend responds", async () => {
+      const manager = createMockManager();
+      vi.mocked(manager.getConnectedMcps).mockReturnValue(["a", "b"]);
+      vi.mocked(manager.ping).mockImplementation(async (name) => {
+        if (name === "a") throw new Error("Process exited");
+        return 1;
+      });
+      const router = new Router(manager, { pingChecksBackends: "any" });
+
+      const response = await router.handleRequest({ jsonrpc: "2.0", id: 1, method: "ping" });
+
+      expect(response.result).toEqual({});
+    });
+
+    it("should fail with all mode when a backend doesn't respond", async () => {
+      const manager = createMockManager();
+      vi.mocked(manager.getConnectedMcps).mockReturnValue(["a", "b"]);
+      vi.mocked(manager.ping).mockImplementation(async (name) => {
+        if (name === "a") throw new Error("Process exited");
+        return 1;
+      });
+      const router = new Router(manager, { pingChecksBackends: "all" });
+
+      const response = await router.handleRequest({ jsonrpc: "2.0", id: 1, method: "ping" });
+
+      expect(response.error?.code).toBe(ErrorCodes.INTERNAL_ERROR);
+      expect(response.error?.data).toEqual({ failed: ["a"] });
+    });
+
+    it("should fail when no backends are connected", async () => {
+      const manager = createMockManager();
+      vi.mocked(manager.getConnectedMcps).mockReturnValue([]);
+      const router = new Router(manager, { pingChecksBackends: "any" });
+
+      const response = await router.handleRequest({ jsonrpc: "2.0", id: 1, method: "ping" });
+
+      expect(response.error?.message).toBe("No MCP servers connected");
+    });
+  });
+
+  describe("$/reconnect", () => {
+    it("should reconnect the named server and return its tool count", async () => {
+      const manager = createMockManager();
+      const router = new Router(manager);
+
+      const response = await router.handleRequest({
+        jsonrpc: "2.0",
+        id: 1,
+        method: "$/reconnect",
+        params: { name: "test" },
+      });
+
+      expect(manager.reconnect).toHaveBeenCalledWith("test");
+      expect(response.result).toEqual({ tools: 3 });
+    });
+
+    it("should require a name", async () => {
+      const router = new Router(createMockManager());
+
+      const response = await router.handleRequest({ jsonrpc: "2.0", id: 1, method: "$/reconnect" });
+
+      expect(response.error?.code).toBe(ErrorCodes.INVALID_PARAMS);
+    });
+  });
+
+  describe("$/tools/validate", () => {
+    const schema = {
+      type: "object",
+      properties: { path: { type: "string" }, limit: { type: "integer", minimum: 1 } },
+      required: ["path"],
+    };
+    const validate = (router: Router, args: unknown) =>
+      router.handleRequest({
+        jsonrpc: "2.0",
+        id: 1,
+        method: "$/tools/validate",
+        params: { name: "fs__read", arguments: args },
+      });
+
+    it("should check arguments against the tool's schema without calling it", async () => {
+      const manager = createMockManager([{ name: "fs__read", description: "Read", inputSchema: schema }]);
+      const router = new Router(manager, { enableValidateExtension: true });
+
+      expect((await validate(router, { path: "/tmp/a", limit: 5 })).result).toEqual({ valid: true, errors: [] });
+      expect((await validate(router, { limit: 0 })).result).toEqual({
+        valid: false,
+        errors: ["arguments.path: is required", "arguments.limit: must be >= 1"],
+      });
+      expect(manager.callTool).not.toHaveBeenCalled();
+    });
+
+    it("should report null arguments as invalid rather than as no arguments", async () => {
+      const manager = createMockManager([{ name: "fs__read", description: "Read", inputSchema: schema }]);
+      const router = new Router(manager, { enableValidateExtension: true });
+
+      expect((await validate(router, null)).result).toEqual({
+        valid: false,
+        errors: ["arguments: expected object, got null"],
+      });
+    });
+
+    it("should reject unknown tools", async () => {
+      const router = new Router(createMockManager(), { enableValidateExtension: true });
+
+      const response = await validate(router, {});
+
+      expect(response.error?.code).toBe(ErrorCodes.METHOD_NOT_FOUND);
+    });
+
+    it("should not exist unless enabled", async () => {
+      const router = new Router(createMockManager([{ name: "fs__read", description: "Read", inputSchema: schema }]));
+
+      const response = await validate(router, { path: "/tmp/a" });
+
+      expect(response.error?.code).toBe(ErrorCodes.METHOD_NOT_FOUND);
+    });
+  });
+
+  describe("advertiseCapabilities", () => {
+    it("should intersect advertised capabilities with what backends support", () => {
+      const supported = { tools: { listChanged: true }, resources: { subscribe: true }, completions: {} };
+
+      expect(filterCapabilities(supported, ["tools", "prompts", "completions"])).toEqual({
+        tools: { listChanged: true },
+        completions: {},
+      });
+      expect(filterCapabilities(supported, undefined)).toEqual(supported);
+      expect(filterCapabilities(supported, [])).toEqual({});
+    });
+
+    it("should only advertise allowed capabilities in initialize", async () => {
+      const manager = createMockManager();
+      vi.mocked(manager.getCapabilities).mockReturnValue({ tools: {}, resources: {} });
+      const router = new Router(manager, { advertiseCapabilities: ["tools"] });
+
+      const response = await router.handleRequest({ jsonrpc: "2.0", id: 1, method: "initialize", params: {} });
+
+      expect((response.result as { capabilities: object }).capabilities).toEqual({ tools: {} });
+    });
+
+    it("should reject methods of capabilities that aren't advertised", async () => {
+      const manager = createMockManager();
+      const router = new Router(manager, { advertiseCapabilities: ["tools"] });
+
+      const response = await router.handleRequest({ jsonrpc: "2.0", id: 1, method: "resources/list" });
+
+      expect(response.error?.code).toBe(ErrorCodes.METHOD_NOT_FOUND);
+      expect(manager.listAllResources).not.toHaveBeenCalled();
+    });
   });
 
   describe("unknown method", () => {
@@ -90,4 +670,55 @@ describe("router", () => {
       expect(response.error?.code).toBe(ErrorCodes.METHOD_NOT_FOUND);
     });
   });
+
+  describe("catchAllServer", () => {
+    it("should forward unknown methods to the catch-all server under the client's id", async () => {
+      const manager = createMockManager();
+      const router = new Router(manager, { catchAllServer: "fs" });
+
+      const response = await router.handleRequest({ jsonrpc: "2.0", id: 1, method: "vendor/stat", params: { a: 1 } });
+
+      expect(manager.forwardRequest).toHaveBeenCalledWith("fs", "vendor/stat", { a: 1 });
+      expect(response).toEqual({ jsonrpc: "2.0", id: 1, result: { vendor: true } });
+    });
+
+    it("should relay the backend's error", async () => {
+      const manager = createMockManager();
+      vi.mocked(manager.forwardRequest).mockResolvedValue({
+        jsonrpc: "2.0",
+        id: 7,
+        error: { code: ErrorCodes.METHOD_NOT_FOUND, message: "Unknown method" },
+      });
+      const router = new Router(manager, { catchAllServer: "fs" });
+
+      const response = await router.handleRequest({ jsonrpc: "2.0", id: 1, method: "vendor/stat" });
+
+      expect(response).toEqual({
+        jsonrpc: "2.0",
+        id: 1,
+        error: { code: ErrorCodes.METHOD_NOT_FOUND, message: "Unknown method" },
+      });
+    });
+
+    it("should forward notifications without waiting for a reply", async () => {
+      const manager = createMockManager();
+      const router = new Router(manager, { catchAllServer: "fs" });
+
+      await router.handleRequest({ jsonrpc: "2.0", method: "notifications/vendor", params: { x: 1 } });
+
+      expect(manager.forwardNotification).toHaveBeenCalledWith("fs", "notifications/vendor", { x: 1 });
+      expect(manager.forwardRequest).not.toHaveBeenCalled();
+    });
+
+    it("should keep methods the bridge owns", async () => {
+      const manager = createMockManager();
+      const router = new Router(manager, { catchAllServer: "fs" });
+
+      await router.handleRequest({ jsonrpc: "2.0", id: 1, method: "initialize", params: {} });
+      const response = await router.handleRequest({ jsonrpc: "2.0", id: 2, method: "$/unknown" });
+
+      expect(manager.forwardRequest).not.toHaveBeenCalled();
+      expect(response.error?.code).toBe(ErrorCodes.METHOD_NOT_FOUND);
+    });
+  });
 });
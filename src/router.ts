@@ -1,30 +1,188 @@
-import type { McpManager } from "./manager.js";
-import type { JsonRpcRequest, JsonRpcResponse } from "./types.js";
-import { ErrorCodes } from "./types.js";
+import type { McpManager, NotificationListener } from "./manager.js";
+import { validateAgainstSchema } from "./schema.js";
+import { SpanKind, withSpan } from "./tracing.js";
+import { recordTranscript } from "./transcript.js";
+import type {
+  CapabilityName,
+  ClientCapabilities,
+  ClientInfo,
+  CompleteParams,
+  InitializeParams,
+  JsonRpcId,
+  JsonRpcRequest,
+  JsonRpcResponse,
+  PaginatedParams,
+  RequestMeta,
+  ResourceParams,
+  ServerCapabilities,
+  Settings,
+} from "./types.js";
+import { ErrorCodes, McpError, PROTOCOL_VERSION } from "./types.js";
+
+// One client connection's state. Request ids are only unique per connection, so each socket and
+// WebSocket gets its own; stdio and one-shot requests use the router's default session.
+export class Session {
+  // From the client's initialize
+  clientCapabilities: ClientCapabilities = {};
+  clientInfo: ClientInfo | undefined;
+
+  // Only the primary session (the stdio client) is passed on to the manager, for roots/list and
+  // forwardClientInfo. Other clients initializing must not replace what it told the bridge.
+  // channel is the connection's notification listener, for what only this client should get
+  // (progress of its requests, its resource updates); without one they are broadcast.
+  constructor(
+    readonly primary = false,
+    readonly channel?: NotificationListener
+  ) {}
+
+  // tools/call requests still running, by client request id, so notifications/cancelled can abort them.
+  // A client reusing an id while the first call runs gets both cancelled together.
+  private inFlight = new Map<JsonRpcId, Set<AbortController>>();
+
+  track(id: JsonRpcId, controller: AbortController): void {
+    const controllers = this.inFlight.get(id) ?? new Set();
+    controllers.add(controller);
+    this.inFlight.set(id, controllers);
+  }
+
+  untrack(id: JsonRpcId, controller: AbortController): void {
+    const controllers = this.inFlight.get(id);
+    controllers?.delete(controller);
+    if (controllers?.size === 0) this.inFlight.delete(id);
+  }
+
+  cancel(id: JsonRpcId, reason?: string): void {
+    for (const controller of this.inFlight.get(id) ?? []) {
+      controller.abort(reason);
+    }
+  }
+
+  // Aborts everything still running, e.g. when the connection closes
+  cancelAll(reason: string): void {
+    for (const id of [...this.inFlight.keys()]) {
+      this.cancel(id, reason);
+    }
+  }
+}
 
 export class Router {
-  constructor(private manager: McpManager) {}
+  readonly session: Session;
 
-  async handleRequest(request: JsonRpcRequest): Promise<JsonRpcResponse> {
+  constructor(
+    private manager: McpManager,
+    private settings: Partial<Settings> = {},
+    channel?: NotificationListener
+  ) {
+    this.session = new Session(true, channel);
+  }
+
+  async handleRequest(request: JsonRpcRequest, session = this.session): Promise<JsonRpcResponse> {
+    recordTranscript("in", request);
+    return withSpan(request.method, SpanKind.SERVER, { "rpc.method": request.method }, async (span) => {
+      const response = await this.dispatch(request, session);
+      if (span && response.error) span.error = response.error.message;
+      // Notifications get no reply on the wire
+      if (request.id !== undefined) recordTranscript("out", response);
+      return response;
+    });
+  }
+
+  private async dispatch(request: JsonRpcRequest, session: Session): Promise<JsonRpcResponse> {
     const id = request.id;
 
+    const capability = capabilityForMethod(request.method);
+    if (capability && !isAdvertised(capability, this.settings.advertiseCapabilities)) {
+      return {
+        jsonrpc: "2.0",
+        id,
+        error: {
+          code: ErrorCodes.METHOD_NOT_FOUND,
+          message: `Method not found: ${request.method}`,
+        },
+      };
+    }
+
     switch (request.method) {
       case "initialize":
-        return this.handleInitialize(id);
+        return this.handleInitialize(id, request.params as Partial<InitializeParams> | undefined, session);
 
       case "notifications/initialized":
         return { jsonrpc: "2.0", id, result: {} };
 
       case "tools/list":
-        return this.handleToolsList(id);
+        return this.handleToolsList(id, request.params as PaginatedParams | undefined);
 
       case "tools/call":
-        return this.handleToolsCall(id, request.params as Record<string, unknown> | undefined);
+        return this.handleToolsCall(id, request.params as Record<string, unknown> | undefined, session);
+
+      case "resources/list": {
+        const meta = (request.params as PaginatedParams | undefined)?._meta;
+        return this.forward(id, async () => ({
+          resources: await this.manager.listAllResources(meta, session.channel),
+        }));
+      }
+
+      case "resources/templates/list": {
+        const meta = (request.params as PaginatedParams | undefined)?._meta;
+        return this.forward(id, async () => ({
+          resourceTemplates: await this.manager.listAllResourceTemplates(meta, session.channel),
+        }));
+      }
+
+      case "resources/read":
+      case "resources/subscribe":
+      case "resources/unsubscribe":
+        return this.handleResourceRequest(id, request.method, request.params as ResourceParams | undefined, session);
+
+      case "completion/complete":
+        return this.handleComplete(id, request.params as CompleteParams | undefined);
+
+      case "notifications/roots/list_changed":
+        if (this.settings.forwardRoots) {
+          this.manager.notifyBackends(request.method);
+        }
+        return { jsonrpc: "2.0", id, result: {} };
 
       case "ping":
+        return this.handlePing(id);
+
+      case "notifications/cancelled": {
+        const { requestId, reason } = (request.params ?? {}) as { requestId?: JsonRpcId; reason?: string };
+        if (requestId !== undefined) {
+          session.cancel(requestId, reason);
+        }
         return { jsonrpc: "2.0", id, result: {} };
+      }
+
+      // Bridge control request: restart one backend, e.g. when it has wedged
+      case "$/reconnect": {
+        const name = (request.params as { name?: unknown } | undefined)?.name;
+        if (typeof name !== "string") {
+          return {
+            jsonrpc: "2.0",
+            id,
+            error: { code: ErrorCodes.INVALID_PARAMS, message: "Missing 'name' in $/reconnect params" },
+          };
+        }
+        return this.forward(id, async () => ({ tools: await this.manager.reconnect(name) }));
+      }
+
+      // Extension: dry-run a tools/call, e.g. for a UI previewing a form
+      case "$/tools/validate":
+        if (this.settings.enableValidateExtension) {
+          return this.handleToolsValidate(id, request.params as Record<string, unknown> | undefined);
+        }
+        return {
+          jsonrpc: "2.0",
+          id,
+          error: { code: ErrorCodes.METHOD_NOT_FOUND, message: `Method not found: ${request.method}` },
+        };
 
       default:
+        // Methods the bridge owns (initialize, ...) have cases above, and $/ is for its own extensions
+        if (this.settings.catchAllServer && !request.method.startsWith("$/")) {
+          return this.handleCatchAll(id, request, this.settings.catchAllServer);
+        }
         return {
           jsonrpc: "2.0",
           id,
@@ -36,35 +194,227 @@ export class Router {
     }
   }
 
-  private handleInitialize(id?: string | number): JsonRpcResponse {
+  // Hands a method the bridge doesn't know to one backend, e.g. a vendor extension or a newer
+  // MCP method, and relays its result or error as-is under the client's id
+  private async handleCatchAll(
+    id: JsonRpcId | undefined,
+    request: JsonRpcRequest,
+    server: string
+  ): Promise<JsonRpcResponse> {
+    if (id === undefined) {
+      this.manager.forwardNotification(server, request.method, request.params);
+      return { jsonrpc: "2.0", id, result: {} };
+    }
+
+    const { method, params } = request;
+    const response = await this.forward(id, () => this.manager.forwardRequest(server, method, params));
+    if (response.error) return response;
+
+    const backend = response.result as JsonRpcResponse;
+    return backend.error
+      ? { jsonrpc: "2.0", id, error: backend.error }
+      : { jsonrpc: "2.0", id, result: backend.result };
+  }
+
+  // Checks tools/call params against the tool's inputSchema without forwarding them
+  private handleToolsValidate(
+    id: JsonRpcId | undefined,
+    params: Record<string, unknown> | undefined
+  ): JsonRpcResponse {
+    if (!params?.name || typeof params.name !== "string") {
+      return {
+        jsonrpc: "2.0",
+        id,
+        error: {
+          code: ErrorCodes.INVALID_PARAMS,
+          message: "Missing 'name' in $/tools/validate params",
+        },
+      };
+    }
+
+    const tool = this.manager.listAllTools().find((t) => t.name === params.name);
+    if (!tool) {
+      return {
+        jsonrpc: "2.0",
+        id,
+        error: {
+          code: ErrorCodes.METHOD_NOT_FOUND,
+          message: `Tool '${params.name}' not found`,
+        },
+      };
+    }
+
+    // Only a missing arguments field means none; null is checked (and rejected) like any other value
+    const errors = validateAgainstSchema(params.arguments === undefined ? {} : params.arguments, tool.inputSchema);
+    return { jsonrpc: "2.0", id, result: { valid: errors.length === 0, errors } };
+  }
+
+  private handleInitialize(
+    id: JsonRpcId | undefined,
+    params: Partial<InitializeParams> | undefined,
+    session: Session
+  ): JsonRpcResponse {
+    session.clientCapabilities = params?.capabilities ?? {};
+    session.clientInfo = params?.clientInfo;
+    if (session.primary) {
+      this.manager.setClientCapabilities(session.clientCapabilities);
+      this.manager.setClientInfo(session.clientInfo);
+    }
+
+    // Merge each backend's instructions, prefixed so the model knows which tools they apply to
+    const instructions = Object.entries(this.manager.getInstructions())
+      .map(([mcpName, text]) => `[${mcpName}] ${text}`)
+      .join("\n\n");
+
     return {
       jsonrpc: "2.0",
       id,
       result: {
-        protocolVersion: "2024-11-05",
-        capabilities: {
-          tools: { listChanged: false },
-        },
+        protocolVersion: PROTOCOL_VERSION,
+        capabilities: filterCapabilities(this.manager.getCapabilities(), this.settings.advertiseCapabilities),
         serverInfo: {
           name: "mcp-central",
           version: "0.1.0",
         },
+        instructions: instructions || undefined,
       },
     };
   }
 
-  private handleToolsList(id?: string | number): JsonRpcResponse {
+  private handleToolsList(id: JsonRpcId | undefined, params: PaginatedParams | undefined): JsonRpcResponse {
     const tools = this.manager.listAllTools();
+    const pageSize = this.settings.toolsPageSize;
+
+    if (!pageSize || pageSize <= 0) {
+      return {
+        jsonrpc: "2.0",
+        id,
+        result: { tools },
+      };
+    }
+
+    const start = params?.cursor ? decodeCursor(params.cursor) : 0;
+    if (start === null || start > tools.length) {
+      return {
+        jsonrpc: "2.0",
+        id,
+        error: {
+          code: ErrorCodes.INVALID_PARAMS,
+          message: `Invalid cursor: ${params?.cursor}`,
+        },
+      };
+    }
+
+    const end = start + pageSize;
     return {
       jsonrpc: "2.0",
       id,
-      result: { tools },
+      result: {
+        tools: tools.slice(start, end),
+        nextCursor: end < tools.length ? encodeCursor(end) : undefined,
+      },
+    };
+  }
+
+  private async handlePing(id: JsonRpcId | undefined): Promise<JsonRpcResponse> {
+    const mode = this.settings.pingChecksBackends;
+    if (!mode) {
+      return { jsonrpc: "2.0", id, result: {} };
+    }
+
+    const names = this.manager.getConnectedMcps();
+    const results = await Promise.allSettled(names.map((name) => this.manager.ping(name)));
+    const failed = names.filter((_, i) => results[i].status === "rejected");
+
+    const healthy = mode === "all" ? failed.length === 0 : failed.length < names.length;
+    if (names.length > 0 && healthy) {
+      return { jsonrpc: "2.0", id, result: {} };
+    }
+
+    return {
+      jsonrpc: "2.0",
+      id,
+      error: {
+        code: ErrorCodes.INTERNAL_ERROR,
+        message: names.length === 0 ? "No MCP servers connected" : `MCP servers not responding: ${failed.join(", ")}`,
+        data: { failed },
+      },
     };
   }
 
+  private async handleComplete(
+    id: JsonRpcId | undefined,
+    params: CompleteParams | undefined
+  ): Promise<JsonRpcResponse> {
+    if (!params?.ref || !params.argument) {
+      return {
+        jsonrpc: "2.0",
+        id,
+        error: {
+          code: ErrorCodes.INVALID_PARAMS,
+          message: "Missing 'ref' or 'argument' in completion/complete params",
+        },
+      };
+    }
+
+    return this.forward(id, () => this.manager.complete(params));
+  }
+
+  private async handleResourceRequest(
+    id: JsonRpcId | undefined,
+    method: string,
+    params: ResourceParams | undefined,
+    session: Session
+  ): Promise<JsonRpcResponse> {
+    if (!params?.uri || typeof params.uri !== "string") {
+      return {
+        jsonrpc: "2.0",
+        id,
+        error: {
+          code: ErrorCodes.INVALID_PARAMS,
+          message: `Missing 'uri' in ${method} params`,
+        },
+      };
+    }
+
+    const uri = params.uri;
+    switch (method) {
+      case "resources/read":
+        return this.forward(id, () => this.manager.readResource(uri, params._meta, session.channel));
+      case "resources/subscribe":
+        return this.forward(id, async () => {
+          await this.manager.subscribeResource(uri, session.channel);
+          return {};
+        });
+      default:
+        return this.forward(id, async () => {
+          await this.manager.unsubscribeResource(uri, session.channel);
+          return {};
+        });
+    }
+  }
+
+  // Runs a manager call, mapping thrown errors onto JSON-RPC error responses
+  private async forward(id: JsonRpcId | undefined, call: () => Promise<unknown>): Promise<JsonRpcResponse> {
+    try {
+      const result = await call();
+      return { jsonrpc: "2.0", id, result };
+    } catch (e) {
+      return {
+        jsonrpc: "2.0",
+        id,
+        error: {
+          code: e instanceof McpError ? e.code : ErrorCodes.INTERNAL_ERROR,
+          message: e instanceof Error ? e.message : String(e),
+        },
+      };
+    }
+  }
+
   private async handleToolsCall(
-    id: string | number | undefined,
-    params: Record<string, unknown> | undefined
+    id: JsonRpcId | undefined,
+    params: Record<string, unknown> | undefined,
+    session: Session
   ): Promise<JsonRpcResponse> {
     if (!params?.name || typeof params.name !== "string") {
       return {
@@ -77,21 +427,99 @@ export class Router {
       };
     }
 
+    // Tools take named arguments; anything else (null included) would otherwise reach the backend as garbage
+    const args = params.arguments === undefined ? {} : params.arguments;
+    if (typeof args !== "object" || args === null || Array.isArray(args)) {
+      return {
+        jsonrpc: "2.0",
+        id,
+        error: {
+          code: ErrorCodes.INVALID_PARAMS,
+          message: "'arguments' in tools/call params must be an object",
+        },
+      };
+    }
+
+    const controller = new AbortController();
+    if (id !== undefined) session.track(id, controller);
+
     try {
       const result = await this.manager.callTool(
         params.name,
-        (params.arguments as Record<string, unknown>) ?? {}
+        args as Record<string, unknown>,
+        controller.signal,
+        params._meta as RequestMeta | undefined,
+        session.channel
       );
+
+      if (result.isError && this.settings.errorContentAsJsonRpcError) {
+        const text = result.content
+          .filter((c) => c.type === "text")
+          .map((c) => (c as { text: string }).text)
+          .join("\n");
+        return {
+          jsonrpc: "2.0",
+          id,
+          error: {
+            code: ErrorCodes.INTERNAL_ERROR,
+            message: text || "Tool returned an error",
+            data: result,
+          },
+        };
+      }
+
       return { jsonrpc: "2.0", id, result };
     } catch (e) {
       return {
         jsonrpc: "2.0",
         id,
         error: {
-          code: ErrorCodes.INTERNAL_ERROR,
+          code: e instanceof McpError ? e.code : ErrorCodes.INTERNAL_ERROR,
           message: e instanceof Error ? e.message : String(e),
         },
       };
+    } finally {
+      if (id !== undefined) session.untrack(id, controller);
     }
   }
 }
+
+const METHOD_CAPABILITIES: Record<string, CapabilityName> = {
+  tools: "tools",
+  resources: "resources",
+  prompts: "prompts",
+  logging: "logging",
+  completion: "completions",
+};
+
+function capabilityForMethod(method: unknown): CapabilityName | undefined {
+  if (typeof method !== "string") return undefined;
+  return METHOD_CAPABILITIES[method.split("/")[0]];
+}
+
+function isAdvertised(capability: CapabilityName, allowed: CapabilityName[] | undefined): boolean {
+  return allowed === undefined || allowed.includes(capability);
+}
+
+// Drops capabilities the operator chose not to expose. Backends decide what's supported at all.
+export function filterCapabilities(
+  capabilities: ServerCapabilities,
+  allowed: CapabilityName[] | undefined
+): ServerCapabilities {
+  if (allowed === undefined) return capabilities;
+
+  return Object.fromEntries(
+    Object.entries(capabilities).filter(([name]) => allowed.includes(name as CapabilityName))
+  ) as ServerCapabilities;
+}
+
+// Cursors are opaque to clients; we just encode the offset into the tool list
+
+function encodeCursor(offset: number): string {
+  return Buffer.from(String(offset)).toString("base64");
+}
+
+function decodeCursor(cursor: string): number | null {
+  const offset = Number(Buffer.from(cursor, "base64").toString());
+  return Number.isInteger(offset) && offset >= 0 ? offset : null;
+}
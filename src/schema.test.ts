@@ -0,0 +1,45 @@
+import { describe, it, expect } from "vitest";
+import { validateAgainstSchema } from "./schema.js";
+
+describe("validateAgainstSchema", () => {
+  it("should accept values matching the schema", () => {
+    const schema = {
+      type: "object",
+      properties: {
+        mode: { enum: ["fast", "slow"] },
+        tags: { type: "array", items: { type: "string" }, maxItems: 3 },
+        limit: { type: ["integer", "null"] },
+      },
+    };
+
+    expect(validateAgainstSchema({ mode: "fast", tags: ["a"], limit: null }, schema)).toEqual([]);
+  });
+
+  it("should report type mismatches with their path", () => {
+    const schema = { type: "object", properties: { tags: { type: "array", items: { type: "string" } } } };
+
+    expect(validateAgainstSchema({ tags: ["a", 2] }, schema)).toEqual(["arguments.tags[1]: expected string, got number"]);
+    expect(validateAgainstSchema({ tags: "a" }, schema)).toEqual(["arguments.tags: expected array, got string"]);
+    expect(validateAgainstSchema({ n: 1.5 }, { properties: { n: { type: "integer" } } })).toEqual([
+      "arguments.n: expected integer, got number",
+    ]);
+  });
+
+  it("should check enums, bounds and extra properties", () => {
+    const schema = {
+      type: "object",
+      properties: { mode: { enum: ["fast"] }, name: { type: "string", minLength: 2 } },
+      additionalProperties: false,
+    };
+
+    expect(validateAgainstSchema({ mode: "slow", name: "a", extra: true }, schema)).toEqual([
+      'arguments.mode: must be one of "fast"',
+      "arguments.name: must be at least 2 characters",
+      "arguments.extra: is not allowed",
+    ]);
+  });
+
+  it("should accept anything for an empty schema", () => {
+    expect(validateAgainstSchema({ anything: [1, { a: 2 }] }, {})).toEqual([]);
+  });
+});
@@ -0,0 +1,96 @@
+// The subset of JSON Schema that tool inputSchemas use in practice: type, enum, const,
+// properties/required/additionalProperties, items, and numeric, length and size bounds.
+// Keywords outside that subset ($ref, oneOf, pattern, ...) are not checked.
+
+type Schema = Record<string, unknown>;
+
+function typeOf(value: unknown): string {
+  if (value === null) return "null";
+  if (Array.isArray(value)) return "array";
+  return typeof value;
+}
+
+function matchesType(value: unknown, type: string): boolean {
+  switch (type) {
+    case "integer":
+      return Number.isInteger(value);
+    case "number":
+      return typeof value === "number" && Number.isFinite(value);
+    default:
+      return typeOf(value) === type;
+  }
+}
+
+function isSchema(value: unknown): value is Schema {
+  return typeof value === "object" && value !== null && !Array.isArray(value);
+}
+
+// Returns one message per problem, each prefixed with the path to the offending value
+export function validateAgainstSchema(value: unknown, schema: unknown, path = "arguments"): string[] {
+  if (!isSchema(schema)) return [];
+  const errors: string[] = [];
+
+  if (schema.type !== undefined) {
+    const types = Array.isArray(schema.type) ? (schema.type as string[]) : [schema.type as string];
+    if (!types.some((t) => matchesType(value, t))) {
+      return [`${path}: expected ${types.join(" or ")}, got ${typeOf(value)}`];
+    }
+  }
+
+  if (Array.isArray(schema.enum) && !schema.enum.some((e) => JSON.stringify(e) === JSON.stringify(value))) {
+    errors.push(`${path}: must be one of ${schema.enum.map((e) => JSON.stringify(e)).join(", ")}`);
+  }
+  if ("const" in schema && JSON.stringify(schema.const) !== JSON.stringify(value)) {
+    errors.push(`${path}: must be ${JSON.stringify(schema.const)}`);
+  }
+
+  if (typeof value === "number") {
+    if (typeof schema.minimum === "number" && value < schema.minimum) {
+      errors.push(`${path}: must be >= ${schema.minimum}`);
+    }
+    if (typeof schema.maximum === "number" && value > schema.maximum) {
+      errors.push(`${path}: must be <= ${schema.maximum}`);
+    }
+  }
+
+  if (typeof value === "string") {
+    if (typeof schema.minLength === "number" && value.length < schema.minLength) {
+      errors.push(`${path}: must be at least ${schema.minLength} characters`);
+    }
+    if (typeof schema.maxLength === "number" && value.length > schema.maxLength) {
+      errors.push(`${path}: must be at most ${schema.maxLength} characters`);
+    }
+  }
+
+  if (Array.isArray(value)) {
+    if (typeof schema.minItems === "number" && value.length < schema.minItems) {
+      errors.push(`${path}: must have at least ${schema.minItems} items`);
+    }
+    if (typeof schema.maxItems === "number" && value.length > schema.maxItems) {
+      errors.push(`${path}: must have at most ${schema.maxItems} items`);
+    }
+    if (isSchema(schema.items)) {
+      value.forEach((item, i) => errors.push(...validateAgainstSchema(item, schema.items, `${path}[${i}]`)));
+    }
+  }
+
+  if (isSchema(value)) {
+    const properties = isSchema(schema.properties) ? schema.properties : {};
+
+    for (const key of Array.isArray(schema.required) ? (schema.required as string[]) : []) {
+      if (!(key in value)) errors.push(`${path}.${key}: is required`);
+    }
+
+    for (const [key, child] of Object.entries(value)) {
+      if (key in properties) {
+        errors.push(...validateAgainstSchema(child, properties[key], `${path}.${key}`));
+      } else if (schema.additionalProperties === false) {
+        errors.push(`${path}.${key}: is not allowed`);
+      } else if (isSchema(schema.additionalProperties)) {
+        errors.push(...validateAgainstSchema(child, schema.additionalProperties, `${path}.${key}`));
+      }
+    }
+  }
+
+  return errors;
+}
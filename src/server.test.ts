@@ -0,0 +1,151 @@
+import { describe, it, expect, vi, beforeEach, afterEach } from "vitest";
+import * as net from "node:net";
+import { PassThrough, Readable } from "node:stream";
+import { handleLine, runOneShot, runStdioServer } from "./server.js";
+import { Router } from "./router.js";
+import type { McpManager } from "./manager.js";
+import type { Config } from "./types.js";
+import { ErrorCodes } from "./types.js";
+
+function createRouter() {
+  return new Router({} as McpManager);
+}
+
+async function freePort(): Promise<number> {
+  const server = net.createServer();
+  await new Promise<void>((resolve) => server.listen(0, "127.0.0.1", resolve));
+  const { port } = server.address() as net.AddressInfo;
+  await new Promise((resolve) => server.close(resolve));
+  return port;
+}
+
+describe("server", () => {
+  describe("handleLine", () => {
+    beforeEach(() => {
+      vi.spyOn(console, "error").mockImplementation(() => {});
+    });
+
+    afterEach(() => {
+      vi.restoreAllMocks();
+    });
+
+    it("should answer valid requests", async () => {
+      const output = await handleLine(createRouter(), JSON.stringify({ jsonrpc: "2.0", id: 1, method: "ping" }));
+
+      expect(JSON.parse(output!)).toEqual({ jsonrpc: "2.0", id: 1, result: {} });
+    });
+
+    it("should echo string ids unchanged", async () => {
+      const output = await handleLine(createRouter(), JSON.stringify({ jsonrpc: "2.0", id: "1", method: "ping" }));
+
+      expect(JSON.parse(output!).id).toBe("1");
+    });
+
+    it("should echo string ids unchanged on errors", async () => {
+      const output = await handleLine(createRouter(), JSON.stringify({ jsonrpc: "2.0", id: "abc", method: "nope" }));
+
+      expect(JSON.parse(output!)).toMatchObject({ id: "abc", error: { code: ErrorCodes.METHOD_NOT_FOUND } });
+    });
+
+    it("should return a parse error for malformed JSON", async () => {
+      const output = await handleLine(createRouter(), `{"jsonrpc": "2.0", "id": 1, "method":`);
+
+      expect(JSON.parse(output!)).toMatchObject({ id: null, error: { code: ErrorCodes.PARSE_ERROR } });
+    });
+
+    it("should return invalid request for JSON without a method", async () => {
+      const output = await handleLine(createRouter(), JSON.stringify({ jsonrpc: "2.0", id: 7, params: {} }));
+
+      expect(JSON.parse(output!)).toEqual({
+        jsonrpc: "2.0",
+        id: 7,
+        error: { code: ErrorCodes.INVALID_REQUEST, message: "Invalid request: missing 'method'" },
+      });
+    });
+
+    it("should return invalid request for JSON that isn't an object", async () => {
+      const output = await handleLine(createRouter(), "[1, 2, 3]");
+
+      expect(JSON.parse(output!)).toMatchObject({ id: null, error: { code: ErrorCodes.INVALID_REQUEST } });
+    });
+
+    it("should ignore responses that don't match an upstream request", async () => {
+      const output = await handleLine(createRouter(), JSON.stringify({ jsonrpc: "2.0", id: "x", result: {} }));
+
+      expect(output).toBeNull();
+    });
+  });
+
+  describe("runStdioServer", () => {
+    const config: Config = { settings: { logLevel: "info", daemonPort: 3000 }, servers: [] };
+
+    beforeEach(() => {
+      vi.spyOn(console, "error").mockImplementation(() => {});
+    });
+
+    afterEach(() => {
+      vi.restoreAllMocks();
+    });
+
+    it("should reopen stdin after EOF with keepAlive", async () => {
+      const log = vi.spyOn(console, "log").mockImplementation(() => {});
+      const request = (id: number) => `${JSON.stringify({ jsonrpc: "2.0", id, method: "ping" })}\n`;
+      const inputs = [Readable.from([request(1)]), Readable.from([request(2)]), new PassThrough()];
+
+      await runStdioServer(config, {}, { keepAlive: true, openInput: () => inputs.shift()! });
+
+      await vi.waitFor(() => expect(log).toHaveBeenCalledTimes(2), { timeout: 3000 });
+      expect(log.mock.calls.map(([line]) => JSON.parse(line).id)).toEqual([1, 2]);
+    });
+
+    it("should serve HTTP on httpPort until stdin closes", async () => {
+      const exit = vi.spyOn(process, "exit").mockImplementation(() => undefined as never);
+      const port = await freePort();
+      const input = new PassThrough();
+      const ping = () =>
+        fetch(`http://127.0.0.1:${port}/mcp`, {
+          method: "POST",
+          headers: { "content-type": "application/json" },
+          body: JSON.stringify({ jsonrpc: "2.0", id: 1, method: "ping" }),
+        });
+
+      await runStdioServer(config, {}, { httpPort: port, openInput: () => input });
+
+      expect(await (await ping()).json()).toEqual({ jsonrpc: "2.0", id: 1, result: {} });
+
+      input.end();
+      await vi.waitFor(() => expect(exit).toHaveBeenCalledWith(0));
+      await expect(ping()).rejects.toThrow();
+    });
+  });
+
+  describe("runOneShot", () => {
+    const config: Config = { settings: { logLevel: "info", daemonPort: 3000 }, servers: [] };
+
+    beforeEach(() => {
+      vi.spyOn(console, "error").mockImplementation(() => {});
+    });
+
+    afterEach(() => {
+      vi.restoreAllMocks();
+    });
+
+    it("should print the response and succeed", async () => {
+      const log = vi.spyOn(console, "log").mockImplementation(() => {});
+
+      const ok = await runOneShot(config, JSON.stringify({ jsonrpc: "2.0", id: 1, method: "tools/list" }));
+
+      expect(ok).toBe(true);
+      expect(JSON.parse(log.mock.calls[0][0])).toEqual({ jsonrpc: "2.0", id: 1, result: { tools: [] } });
+    });
+
+    it("should print a parse error and fail for malformed JSON", async () => {
+      const log = vi.spyOn(console, "log").mockImplementation(() => {});
+
+      const ok = await runOneShot(config, "{not json");
+
+      expect(ok).toBe(false);
+      expect(JSON.parse(log.mock.calls[0][0]).error.code).toBe(ErrorCodes.PARSE_ERROR);
+    });
+  });
+});
@@ -1,13 +1,161 @@
+import * as fs from "node:fs";
 import * as readline from "node:readline";
 import type { Config } from "./types.js";
+import type { ServerFilter } from "./config.js";
 import { McpManager } from "./manager.js";
-import { Router } from "./router.js";
-import type { JsonRpcRequest } from "./types.js";
+import { Router, type Session } from "./router.js";
+import type { JsonRpcId, JsonRpcRequest, JsonRpcResponse } from "./types.js";
 import { ErrorCodes } from "./types.js";
 
-export async function runStdioServer(config: Config): Promise<void> {
-  const manager = new McpManager();
-  await manager.connectAll(config);
+// Requests the bridge sends to the upstream client on a backend's behalf (e.g. roots/list),
+// matched to responses arriving on the same stream. String ids keep them apart from the client's.
+export class UpstreamRequests {
+  private pending = new Map<string, (response: JsonRpcResponse) => void>();
+  private nextId = 1;
+
+  constructor(private write: (message: string) => void) {}
+
+  request(method: string, params?: unknown): Promise<JsonRpcResponse> {
+    const id = `mcp-central-${this.nextId++}`;
+
+    return new Promise((resolve, reject) => {
+      this.pending.set(id, resolve);
+      this.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }));
+
+      setTimeout(() => {
+        if (this.pending.delete(id)) {
+          reject(new Error(`Upstream request timeout: ${method}`));
+        }
+      }, 30000);
+    });
+  }
+
+  // Returns true if the message answered one of our requests
+  handleResponse(response: JsonRpcResponse): boolean {
+    if (typeof response.id !== "string") return false;
+
+    const resolve = this.pending.get(response.id);
+    if (!resolve) return false;
+
+    this.pending.delete(response.id);
+    resolve(response);
+    return true;
+  }
+}
+
+// Handles one newline-delimited JSON-RPC message, returning the serialized
+// response to write back (or null for notifications and blank lines)
+export async function handleLine(
+  router: Router,
+  line: string,
+  upstream?: UpstreamRequests,
+  session?: Session
+): Promise<string | null> {
+  if (!line.trim()) return null;
+
+  let message: unknown;
+  try {
+    message = JSON.parse(line);
+  } catch (e) {
+    console.error("Failed to parse request:", line);
+    return JSON.stringify(parseErrorResponse(e));
+  }
+
+  // Replies from the client to requests we sent it (e.g. roots/list)
+  if (isResponse(message)) {
+    if (!upstream?.handleResponse(message)) {
+      console.error("Ignoring response to an unknown request:", line);
+    }
+    return null;
+  }
+
+  const invalid = invalidRequestResponse(message);
+  if (invalid) {
+    console.error("Invalid request:", line);
+    return JSON.stringify(invalid);
+  }
+
+  const request = message as JsonRpcRequest;
+  const isNotification = request.id === undefined;
+
+  const response = await router.handleRequest(request, session);
+
+  return isNotification ? null : JSON.stringify(response);
+}
+
+function parseErrorResponse(e: unknown): JsonRpcResponse {
+  return {
+    jsonrpc: "2.0",
+    id: null,
+    error: {
+      code: ErrorCodes.PARSE_ERROR,
+      message: `Parse error: ${e instanceof Error ? e.message : String(e)}`,
+    },
+  };
+}
+
+function isResponse(message: unknown): message is JsonRpcResponse {
+  if (typeof message !== "object" || message === null || "method" in message) return false;
+  return "result" in message || "error" in message;
+}
+
+// The INVALID_REQUEST error to answer a parsed message that isn't a JSON-RPC request with,
+// or null if it is one
+export function invalidRequestResponse(message: unknown): JsonRpcResponse | null {
+  const invalid = validateRequest(message);
+  if (!invalid) return null;
+
+  return {
+    jsonrpc: "2.0",
+    id: validId(message) ?? null,
+    error: { code: ErrorCodes.INVALID_REQUEST, message: `Invalid request: ${invalid}` },
+  };
+}
+
+// Returns why a parsed message isn't a JSON-RPC request, or null if it is one
+function validateRequest(message: unknown): string | null {
+  if (typeof message !== "object" || message === null || Array.isArray(message)) {
+    return "expected a JSON object";
+  }
+
+  const request = message as Record<string, unknown>;
+  if (request.jsonrpc !== "2.0") {
+    return "'jsonrpc' must be \"2.0\"";
+  }
+  if (typeof request.method !== "string") {
+    return "missing 'method'";
+  }
+  if (request.id !== undefined && validId(message) === undefined) {
+    return "'id' must be a string or number";
+  }
+  return null;
+}
+
+function validId(message: unknown): JsonRpcId | undefined {
+  const id = (message as { id?: unknown } | null)?.id;
+  return typeof id === "string" || typeof id === "number" ? id : undefined;
+}
+
+// Wait before reopening stdin after EOF in keep-alive mode
+const KEEP_ALIVE_REOPEN_MS = 1000;
+
+export interface StdioServerOptions {
+  // On EOF, reopen stdin and keep serving instead of shutting down, for supervisors that
+  // briefly detach it. Without this (the default) a closed stdin stops the bridge.
+  keepAlive?: boolean;
+  // Also serve the daemon's HTTP endpoints on this port, sharing the same backends
+  httpPort?: number;
+  // Opens stdin, and again after each EOF in keep-alive mode; tests pass their own
+  openInput?: () => NodeJS.ReadableStream;
+}
+
+export async function runStdioServer(
+  config: Config,
+  filter: ServerFilter = {},
+  options: StdioServerOptions = {}
+): Promise<void> {
+  const manager = new McpManager(config.settings);
+  await manager.connectAll(config, filter);
 
   const connected = manager.getConnectedMcps();
   if (connected.length === 0) {
@@ -16,42 +164,93 @@ export async function runStdioServer(config: Config): Promise<void> {
     console.error(`Connected to ${connected.length} MCP servers: ${connected.join(", ")}`);
   }
 
-  const router = new Router(manager);
+  // Relay backend notifications (e.g. resource updates) to the client
+  const relay = (notification: JsonRpcRequest) => {
+    console.log(JSON.stringify(notification));
+  };
+  manager.onNotification(relay);
+  const router = new Router(manager, config.settings, relay);
 
-  const rl = readline.createInterface({ input: process.stdin });
+  const upstream = new UpstreamRequests((message) => console.log(message));
+  manager.setUpstream((method, params) => upstream.request(method, params));
 
-  console.error("MCP bridge ready, waiting for requests on stdin");
+  // Imported here so stdio-only runs don't need fastify
+  let http: { close: () => Promise<unknown> } | null = null;
+  if (options.httpPort !== undefined) {
+    const { createDaemonApp } = await import("./daemon.js");
+    const app = await createDaemonApp(manager, router, config.settings);
+    await app.listen({ port: options.httpPort, host: "0.0.0.0" });
+    http = app;
+    const scheme = config.settings.tlsCert ? "https" : "http";
+    console.error(`Also listening on ${scheme}://0.0.0.0:${options.httpPort}`);
+  }
+
+  let reopening = false;
+  const openInput =
+    options.openInput ??
+    (() => {
+      if (!reopening) {
+        reopening = true;
+        return process.stdin;
+      }
+      return fs.createReadStream("/dev/stdin");
+    });
 
-  rl.on("line", async (line) => {
-    if (!line.trim()) return;
+  const listen = (input: NodeJS.ReadableStream) => {
+    const rl = readline.createInterface({ input });
 
-    try {
-      const request = JSON.parse(line) as JsonRpcRequest;
-      const isNotification = request.id === undefined;
+    rl.on("line", async (line) => {
+      const output = await handleLine(router, line, upstream);
+      if (output) {
+        console.log(output);
+      }
+    });
 
-      const response = await router.handleRequest(request);
+    // e.g. /dev/stdin can't be opened while nothing is attached; closing retries in keep-alive mode
+    rl.on("error", (e) => {
+      console.error("stdin error:", e instanceof Error ? e.message : e);
+      rl.close();
+    });
 
-      if (!isNotification) {
-        console.log(JSON.stringify(response));
+    rl.on("close", async () => {
+      if (options.keepAlive) {
+        console.error(`stdin closed, reopening in ${KEEP_ALIVE_REOPEN_MS}ms (--keep-alive)`);
+        setTimeout(() => listen(openInput()), KEEP_ALIVE_REOPEN_MS);
+        return;
       }
-    } catch (e) {
-      console.error("Failed to parse request:", line);
-      console.log(
-        JSON.stringify({
-          jsonrpc: "2.0",
-          id: null,
-          error: {
-            code: ErrorCodes.PARSE_ERROR,
-            message: `Parse error: ${e instanceof Error ? e.message : String(e)}`,
-          },
-        })
-      );
-    }
-  });
 
-  rl.on("close", async () => {
-    console.error("stdin closed, shutting down");
+      console.error("stdin closed, shutting down");
+      await http?.close();
+      await manager.shutdownAll();
+      process.exit(0);
+    });
+  };
+
+  console.error("MCP bridge ready, waiting for requests on stdin");
+  listen(openInput());
+}
+
+// Handles a single request passed on the command line and prints the response.
+// Returns false if the request couldn't be parsed or the response is an error.
+export async function runOneShot(config: Config, json: string, filter: ServerFilter = {}): Promise<boolean> {
+  // Report malformed input before spawning any servers
+  try {
+    JSON.parse(json);
+  } catch (e) {
+    console.log(JSON.stringify(parseErrorResponse(e)));
+    return false;
+  }
+
+  const manager = new McpManager(config.settings);
+  await manager.connectAll(config, filter);
+
+  try {
+    const output = await handleLine(new Router(manager, config.settings), json);
+    if (!output) return true;
+
+    console.log(output);
+    return !(JSON.parse(output) as JsonRpcResponse).error;
+  } finally {
     await manager.shutdownAll();
-    process.exit(0);
-  });
+  }
 }
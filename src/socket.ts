@@ -0,0 +1,76 @@
+import * as fs from "node:fs";
+import * as net from "node:net";
+import * as readline from "node:readline";
+import type { Config, JsonRpcRequest } from "./types.js";
+import type { ServerFilter } from "./config.js";
+import { McpManager } from "./manager.js";
+import { Router, Session } from "./router.js";
+import { handleLine } from "./server.js";
+
+export async function runSocketServer(config: Config, socketPath: string, filter: ServerFilter = {}): Promise<void> {
+  const manager = new McpManager(config.settings);
+  await manager.connectAll(config, filter);
+
+  const connected = manager.getConnectedMcps();
+  if (connected.length === 0) {
+    console.error("No MCP servers connected. Add servers with 'mcp-central add'");
+  } else {
+    console.error(`Connected to ${connected.length} MCP servers: ${connected.join(", ")}`);
+  }
+
+  const router = new Router(manager, config.settings);
+
+  // Each connection is its own newline-delimited JSON-RPC session sharing the same backends
+  const server = net.createServer((socket) => {
+    const rl = readline.createInterface({ input: socket });
+
+    const send = (notification: JsonRpcRequest) => {
+      if (socket.writable) {
+        socket.write(JSON.stringify(notification) + "\n");
+      }
+    };
+    const stopNotifications = manager.onNotification(send);
+    const session = new Session(false, send);
+    socket.on("close", () => {
+      stopNotifications();
+      session.cancelAll("Connection closed");
+      manager.closeChannel(send);
+    });
+
+    rl.on("line", async (line) => {
+      const output = await handleLine(router, line, undefined, session);
+      if (output && socket.writable) {
+        socket.write(output + "\n");
+      }
+    });
+
+    socket.on("error", (err) => {
+      console.error("Socket connection error:", err.message);
+    });
+  });
+
+  // Clear a stale socket left behind by a previous run that didn't shut down cleanly
+  if (fs.existsSync(socketPath) && fs.statSync(socketPath).isSocket()) {
+    fs.unlinkSync(socketPath);
+  }
+
+  const shutdown = async () => {
+    console.error("Shutting down");
+    server.close();
+    fs.rmSync(socketPath, { force: true });
+    await manager.shutdownAll();
+    process.exit(0);
+  };
+
+  process.on("SIGINT", shutdown);
+  process.on("SIGTERM", shutdown);
+
+  server.on("error", (err) => {
+    console.error("Failed to start socket server:", err);
+    process.exit(1);
+  });
+
+  server.listen(socketPath, () => {
+    console.error(`MCP bridge listening on unix socket ${socketPath}`);
+  });
+}
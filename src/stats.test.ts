@@ -0,0 +1,80 @@
+import { describe, it, expect, beforeEach, afterEach } from "vitest";
+import * as fs from "node:fs";
+import * as os from "node:os";
+import * as path from "node:path";
+import { UsageStatsStore, loadUsageStats } from "./stats.js";
+
+describe("stats", () => {
+  let dir: string;
+  let file: string;
+
+  beforeEach(() => {
+    dir = fs.mkdtempSync(path.join(os.tmpdir(), "mcp-central-stats-"));
+    file = path.join(dir, "stats.json");
+  });
+
+  afterEach(() => {
+    fs.rmSync(dir, { recursive: true, force: true });
+  });
+
+  it("should persist call counts across store instances", () => {
+    const store = new UsageStatsStore(file);
+    store.record("github__create_issue", true);
+    store.record("github__create_issue", false);
+    store.record("fs__read_file", true);
+    store.flush();
+
+    const reloaded = new UsageStatsStore(file);
+    reloaded.record("github__create_issue", true);
+    reloaded.flush();
+
+    const stats = loadUsageStats(file);
+    expect(stats.tools["github__create_issue"]).toMatchObject({ calls: 3, failures: 1 });
+    expect(stats.tools["fs__read_file"]).toMatchObject({ calls: 1, failures: 0 });
+  });
+
+  it("should keep the counts of two stores flushing to the same file", () => {
+    const bridge = new UsageStatsStore(file);
+    const daemon = new UsageStatsStore(file);
+
+    bridge.record("fs__read_file", true);
+    daemon.record("fs__read_file", false);
+    daemon.record("github__create_issue", true);
+    bridge.flush();
+    daemon.flush();
+    bridge.record("fs__read_file", true);
+    bridge.flush();
+
+    const stats = loadUsageStats(file);
+    expect(stats.tools["fs__read_file"]).toMatchObject({ calls: 3, failures: 1 });
+    expect(stats.tools["github__create_issue"]).toMatchObject({ calls: 1, failures: 0 });
+    expect(bridge.get()).toEqual(stats);
+  });
+
+  it("should write atomically without leaving temp files", () => {
+    const store = new UsageStatsStore(file);
+    store.record("fs__read_file", true);
+    store.flush();
+
+    expect(fs.readdirSync(dir)).toEqual(["stats.json"]);
+  });
+
+  it("should default to stats.json in the config dir", () => {
+    const originalDir = process.env.MCP_CENTRAL_CONFIG_DIR;
+    process.env.MCP_CENTRAL_CONFIG_DIR = dir;
+    try {
+      const store = new UsageStatsStore();
+      store.record("fs__read_file", true);
+      store.flush();
+    } finally {
+      process.env.MCP_CENTRAL_CONFIG_DIR = originalDir;
+    }
+
+    expect(loadUsageStats(file).tools["fs__read_file"]).toMatchObject({ calls: 1 });
+  });
+
+  it("should not write when nothing changed", () => {
+    new UsageStatsStore(file).flush();
+    expect(fs.existsSync(file)).toBe(false);
+  });
+});
@@ -0,0 +1,95 @@
+import * as fs from "node:fs";
+import * as path from "node:path";
+import { getConfigDir, withConfigLock, writeFileAtomic } from "./config.js";
+
+export interface ToolUsage {
+  calls: number;
+  failures: number;
+  lastUsed: string;
+}
+
+export interface UsageStats {
+  // Keyed by namespaced tool name
+  tools: Record<string, ToolUsage>;
+}
+
+function getStatsPath(): string {
+  return path.join(getConfigDir(), "stats.json");
+}
+
+export function loadUsageStats(filePath = getStatsPath()): UsageStats {
+  if (!fs.existsSync(filePath)) {
+    return { tools: {} };
+  }
+
+  try {
+    return JSON.parse(fs.readFileSync(filePath, "utf-8")) as UsageStats;
+  } catch {
+    console.error(`Ignoring unreadable stats file: ${filePath}`);
+    return { tools: {} };
+  }
+}
+
+// Lifetime per-tool usage. Unlike usage.log this is never rotated, so it survives
+// restarts and long histories. Writes are batched and flushed on an interval.
+export class UsageStatsStore {
+  private stats: UsageStats;
+  // Usage recorded since the last flush. A flush adds it to what's on disk then, so a stdio
+  // bridge and the daemon flushing the same file both keep their counts.
+  private pending: Record<string, ToolUsage> = {};
+  private timer: NodeJS.Timeout | null = null;
+
+  constructor(private filePath = getStatsPath()) {
+    this.stats = loadUsageStats(filePath);
+  }
+
+  record(toolName: string, success: boolean): void {
+    const lastUsed = new Date().toISOString();
+    for (const tools of [this.stats.tools, this.pending]) {
+      const usage = (tools[toolName] ??= { calls: 0, failures: 0, lastUsed: "" });
+      usage.calls++;
+      if (!success) usage.failures++;
+      usage.lastUsed = lastUsed;
+    }
+  }
+
+  get(): UsageStats {
+    return this.stats;
+  }
+
+  start(flushSecs: number): void {
+    this.stop();
+    this.timer = setInterval(() => this.flush(), flushSecs * 1000);
+    // Don't keep the process alive just to flush stats
+    this.timer.unref();
+  }
+
+  stop(): void {
+    if (this.timer) {
+      clearInterval(this.timer);
+      this.timer = null;
+    }
+  }
+
+  flush(): void {
+    if (Object.keys(this.pending).length === 0) return;
+
+    try {
+      fs.mkdirSync(path.dirname(this.filePath), { recursive: true });
+      withConfigLock(() => {
+        const stats = loadUsageStats(this.filePath);
+        for (const [toolName, delta] of Object.entries(this.pending)) {
+          const usage = (stats.tools[toolName] ??= { calls: 0, failures: 0, lastUsed: "" });
+          usage.calls += delta.calls;
+          usage.failures += delta.failures;
+          if (delta.lastUsed > usage.lastUsed) usage.lastUsed = delta.lastUsed;
+        }
+        writeFileAtomic(this.filePath, JSON.stringify(stats, null, 2));
+        this.stats = stats;
+        this.pending = {};
+      });
+    } catch (e) {
+      console.error("Failed to write usage stats:", e);
+    }
+  }
+}
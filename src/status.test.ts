@@ -0,0 +1,75 @@
+import { describe, it, expect } from "vitest";
+import * as http from "node:http";
+import type { AddressInfo } from "node:net";
+import { DaemonNotRunningError, fetchDaemonHealth, formatStatus, formatUptime, type DaemonHealth } from "./status.js";
+
+const health: DaemonHealth = {
+  status: "degraded",
+  connected: ["github"],
+  servers: {
+    github: {
+      status: "healthy",
+      latencyMs: 2,
+      tools: 12,
+      uptimeSecs: 3725,
+      restarts: 0,
+      lastError: "Request timeout: tools/call",
+      lastErrorAt: "2026-10-16T09:00:00.000Z",
+    },
+    fs: {
+      status: "dead",
+      error: "Not connected",
+      restarts: 3,
+      lastError: "command not found: fs-mcp",
+      lastErrorAt: "2026-10-16T09:30:00.000Z",
+    },
+  },
+};
+
+async function listen(handler: http.RequestListener): Promise<{ url: string; close: () => Promise<void> }> {
+  const server = http.createServer(handler);
+  await new Promise<void>((resolve) => server.listen(0, "127.0.0.1", resolve));
+  const { port } = server.address() as AddressInfo;
+  return {
+    url: `http://127.0.0.1:${port}`,
+    close: () => new Promise((resolve) => server.close(() => resolve())),
+  };
+}
+
+describe("status", () => {
+  it("should fetch the daemon's /health", async () => {
+    const daemon = await listen((req, res) => {
+      res.setHeader("content-type", "application/json");
+      res.end(req.url === "/health" ? JSON.stringify(health) : "{}");
+    });
+    try {
+      expect(await fetchDaemonHealth(`${daemon.url}/`)).toEqual(health);
+    } finally {
+      await daemon.close();
+    }
+  });
+
+  it("should report a daemon that isn't running", async () => {
+    const daemon = await listen(() => {});
+    await daemon.close();
+
+    await expect(fetchDaemonHealth(daemon.url)).rejects.toThrow(DaemonNotRunningError);
+  });
+
+  it("should print one line per server", () => {
+    expect(formatStatus("http://127.0.0.1:3000", health)).toBe(
+      [
+        "Daemon at http://127.0.0.1:3000: degraded",
+        "",
+        "  github  healthy       12 tools, up 1h 2m, 2ms",
+        "  fs      dead          3 restarts, Not connected, last error: command not found: fs-mcp",
+      ].join("\n")
+    );
+  });
+
+  it("should format uptimes", () => {
+    expect(formatUptime(42)).toBe("42s");
+    expect(formatUptime(125)).toBe("2m 5s");
+    expect(formatUptime(90000)).toBe("1d 1h");
+  });
+});
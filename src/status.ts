@@ -0,0 +1,71 @@
+import type { ServerHealth } from "./types.js";
+
+// What the daemon's GET /health returns
+export interface DaemonHealth {
+  status: "ok" | "degraded";
+  connected: string[];
+  servers: Record<string, ServerHealth>;
+}
+
+export class DaemonNotRunningError extends Error {
+  constructor(url: string) {
+    super(`Daemon not running at ${url} (start it with: mcp-central daemon)`);
+    this.name = "DaemonNotRunningError";
+  }
+}
+
+export async function fetchDaemonHealth(url: string): Promise<DaemonHealth> {
+  const healthUrl = `${url.replace(/\/+$/, "")}/health`;
+
+  let response: Response;
+  try {
+    response = await fetch(healthUrl);
+  } catch (e) {
+    const code = ((e as Error).cause as NodeJS.ErrnoException | undefined)?.code;
+    if (code === "ECONNREFUSED") throw new DaemonNotRunningError(url);
+    throw new Error(`Failed to reach ${healthUrl}: ${e instanceof Error ? e.message : e}`);
+  }
+
+  // Degraded health is still a 200; anything else isn't our daemon answering
+  if (!response.ok) {
+    throw new Error(`${healthUrl} returned HTTP ${response.status}`);
+  }
+  return (await response.json()) as DaemonHealth;
+}
+
+// 3725 -> "1h 2m", 42 -> "42s"
+export function formatUptime(secs: number): string {
+  const days = Math.floor(secs / 86400);
+  const hours = Math.floor((secs % 86400) / 3600);
+  const minutes = Math.floor((secs % 3600) / 60);
+
+  if (days > 0) return `${days}d ${hours}h`;
+  if (hours > 0) return `${hours}h ${minutes}m`;
+  if (minutes > 0) return `${minutes}m ${secs % 60}s`;
+  return `${secs}s`;
+}
+
+// One line per server: state, tool count, uptime and restarts, or why it's down
+export function formatStatus(url: string, health: DaemonHealth): string {
+  const lines = [`Daemon at ${url}: ${health.status}`, ""];
+  const entries = Object.entries(health.servers);
+  if (entries.length === 0) {
+    lines.push("  No servers connected.");
+  }
+
+  const width = Math.max(0, ...entries.map(([name]) => name.length));
+  for (const [name, server] of entries) {
+    const details: string[] = [];
+    if (server.tools !== undefined) details.push(`${server.tools} tools`);
+    if (server.uptimeSecs !== undefined) details.push(`up ${formatUptime(server.uptimeSecs)}`);
+    if (server.latencyMs !== undefined) details.push(`${server.latencyMs}ms`);
+    if (server.restarts) details.push(`${server.restarts} restarts`);
+    if (server.error) details.push(server.error);
+    // A healthy server's last error is old news
+    if (server.lastError && server.status !== "healthy") details.push(`last error: ${server.lastError}`);
+
+    lines.push(`  ${name.padEnd(width)}  ${server.status.padEnd(12)}  ${details.join(", ")}`.trimEnd());
+  }
+
+  return lines.join("\n");
+}
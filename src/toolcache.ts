@@ -0,0 +1,71 @@
+import * as crypto from "node:crypto";
+import * as fs from "node:fs";
+import * as path from "node:path";
+import { getConfigDir, writeFileAtomic } from "./config.js";
+import type { McpServerConfig, ServerCapabilities, Tool } from "./types.js";
+
+export interface CachedSchema {
+  tools: Tool[];
+  capabilities?: ServerCapabilities;
+}
+
+interface CachedServer extends CachedSchema {
+  // Changes when the server's command or args do, so stale tools aren't served
+  key: string;
+}
+
+interface ToolCacheFile {
+  servers: Record<string, CachedServer>;
+}
+
+function getToolCachePath(): string {
+  return path.join(getConfigDir(), "tools-cache.json");
+}
+
+export function serverCacheKey(config: McpServerConfig): string {
+  return crypto
+    .createHash("sha256")
+    .update(JSON.stringify([config.command, config.args]))
+    .digest("hex")
+    .slice(0, 16);
+}
+
+// Tool lists and capabilities from previous runs, so servers can be listed before
+// (or, when lazy, without) starting them
+export class ToolCache {
+  private cache: ToolCacheFile;
+
+  constructor(private filePath = getToolCachePath()) {
+    this.cache = this.load();
+  }
+
+  private load(): ToolCacheFile {
+    if (!fs.existsSync(this.filePath)) {
+      return { servers: {} };
+    }
+
+    try {
+      return JSON.parse(fs.readFileSync(this.filePath, "utf-8")) as ToolCacheFile;
+    } catch {
+      console.error(`Ignoring unreadable tool cache: ${this.filePath}`);
+      return { servers: {} };
+    }
+  }
+
+  get(config: McpServerConfig): CachedSchema | null {
+    const cached = this.cache.servers[config.name];
+    if (!cached || cached.key !== serverCacheKey(config)) return null;
+    return { tools: cached.tools, capabilities: cached.capabilities };
+  }
+
+  set(config: McpServerConfig, schema: CachedSchema): void {
+    this.cache.servers[config.name] = { key: serverCacheKey(config), ...schema };
+
+    try {
+      fs.mkdirSync(path.dirname(this.filePath), { recursive: true });
+      writeFileAtomic(this.filePath, JSON.stringify(this.cache, null, 2));
+    } catch (e) {
+      console.error("Failed to write tool cache:", e);
+    }
+  }
+}
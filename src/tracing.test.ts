@@ -0,0 +1,85 @@
+import { describe, it, expect, afterEach } from "vitest";
+import * as http from "node:http";
+import type { AddressInfo } from "node:net";
+import { initTracing, shutdownTracing, SpanKind, withSpan } from "./tracing.js";
+
+interface ExportedSpan {
+  traceId: string;
+  spanId: string;
+  parentSpanId?: string;
+  name: string;
+  kind: number;
+  attributes: { key: string; value: Record<string, unknown> }[];
+  status: { code: number; message?: string };
+}
+
+// Collector stand-in that records the spans posted to /v1/traces
+async function startCollector(): Promise<{ url: string; spans: ExportedSpan[]; close: () => void }> {
+  const spans: ExportedSpan[] = [];
+  const server = http.createServer((req, res) => {
+    let body = "";
+    req.on("data", (chunk) => (body += chunk));
+    req.on("end", () => {
+      if (req.url === "/v1/traces") {
+        for (const resourceSpans of JSON.parse(body).resourceSpans) {
+          for (const scopeSpans of resourceSpans.scopeSpans) spans.push(...scopeSpans.spans);
+        }
+      }
+      res.end("{}");
+    });
+  });
+  await new Promise<void>((resolve) => server.listen(0, "127.0.0.1", resolve));
+
+  const { port } = server.address() as AddressInfo;
+  return { url: `http://127.0.0.1:${port}`, spans, close: () => server.close() };
+}
+
+describe("tracing", () => {
+  afterEach(async () => {
+    await shutdownTracing();
+  });
+
+  it("should only run the callback when no endpoint is configured", async () => {
+    initTracing({});
+
+    const result = await withSpan("tools/list", SpanKind.SERVER, {}, async (span) => {
+      expect(span).toBeNull();
+      return 42;
+    });
+
+    expect(result).toBe(42);
+  });
+
+  it("should export nested spans with attributes and errors on shutdown", async () => {
+    const collector = await startCollector();
+    try {
+      initTracing({ otlpEndpoint: `${collector.url}/` });
+
+      await withSpan("tools/call", SpanKind.SERVER, { "rpc.method": "tools/call" }, async () => {
+        await withSpan("tools/call fs__read", SpanKind.CLIENT, { "mcp.server": "fs" }, async (span) => {
+          span!.attributes["mcp.latency_ms"] = 12;
+        });
+        await expect(
+          withSpan("tools/call fs__write", SpanKind.CLIENT, {}, async () => {
+            throw new Error("denied");
+          })
+        ).rejects.toThrow("denied");
+      });
+      await shutdownTracing();
+
+      const [read, write, request] = collector.spans;
+      expect(collector.spans.map((s) => s.name)).toEqual(["tools/call fs__read", "tools/call fs__write", "tools/call"]);
+      expect(read.traceId).toBe(request.traceId);
+      expect(read.parentSpanId).toBe(request.spanId);
+      expect(read.attributes).toEqual([
+        { key: "mcp.server", value: { stringValue: "fs" } },
+        { key: "mcp.latency_ms", value: { intValue: "12" } },
+      ]);
+      expect(write.status).toEqual({ code: 2, message: "denied" });
+      expect(request.kind).toBe(SpanKind.SERVER);
+      expect(request.parentSpanId).toBeUndefined();
+    } finally {
+      collector.close();
+    }
+  });
+});
@@ -0,0 +1,160 @@
+import { AsyncLocalStorage } from "node:async_hooks";
+import * as crypto from "node:crypto";
+import type { Settings } from "./types.js";
+
+// Optional span export to an OpenTelemetry collector over OTLP/HTTP with JSON bodies. Enabled by
+// settings.otlpEndpoint or OTEL_EXPORTER_OTLP_ENDPOINT; otherwise withSpan only runs its callback.
+
+type AttributeValue = string | number | boolean;
+
+export const SpanKind = {
+  INTERNAL: 1,
+  SERVER: 2,
+  CLIENT: 3,
+} as const;
+
+export interface Span {
+  traceId: string;
+  spanId: string;
+  parentSpanId?: string;
+  name: string;
+  kind: number;
+  startTimeMs: number;
+  attributes: Record<string, AttributeValue>;
+  // Marks the span as failed
+  error?: string;
+}
+
+const FLUSH_INTERVAL_MS = 5000;
+const MAX_BATCH_SIZE = 512;
+const SERVICE_NAME = "mcp-central";
+
+function otlpAttributes(attributes: Record<string, AttributeValue>): object[] {
+  return Object.entries(attributes).map(([key, value]) => ({
+    key,
+    value:
+      typeof value === "string"
+        ? { stringValue: value }
+        : typeof value === "boolean"
+          ? { boolValue: value }
+          : Number.isInteger(value)
+            ? { intValue: String(value) }
+            : { doubleValue: value },
+  }));
+}
+
+function otlpSpan(span: Span, endTimeMs: number): object {
+  return {
+    traceId: span.traceId,
+    spanId: span.spanId,
+    parentSpanId: span.parentSpanId,
+    name: span.name,
+    kind: span.kind,
+    startTimeUnixNano: String(BigInt(span.startTimeMs) * 1_000_000n),
+    endTimeUnixNano: String(BigInt(endTimeMs) * 1_000_000n),
+    attributes: otlpAttributes(span.attributes),
+    status: span.error ? { code: 2, message: span.error } : { code: 1 },
+  };
+}
+
+class OtlpExporter {
+  private batch: object[] = [];
+  private timer: NodeJS.Timeout;
+  private failing = false;
+
+  constructor(private url: string) {
+    this.timer = setInterval(() => void this.flush(), FLUSH_INTERVAL_MS);
+    // Don't keep the process alive just to export spans
+    this.timer.unref();
+  }
+
+  add(span: object): void {
+    this.batch.push(span);
+    if (this.batch.length >= MAX_BATCH_SIZE) void this.flush();
+  }
+
+  async flush(): Promise<void> {
+    if (this.batch.length === 0) return;
+    const spans = this.batch;
+    this.batch = [];
+
+    const body = {
+      resourceSpans: [
+        {
+          resource: { attributes: otlpAttributes({ "service.name": SERVICE_NAME }) },
+          scopeSpans: [{ scope: { name: SERVICE_NAME }, spans }],
+        },
+      ],
+    };
+
+    try {
+      const response = await fetch(this.url, {
+        method: "POST",
+        headers: { "content-type": "application/json" },
+        body: JSON.stringify(body),
+      });
+      if (!response.ok) throw new Error(`HTTP ${response.status}`);
+      this.failing = false;
+    } catch (e) {
+      // Report once per outage rather than on every flush
+      if (!this.failing) {
+        console.error(`Failed to export spans to ${this.url}:`, e instanceof Error ? e.message : e);
+      }
+      this.failing = true;
+    }
+  }
+
+  async shutdown(): Promise<void> {
+    clearInterval(this.timer);
+    await this.flush();
+  }
+}
+
+let exporter: OtlpExporter | null = null;
+const activeSpan = new AsyncLocalStorage<Span>();
+
+// Starts exporting if an endpoint is configured. The endpoint is the collector's base URL;
+// spans are posted to {endpoint}/v1/traces. Later calls are no-ops until shutdownTracing.
+export function initTracing(settings: Partial<Settings>): void {
+  const endpoint = settings.otlpEndpoint ?? process.env.OTEL_EXPORTER_OTLP_ENDPOINT;
+  if (!endpoint || exporter) return;
+  exporter = new OtlpExporter(`${endpoint.replace(/\/+$/, "")}/v1/traces`);
+}
+
+export async function shutdownTracing(): Promise<void> {
+  const current = exporter;
+  exporter = null;
+  await current?.shutdown();
+}
+
+// Runs fn as a span, nested under the span it is called within. fn gets null when tracing is off.
+// A throw marks the span as failed; fn can also set span.error for failures it returns.
+export async function withSpan<T>(
+  name: string,
+  kind: number,
+  attributes: Record<string, AttributeValue>,
+  fn: (span: Span | null) => Promise<T>
+): Promise<T> {
+  const target = exporter;
+  if (!target) return fn(null);
+
+  const parent = activeSpan.getStore();
+  const span: Span = {
+    traceId: parent?.traceId ?? crypto.randomBytes(16).toString("hex"),
+    spanId: crypto.randomBytes(8).toString("hex"),
+    parentSpanId: parent?.spanId,
+    name,
+    kind,
+    startTimeMs: Date.now(),
+    attributes: { ...attributes },
+  };
+
+  try {
+    return await activeSpan.run(span, () => fn(span));
+  } catch (e) {
+    span.error ??= e instanceof Error ? e.message : String(e);
+    throw e;
+  } finally {
+    target.add(otlpSpan(span, Date.now()));
+  }
+}
@@ -0,0 +1,49 @@
+import { describe, it, expect, beforeEach, afterEach } from "vitest";
+import * as fs from "node:fs";
+import * as os from "node:os";
+import * as path from "node:path";
+import type { McpManager } from "./manager.js";
+import { Router } from "./router.js";
+import { closeTranscript, openTranscript, type TranscriptEntry } from "./transcript.js";
+
+describe("transcript", () => {
+  let dir: string;
+  let file: string;
+
+  const readEntries = (): TranscriptEntry[] =>
+    fs.readFileSync(file, "utf-8").trim().split("\n").map((line) => JSON.parse(line));
+
+  beforeEach(() => {
+    dir = fs.mkdtempSync(path.join(os.tmpdir(), "mcp-central-transcript-"));
+    file = path.join(dir, "transcript.jsonl");
+  });
+
+  afterEach(async () => {
+    await closeTranscript();
+    fs.rmSync(dir, { recursive: true, force: true });
+  });
+
+  it("should record a request and its response", async () => {
+    openTranscript({ transcriptFile: file });
+    const router = new Router({} as McpManager);
+
+    const response = await router.handleRequest({ jsonrpc: "2.0", id: 1, method: "unknown/method" });
+    await closeTranscript();
+
+    const entries = readEntries();
+    expect(entries.map((e) => e.direction)).toEqual(["in", "out"]);
+    expect(entries[0].message).toEqual({ jsonrpc: "2.0", id: 1, method: "unknown/method" });
+    expect(entries[1].message).toEqual(response);
+    expect(Date.parse(entries[0].time)).not.toBeNaN();
+  });
+
+  it("should not record a response to a notification", async () => {
+    openTranscript({ transcriptFile: file });
+    const router = new Router({} as McpManager);
+
+    await router.handleRequest({ jsonrpc: "2.0", method: "notifications/initialized" });
+    await closeTranscript();
+
+    expect(readEntries().map((e) => e.direction)).toEqual(["in"]);
+  });
+});
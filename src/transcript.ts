@@ -0,0 +1,44 @@
+import * as fs from "node:fs";
+import * as path from "node:path";
+import { getConfigDir } from "./config.js";
+import type { Settings } from "./types.js";
+
+// Optional transcript of the JSON-RPC traffic between clients and the bridge, one JSON object
+// per line, for debugging integrations offline. Enabled by settings.transcriptFile.
+
+export type TranscriptDirection = "in" | "out";
+
+export interface TranscriptEntry {
+  time: string;
+  direction: TranscriptDirection;
+  message: unknown;
+}
+
+let stream: fs.WriteStream | null = null;
+
+// Relative paths resolve against the config dir. Later calls are no-ops until closeTranscript.
+export function openTranscript(settings: Partial<Settings>): void {
+  if (!settings.transcriptFile || stream) return;
+
+  const filePath = path.resolve(getConfigDir(), settings.transcriptFile);
+  fs.mkdirSync(path.dirname(filePath), { recursive: true });
+  // The stream queues writes, so recording never waits on the disk
+  stream = fs.createWriteStream(filePath, { flags: "a" });
+  stream.on("error", (e) => {
+    console.error(`Failed to write transcript ${filePath}:`, e.message);
+    stream = null;
+  });
+}
+
+export function recordTranscript(direction: TranscriptDirection, message: unknown): void {
+  if (!stream) return;
+  const entry: TranscriptEntry = { time: new Date().toISOString(), direction, message };
+  stream.write(JSON.stringify(entry) + "\n");
+}
+
+// Waits for queued entries to reach the file
+export async function closeTranscript(): Promise<void> {
+  const current = stream;
+  stream = null;
+  if (current) await new Promise<void>((resolve) => current.end(resolve));
+}
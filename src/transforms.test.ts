@@ -0,0 +1,17 @@
+import { describe, it, expect } from "vitest";
+import { provenanceTransform } from "./transforms.js";
+
+describe("provenanceTransform", () => {
+  it("should add the server name to the input schema without touching the original", () => {
+    const tool = { name: "fs__read", description: "[fs] Read", inputSchema: { type: "object" } };
+
+    const transformed = provenanceTransform(tool, { server: "fs", config: { name: "fs", command: "fs-mcp", args: [], env: {} } });
+
+    expect(transformed).toEqual({
+      name: "fs__read",
+      description: "[fs] Read",
+      inputSchema: { type: "object", "x-mcp-server": "fs" },
+    });
+    expect(tool.inputSchema).toEqual({ type: "object" });
+  });
+});
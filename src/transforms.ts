@@ -0,0 +1,19 @@
+import type { McpServerConfig, Tool } from "./types.js";
+
+// Hooks that rewrite tools as the bridge exposes them, after namespacing and description
+// handling. Library users register their own with McpManager.addSchemaTransform.
+
+export interface ToolSource {
+  // Server the tool came from (for a group, the member whose copy is exposed)
+  server: string;
+  config: McpServerConfig;
+}
+
+// Returns the tool to expose. Transforms must not mutate their input, which may be shared.
+export type SchemaTransform = (tool: Tool, source: ToolSource) => Tool;
+
+// Records which server a tool came from as an `x-mcp-server` field on its input schema
+export const provenanceTransform: SchemaTransform = (tool, source) => ({
+  ...tool,
+  inputSchema: { ...tool.inputSchema, "x-mcp-server": source.server },
+});
@@ -1,15 +1,19 @@
 // JSON-RPC types
 
+// Ids are echoed back exactly as received, so a string id never becomes a number or vice versa
+export type JsonRpcId = string | number;
+
 export interface JsonRpcRequest {
   jsonrpc: "2.0";
-  id?: string | number;
+  id?: JsonRpcId;
   method: string;
   params?: unknown;
 }
 
 export interface JsonRpcResponse {
   jsonrpc: "2.0";
-  id?: string | number;
+  // null when the request's id couldn't be determined (parse errors, invalid requests)
+  id?: JsonRpcId | null;
   result?: unknown;
   error?: JsonRpcError;
 }
@@ -25,7 +29,12 @@ export interface JsonRpcError {
 export interface InitializeParams {
   protocolVersion: string;
   capabilities: ClientCapabilities;
-  clientInfo: { name: string; version: string };
+  clientInfo: ClientInfo;
+}
+
+export interface ClientInfo {
+  name: string;
+  version: string;
 }
 
 export interface ClientCapabilities {
@@ -37,14 +46,19 @@ export interface InitializeResult {
   protocolVersion: string;
   capabilities: ServerCapabilities;
   serverInfo: { name: string; version: string };
+  instructions?: string;
 }
 
 export interface ServerCapabilities {
   tools?: { listChanged?: boolean };
   resources?: { subscribe?: boolean; listChanged?: boolean };
   prompts?: { listChanged?: boolean };
+  logging?: object;
+  completions?: object;
 }
 
+export type CapabilityName = keyof ServerCapabilities;
+
 export interface Tool {
   name: string;
   description?: string;
@@ -53,11 +67,25 @@ export interface Tool {
 
 export interface ToolsListResult {
   tools: Tool[];
+  nextCursor?: string;
+}
+
+export type ProgressToken = string | number;
+
+// Request metadata. A progressToken asks the server to send notifications/progress for the request.
+export interface RequestMeta {
+  progressToken?: ProgressToken;
+}
+
+export interface PaginatedParams {
+  cursor?: string;
+  _meta?: RequestMeta;
 }
 
 export interface ToolCallParams {
   name: string;
   arguments?: Record<string, unknown>;
+  _meta?: RequestMeta;
 }
 
 export interface ToolCallResult {
@@ -65,12 +93,87 @@ export interface ToolCallResult {
   isError?: boolean;
 }
 
+// Hints about who a piece of content is for and how important it is. Passed through untouched.
+export interface Annotations {
+  audience?: ("user" | "assistant")[];
+  priority?: number;
+}
+
 export type ToolContent =
-  | { type: "text"; text: string }
-  | { type: "image"; data: string; mimeType: string }
-  | { type: "resource"; resource: { uri: string; mimeType?: string; text?: string; blob?: string } };
+  | { type: "text"; text: string; annotations?: Annotations }
+  | { type: "image"; data: string; mimeType: string; annotations?: Annotations }
+  | {
+      type: "resource";
+      resource: { uri: string; mimeType?: string; text?: string; blob?: string };
+      annotations?: Annotations;
+    };
+
+export interface Resource {
+  uri: string;
+  name: string;
+  description?: string;
+  mimeType?: string;
+  annotations?: Annotations;
+}
+
+export interface ResourcesListResult {
+  resources: Resource[];
+  nextCursor?: string;
+}
+
+export interface ResourceTemplate {
+  uriTemplate: string;
+  name: string;
+  description?: string;
+  mimeType?: string;
+  annotations?: Annotations;
+}
+
+export interface ResourceTemplatesListResult {
+  resourceTemplates: ResourceTemplate[];
+  nextCursor?: string;
+}
+
+export interface ResourceContents {
+  uri: string;
+  mimeType?: string;
+  text?: string;
+  blob?: string;
+  annotations?: Annotations;
+}
+
+export interface ReadResourceResult {
+  contents: ResourceContents[];
+}
+
+// Params for resources/read, resources/subscribe and resources/unsubscribe
+export interface ResourceParams {
+  uri: string;
+  _meta?: RequestMeta;
+}
+
+export type CompletionRef =
+  | { type: "ref/prompt"; name: string }
+  | { type: "ref/resource"; uri: string };
+
+export interface CompleteParams {
+  ref: CompletionRef;
+  argument: { name: string; value: string };
+}
+
+export interface CompletionResult {
+  completion: {
+    values: string[];
+    total?: number;
+    hasMore?: boolean;
+  };
+}
 
 // Error codes
+// Revision we speak to clients and, unless a server overrides it, to backends
+export const PROTOCOL_VERSION = "2024-11-05";
+export const KNOWN_PROTOCOL_VERSIONS = ["2024-11-05", "2025-03-26", "2025-06-18"];
+
 export const ErrorCodes = {
   PARSE_ERROR: -32700,
   INVALID_REQUEST: -32600,
@@ -79,16 +182,171 @@ export const ErrorCodes = {
   INTERNAL_ERROR: -32603,
 } as const;
 
+// An error that maps onto a specific JSON-RPC error code when returned to clients
+export class McpError extends Error {
+  constructor(
+    readonly code: number,
+    message: string
+  ) {
+    super(message);
+    this.name = "McpError";
+  }
+}
+
 // Config types
 
 export interface Config {
   settings: Settings;
   servers: McpServerConfig[];
+  // More files whose servers are added to these, relative to the config dir. Names must stay
+  // unique across all of them.
+  include?: string[];
 }
 
 export interface Settings {
   logLevel: string;
   daemonPort: number;
+  // Max tools per tools/list page sent to clients (unset = everything in one page)
+  toolsPageSize?: number;
+  // Return tool results with isError: true as JSON-RPC errors instead of passing them through
+  errorContentAsJsonRpcError?: boolean;
+  // Re-list tools this many times on connect while a server reports none (default 0)
+  toolsReadyRetries?: number;
+  toolsReadyDelayMs?: number;
+  // PEM certificate and key; when both are set the daemon serves HTTPS
+  tlsCert?: string;
+  tlsKey?: string;
+  // Origins browsers may call the daemon from, e.g. ["https://app.example.com"] or ["*"] (default: localhost)
+  corsOrigins?: string[];
+  // Max HTTP request body for the daemon (default 10 MiB). Base64 arguments are ~4/3 their raw size.
+  maxBodyBytes?: number;
+  // Persist per-tool call counts to stats.json (default true, flushed every 30s)
+  statsEnabled?: boolean;
+  statsFlushSecs?: number;
+  // Fail startup instead of warning when two tools would be exposed under the same name
+  strictToolNames?: boolean;
+  // Advertise roots to backends and answer their roots/list by asking the upstream client (stdio mode)
+  forwardRoots?: boolean;
+  // Default backend request timeout (default 30). Overridden by --timeout, then per-server timeoutSecs.
+  requestTimeoutSecs?: number;
+  // How long a backend gets to exit after SIGTERM on shutdown before it is SIGKILLed (default 5000)
+  shutdownTimeoutMs?: number;
+  // When a reconnect replaces a server with an edited config, how long calls already running on
+  // the old process get to finish before it is stopped (default 30000)
+  drainTimeoutMs?: number;
+  // How long to wait for a backend to answer initialize before killing it (default: the request
+  // timeout). Per-server initTimeoutSecs wins.
+  initTimeoutSecs?: number;
+  // Answer client pings by pinging backends: "any" needs one to respond, "all" needs every one.
+  // Unset answers locally without touching backends.
+  pingChecksBackends?: "any" | "all";
+  // clientInfo sent to backends in initialize (default mcp-central / 0.1.0)
+  clientName?: string;
+  clientVersion?: string;
+  // Present the upstream client's clientInfo to backends that connect after it initializes.
+  // Only the stdio client's: socket, WebSocket and HTTP clients are many, so theirs isn't used.
+  forwardClientInfo?: boolean;
+  // Capabilities offered to clients, intersected with what backends support (default: all).
+  // Methods of capabilities left out are rejected as not found.
+  advertiseCapabilities?: CapabilityName[];
+  // Remember every server's tools and capabilities in tools-cache.json, list them from there at
+  // startup and connect in the background (default false; lazy servers always use the cache)
+  schemaCache?: boolean;
+  // In isError results, replace mentions of the backend's tool name with the namespaced name
+  // clients call it by (default false, since it rewrites tool output)
+  namespaceErrorText?: boolean;
+  // Answer the non-standard $/tools/validate request, which checks arguments against a tool's
+  // inputSchema without calling it (default false)
+  enableValidateExtension?: boolean;
+  // Server to forward requests for methods the bridge doesn't handle to, instead of answering
+  // method not found (default: none)
+  catchAllServer?: string;
+  // Truncate tool descriptions (including the [server] prefix) longer than this many characters,
+  // ending them with an ellipsis (default: no limit). Per-server maxDescriptionLength wins.
+  maxDescriptionLength?: number;
+  // Accept a bare tool name in tools/call when exactly one server has a tool by that name
+  // (default false). Namespaced names are always resolved as such.
+  allowShortNames?: boolean;
+  // Expose at most this many tools, keeping those of higher-priority servers (default: no limit).
+  // Tools left out can still be called by name
+  maxExposedTools?: number;
+  // Treat non-JSON lines on a backend's stdout as its log output (kept in its logFile, echoed at
+  // logLevel "debug") instead of warning about each one (default false)
+  tolerateStdoutLogs?: boolean;
+  // Add an `x-mcp-server` field naming the source server to every exposed tool's inputSchema
+  // (default false)
+  schemaProvenance?: boolean;
+  // Append every client request and bridge response to this file as JSON lines, for debugging
+  // (relative paths resolve against the config dir; default: off). Tool arguments and results are
+  // written as-is, so the file can hold whatever secrets they carry.
+  transcriptFile?: string;
+  // OpenTelemetry collector to export request and tool call spans to over OTLP/HTTP, e.g.
+  // http://localhost:4318 (default: OTEL_EXPORTER_OTLP_ENDPOINT, or no export)
+  otlpEndpoint?: string;
+  // Ping every connected server this often and restart ones that are dead or stop answering
+  // (default: off)
+  healthCheckSecs?: number;
+  // Backoff between failed health check restarts of a server: reconnectInitialMs (default 1000)
+  // doubling per failure up to reconnectMaxMs (default 60000), each wait shortened by a random
+  // fraction of up to reconnectJitter (default 0.2) so servers don't retry in lockstep
+  reconnectInitialMs?: number;
+  reconnectMaxMs?: number;
+  reconnectJitter?: number;
+  // Stop restarting a server after this many failures in a row, until it is reconnected by hand
+  // (default: no limit)
+  reconnectMaxAttempts?: number;
+  // Per-group options, keyed by group name
+  groups?: Record<string, GroupSettings>;
+}
+
+// failover: always try members in order. The others spread calls and still fail over on error.
+export type LoadBalanceStrategy = "failover" | "round_robin" | "random" | "least_busy";
+
+// dead: not connected or its process exited. unresponsive: running but didn't answer a ping in time.
+export type HealthStatus = "healthy" | "unresponsive" | "dead";
+
+export interface ServerHealth {
+  status: HealthStatus;
+  latencyMs?: number;
+  error?: string;
+  // Tools the running process provides
+  tools?: number;
+  // Seconds since the running process finished initialize
+  uptimeSecs?: number;
+  // Times the server has been reconnected, by hand or by health checks. High restarts with low
+  // uptime means it keeps falling over.
+  restarts?: number;
+  // Why the server last failed to start, answer a call, or stay up, and when (ISO 8601). Kept
+  // after it recovers.
+  lastError?: string;
+  lastErrorAt?: string;
+  // Health check restarts failing in a row, when the next one is due (ISO 8601), and whether
+  // they've stopped at reconnectMaxAttempts
+  backoff?: { failures: number; nextAttemptAt?: string; gaveUp: boolean };
+}
+
+// Connection churn since the bridge started. Steadily climbing reconnects point at a flapping backend.
+export interface ConnectionStats {
+  connectsTotal: number;
+  connectFailuresTotal: number;
+  disconnectsTotal: number;
+  reconnectsTotal: number;
+  connectedServers: number;
+}
+
+// A tool call that has to succeed before a server's tools are exposed, for backends that
+// answer initialize before they can do useful work (e.g. while indexing)
+export interface ReadyProbe {
+  tool: string;
+  arguments?: Record<string, unknown>;
+  // Fail the connect if the probe still fails after this long (default 30)
+  timeoutSecs?: number;
+  // Wait between attempts (default 500)
+  intervalMs?: number;
+}
+
+export interface GroupSettings {
+  loadBalance?: LoadBalanceStrategy;
 }
 
 export interface McpServerConfig {
@@ -96,7 +354,65 @@ export interface McpServerConfig {
   command: string;
   args: string[];
   env: Record<string, string>;
+  // Set by loadConfig on servers that come from an include file (never saved)
+  includedFrom?: string;
+  // Env keys to mask when the config is displayed, besides ones that look like credentials
+  // (containing TOKEN, KEY, SECRET, PASSWORD or CREDENTIAL)
+  secretEnv?: string[];
+  // KEY=VALUE file merged into the environment (env wins). Relative paths resolve against the config dir.
+  envFile?: string;
+  // Working directory for the process (default: the bridge's). Relative paths resolve against the
+  // config dir. Lets several servers run one command against different directories.
+  cwd?: string;
+  // Start the server with an empty environment instead of inheriting ours
+  envClear?: boolean;
+  // With envClear, parent variables still passed through (e.g. PATH, HOME)
+  envInherit?: string[];
   enabled: boolean;
+  tags?: string[];
+  // Servers are connected one at a time in ascending startOrder (default 0)
+  startOrder?: number;
+  // Wait this long before starting this server, e.g. for a dependency to come up
+  startDelayMs?: number;
+  // Request timeout for this server, taking precedence over settings and --timeout
+  timeoutSecs?: number;
+  // Initialize timeout for this server, taking precedence over settings
+  initTimeoutSecs?: number;
+  // Append the server's stderr to this file as well (relative paths resolve against the config dir)
+  logFile?: string;
+  // Rotate logFile to <logFile>.1 when it would grow past this size (default 10 MiB)
+  logFileMaxBytes?: number;
+  // This server writes its logs to stdout; takes precedence over settings.tolerateStdoutLogs
+  tolerateStdoutLogs?: boolean;
+  // Don't start the server until one of its tools is called. Its tools are listed from the
+  // tool cache, so it is started once up front when nothing is cached yet.
+  lazy?: boolean;
+  // Servers sharing a group expose one merged tool set as {group}__{tool} and fail over to each other
+  group?: string;
+  // Retry tool calls that fail to reach the tool (transport errors, retryErrorCodes) this many
+  // times, waiting callRetryBackoffMs (default 200) before the first retry and doubling after
+  callRetries?: number;
+  callRetryBackoffMs?: number;
+  // Also retry calls that timed out. Off by default since the backend may have run the call anyway.
+  retryTimeouts?: boolean;
+  // JSON-RPC error codes worth retrying (default [-32603], internal error). Invalid params never are.
+  retryErrorCodes?: number[];
+  // MCP revision sent in initialize to this server only, for backends that reject the default
+  protocolVersion?: string;
+  // Vendor-specific fields merged into this server's initialize params. Nested objects are merged
+  // (e.g. capabilities.experimental); standard fields the bridge sets are never overridden.
+  initParamsExtra?: Record<string, unknown>;
+  // Description length limit for this server's tools, taking precedence over settings
+  maxDescriptionLength?: number;
+  // Replacement descriptions by backend tool name, used instead of the backend's own
+  toolDescriptions?: Record<string, string>;
+  // Retry this tool call after connecting until it succeeds, only then exposing the server's tools
+  readyProbe?: ReadyProbe;
+  // Deterministic tools (backend names) whose concurrent calls with identical arguments share one
+  // backend request
+  cacheableTools?: string[];
+  // When maxExposedTools caps the tool list, tools of higher-priority servers are kept first (default 0)
+  priority?: number;
 }
 
 // Namespacing
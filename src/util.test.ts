@@ -0,0 +1,49 @@
+import { describe, it, expect } from "vitest";
+import * as fs from "node:fs";
+import * as os from "node:os";
+import * as path from "node:path";
+import { canonicalJson, readJsonValueSync } from "./util.js";
+
+describe("canonicalJson", () => {
+  it("should ignore key order, including in nested objects", () => {
+    expect(canonicalJson({ a: 1, b: 2 })).toBe(canonicalJson({ b: 2, a: 1 }));
+    expect(canonicalJson({ z: { y: 1, x: [{ b: 1, a: 2 }] }, a: null })).toBe(
+      '{"a":null,"z":{"x":[{"a":2,"b":1}],"y":1}}'
+    );
+  });
+
+  it("should keep array order and serialize scalars like JSON.stringify", () => {
+    expect(canonicalJson([3, 1, 2])).toBe("[3,1,2]");
+    expect(canonicalJson([{ b: 1, a: 1 }, "x"])).toBe('[{"a":1,"b":1},"x"]');
+    expect(canonicalJson("text")).toBe('"text"');
+    expect(canonicalJson({ b: undefined, a: 1 })).toBe('{"a":1}');
+  });
+});
+
+describe("readJsonValueSync", () => {
+  it("should read only the first value, leaving the rest of the input unread", () => {
+    const file = path.join(fs.mkdtempSync(path.join(os.tmpdir(), "mcp-central-util-")), "input");
+    const config = '{\n  "servers": [{ "name": "a}\\"b", "args": ["[", "{"] }]\n}';
+    fs.writeFileSync(file, `${config}\n{"jsonrpc":"2.0","id":1,"method":"tools/list"}\n`);
+
+    const fd = fs.openSync(file, "r");
+    try {
+      expect(JSON.parse(readJsonValueSync(fd))).toEqual(JSON.parse(config));
+      expect(fs.readFileSync(fd, "utf-8")).toBe('\n{"jsonrpc":"2.0","id":1,"method":"tools/list"}\n');
+    } finally {
+      fs.closeSync(fd);
+    }
+  });
+
+  it("should return what it read when the input ends early", () => {
+    const file = path.join(fs.mkdtempSync(path.join(os.tmpdir(), "mcp-central-util-")), "input");
+    fs.writeFileSync(file, '{"servers": [');
+
+    const fd = fs.openSync(file, "r");
+    try {
+      expect(readJsonValueSync(fd)).toBe('{"servers": [');
+    } finally {
+      fs.closeSync(fd);
+    }
+  });
+});
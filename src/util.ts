@@ -0,0 +1,58 @@
+import * as fs from "node:fs";
+
+// JSON with object keys sorted at every level, so values that are equal as JSON serialize the
+// same way regardless of key order (e.g. for keying caches and coalesced calls by arguments).
+// Array order is kept, and like JSON.stringify, undefined object values are left out.
+export function canonicalJson(value: unknown): string {
+  return JSON.stringify(value, (_key, v: unknown) => {
+    if (typeof v !== "object" || v === null || Array.isArray(v)) return v;
+    const object = v as Record<string, unknown>;
+    return Object.fromEntries(
+      Object.keys(object)
+        .sort()
+        .map((key) => [key, object[key]])
+    );
+  });
+}
+
+// Reads one top-level JSON object or array from fd a byte at a time, so nothing after it is
+// consumed and the rest can still be read from the same fd (e.g. serve's requests following a
+// config on stdin). Returns what was read so far at end of input, for JSON.parse to reject.
+export function readJsonValueSync(fd: number): string {
+  const byte = Buffer.alloc(1);
+  const bytes: number[] = [];
+  let depth = 0;
+  let inString = false;
+  let escaped = false;
+
+  for (;;) {
+    let read: number;
+    try {
+      read = fs.readSync(fd, byte, 0, 1, null);
+    } catch (e) {
+      // Non-blocking stdin (e.g. a TTY) with nothing typed yet
+      if ((e as NodeJS.ErrnoException).code !== "EAGAIN") throw e;
+      Atomics.wait(new Int32Array(new SharedArrayBuffer(4)), 0, 0, 10);
+      continue;
+    }
+    if (read === 0) break;
+
+    const ch = String.fromCharCode(byte[0]);
+    bytes.push(byte[0]);
+    if (inString) {
+      if (escaped) escaped = false;
+      else if (ch === "\\") escaped = true;
+      else if (ch === '"') inString = false;
+    } else if (ch === '"') {
+      inString = true;
+    } else if (ch === "{" || ch === "[") {
+      depth++;
+    } else if (ch === "}" || ch === "]") {
+      if (--depth <= 0) break;
+    } else if (depth === 0 && !/\s/.test(ch)) {
+      break;
+    }
+  }
+
+  return Buffer.from(bytes).toString("utf-8");
+}
@@ -0,0 +1,102 @@
+import { describe, it, expect, vi } from "vitest";
+import { EventEmitter } from "node:events";
+import type { Duplex } from "node:stream";
+import type { IncomingMessage } from "node:http";
+import { acceptKey, decodeFrames, encodeFrame, Opcodes, upgradeToWebSocket, WebSocketSession } from "./websocket.js";
+
+const MASK = Buffer.from([1, 2, 3, 4]);
+
+function fakeSocket() {
+  return Object.assign(new EventEmitter(), {
+    writable: true,
+    write: vi.fn(),
+    end: vi.fn(),
+    destroy: vi.fn(),
+  });
+}
+
+describe("websocket", () => {
+  it("should compute the handshake accept key", () => {
+    // Example from RFC 6455 section 1.3
+    expect(acceptKey("dGhlIHNhbXBsZSBub25jZQ==")).toBe("s3pPLMBiTxaQ9kF+G0muJxbK4Wo=");
+  });
+
+  it("should round-trip masked frames of every length encoding", () => {
+    for (const length of [5, 300, 70000]) {
+      const payload = Buffer.alloc(length, "a");
+      const { frames, rest } = decodeFrames(encodeFrame(Opcodes.TEXT, payload, MASK));
+
+      expect(frames).toEqual([{ fin: true, opcode: Opcodes.TEXT, masked: true, payload }]);
+      expect(rest).toHaveLength(0);
+    }
+  });
+
+  it("should leave partial frames for the next read", () => {
+    const frame = encodeFrame(Opcodes.TEXT, Buffer.from("hello"), MASK);
+
+    const { frames, rest } = decodeFrames(frame.subarray(0, 4));
+
+    expect(frames).toEqual([]);
+    expect(rest).toEqual(frame.subarray(0, 4));
+  });
+
+  it("should reassemble fragmented messages split across reads", () => {
+    const onMessage = vi.fn();
+    const session = new WebSocketSession(fakeSocket() as unknown as Duplex, 1024, onMessage);
+    const first = encodeFrame(Opcodes.TEXT, Buffer.from('{"id":'), MASK);
+    first[0] &= 0x7f; // not final
+    const last = encodeFrame(Opcodes.CONTINUATION, Buffer.from("1}"), MASK);
+    const bytes = Buffer.concat([first, last]);
+
+    session.receive(bytes.subarray(0, 7));
+    session.receive(bytes.subarray(7));
+
+    expect(onMessage).toHaveBeenCalledWith('{"id":1}');
+  });
+
+  it("should answer pings and close oversized messages", () => {
+    const socket = fakeSocket();
+    const session = new WebSocketSession(socket as unknown as Duplex, 4, vi.fn());
+
+    session.receive(encodeFrame(Opcodes.PING, Buffer.from("hi"), MASK));
+    expect(socket.write).toHaveBeenCalledWith(encodeFrame(Opcodes.PONG, Buffer.from("hi")));
+
+    session.receive(encodeFrame(Opcodes.TEXT, Buffer.from("too long"), MASK));
+    expect(socket.end).toHaveBeenCalledWith(encodeFrame(Opcodes.CLOSE, Buffer.from([0x03, 0xf1])));
+  });
+
+  it("should close the connection on an unmasked client frame", () => {
+    const socket = fakeSocket();
+    const onMessage = vi.fn();
+    const session = new WebSocketSession(socket as unknown as Duplex, 1024, onMessage);
+
+    session.receive(encodeFrame(Opcodes.TEXT, Buffer.from('{"id":1}')));
+
+    expect(onMessage).not.toHaveBeenCalled();
+    expect(socket.end).toHaveBeenCalledWith(encodeFrame(Opcodes.CLOSE, Buffer.from([0x03, 0xea])));
+  });
+
+  it("should close on a huge declared length without waiting for the payload", () => {
+    const socket = fakeSocket();
+    const session = new WebSocketSession(socket as unknown as Duplex, 1024, vi.fn());
+    const header = Buffer.from([0x81, 0x80 | 127, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+
+    session.receive(header);
+
+    expect(socket.end).toHaveBeenCalledWith(encodeFrame(Opcodes.CLOSE, Buffer.from([0x03, 0xf1])));
+  });
+
+  it("should answer 426 to a handshake for another WebSocket version", () => {
+    const socket = fakeSocket();
+    const request = {
+      headers: { upgrade: "websocket", "sec-websocket-key": "dGhlIHNhbXBsZSBub25jZQ==", "sec-websocket-version": "8" },
+    } as unknown as IncomingMessage;
+
+    const session = upgradeToWebSocket(request, socket as unknown as Duplex, Buffer.alloc(0), 1024, vi.fn());
+
+    expect(session).toBeNull();
+    expect(socket.write).not.toHaveBeenCalled();
+    expect(socket.end).toHaveBeenCalledWith(expect.stringMatching(/^HTTP\/1.1 426 Upgrade Required\r\n/));
+    expect(socket.end).toHaveBeenCalledWith(expect.stringContaining("Sec-WebSocket-Version: 13\r\n"));
+  });
+});
@@ -0,0 +1,259 @@
+import * as crypto from "node:crypto";
+import type { IncomingMessage } from "node:http";
+import type { Duplex } from "node:stream";
+
+// Just enough of RFC 6455 to carry JSON-RPC text messages: no extensions or subprotocols.
+
+const HANDSHAKE_GUID = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+const WEBSOCKET_VERSION = "13";
+
+export const Opcodes = {
+  CONTINUATION: 0x0,
+  TEXT: 0x1,
+  BINARY: 0x2,
+  CLOSE: 0x8,
+  PING: 0x9,
+  PONG: 0xa,
+} as const;
+
+const CloseCodes = {
+  NORMAL: 1000,
+  PROTOCOL_ERROR: 1002,
+  UNSUPPORTED_DATA: 1003,
+  MESSAGE_TOO_BIG: 1009,
+} as const;
+
+export interface Frame {
+  fin: boolean;
+  opcode: number;
+  // Whether the sender masked it, as clients must. The payload is already unmasked.
+  masked: boolean;
+  payload: Buffer;
+}
+
+export function acceptKey(key: string): string {
+  return crypto.createHash("sha1").update(key + HANDSHAKE_GUID).digest("base64");
+}
+
+function applyMask(payload: Buffer, mask: Buffer): Buffer {
+  const out = Buffer.alloc(payload.length);
+  for (let i = 0; i < payload.length; i++) {
+    out[i] = payload[i] ^ mask[i % 4];
+  }
+  return out;
+}
+
+// Servers send unmasked frames; clients must pass a mask
+export function encodeFrame(opcode: number, payload: Buffer, mask?: Buffer): Buffer {
+  const length = payload.length;
+  const lengthBytes = length < 126 ? 0 : length < 65536 ? 2 : 8;
+  const header = Buffer.alloc(2 + lengthBytes + (mask ? 4 : 0));
+
+  header[0] = 0x80 | opcode;
+  const maskBit = mask ? 0x80 : 0;
+  if (lengthBytes === 0) {
+    header[1] = maskBit | length;
+  } else if (lengthBytes === 2) {
+    header[1] = maskBit | 126;
+    header.writeUInt16BE(length, 2);
+  } else {
+    header[1] = maskBit | 127;
+    header.writeBigUInt64BE(BigInt(length), 2);
+  }
+
+  if (!mask) return Buffer.concat([header, payload]);
+  mask.copy(header, 2 + lengthBytes);
+  return Buffer.concat([header, applyMask(payload, mask)]);
+}
+
+// Decodes the complete frames at the start of buffer, returning the bytes of any partial one.
+// Stops with oversized set at a frame whose header announces more than maxPayloadBytes.
+export function decodeFrames(
+  buffer: Buffer,
+  maxPayloadBytes = Infinity
+): { frames: Frame[]; rest: Buffer; oversized: boolean } {
+  const frames: Frame[] = [];
+  let offset = 0;
+
+  while (buffer.length - offset >= 2) {
+    const fin = (buffer[offset] & 0x80) !== 0;
+    const opcode = buffer[offset] & 0x0f;
+    const masked = (buffer[offset + 1] & 0x80) !== 0;
+    let length = buffer[offset + 1] & 0x7f;
+    let headerLength = 2;
+
+    if (length === 126) {
+      if (buffer.length - offset < 4) break;
+      length = buffer.readUInt16BE(offset + 2);
+      headerLength = 4;
+    } else if (length === 127) {
+      if (buffer.length - offset < 10) break;
+      // Past 2^53 the length can't be a Number, and is far too big to accept anyway
+      const declared = buffer.readBigUInt64BE(offset + 2);
+      length = declared > BigInt(Number.MAX_SAFE_INTEGER) ? Infinity : Number(declared);
+      headerLength = 10;
+    }
+    if (length > maxPayloadBytes) {
+      return { frames, rest: buffer.subarray(offset), oversized: true };
+    }
+
+    const maskLength = masked ? 4 : 0;
+    const start = offset + headerLength + maskLength;
+    if (buffer.length < start + length) break;
+
+    const payload = buffer.subarray(start, start + length);
+    frames.push({
+      fin,
+      opcode,
+      masked,
+      payload: masked ? applyMask(payload, buffer.subarray(start - 4, start)) : Buffer.from(payload),
+    });
+    offset = start + length;
+  }
+
+  return { frames, rest: buffer.subarray(offset), oversized: false };
+}
+
+// One accepted WebSocket connection. Text messages are reassembled from fragments and
+// handed to onMessage; pings are answered and close frames echoed.
+export class WebSocketSession {
+  private buffer = Buffer.alloc(0);
+  private fragments: Buffer[] = [];
+  private fragmentBytes = 0;
+  private messageOpcode: number | null = null;
+  private closed = false;
+
+  constructor(
+    private socket: Duplex,
+    private maxMessageBytes: number,
+    private onMessage: (text: string) => void
+  ) {
+    socket.on("data", (chunk: Buffer) => this.receive(chunk));
+    socket.on("error", (err) => {
+      console.error("WebSocket connection error:", err.message);
+      socket.destroy();
+    });
+  }
+
+  send(text: string): void {
+    if (this.closed || !this.socket.writable) return;
+    this.socket.write(encodeFrame(Opcodes.TEXT, Buffer.from(text, "utf-8")));
+  }
+
+  close(code: number = CloseCodes.NORMAL): void {
+    if (this.closed) return;
+    this.closed = true;
+
+    const payload = Buffer.alloc(2);
+    payload.writeUInt16BE(code);
+    this.socket.end(encodeFrame(Opcodes.CLOSE, payload));
+  }
+
+  receive(chunk: Buffer): void {
+    this.buffer = Buffer.concat([this.buffer, chunk]);
+    const { frames, rest, oversized } = decodeFrames(this.buffer, this.maxMessageBytes);
+    this.buffer = Buffer.from(rest);
+
+    for (const frame of frames) {
+      if (this.closed) return;
+      this.handleFrame(frame);
+    }
+    // A frame header announcing more than we'd accept; don't buffer it first
+    if (oversized) this.close(CloseCodes.MESSAGE_TOO_BIG);
+  }
+
+  private handleFrame(frame: Frame): void {
+    // RFC 6455 section 5.1: a server must close the connection on an unmasked client frame
+    if (!frame.masked) {
+      this.close(CloseCodes.PROTOCOL_ERROR);
+      return;
+    }
+
+    switch (frame.opcode) {
+      case Opcodes.CLOSE:
+        this.close();
+        return;
+      case Opcodes.PING:
+        this.socket.write(encodeFrame(Opcodes.PONG, frame.payload));
+        return;
+      case Opcodes.PONG:
+        return;
+      case Opcodes.TEXT:
+      case Opcodes.BINARY:
+        if (this.messageOpcode !== null) {
+          this.close(CloseCodes.PROTOCOL_ERROR);
+          return;
+        }
+        this.messageOpcode = frame.opcode;
+        break;
+      case Opcodes.CONTINUATION:
+        if (this.messageOpcode === null) {
+          this.close(CloseCodes.PROTOCOL_ERROR);
+          return;
+        }
+        break;
+      default:
+        this.close(CloseCodes.PROTOCOL_ERROR);
+        return;
+    }
+
+    this.fragments.push(frame.payload);
+    this.fragmentBytes += frame.payload.length;
+    if (this.fragmentBytes > this.maxMessageBytes) {
+      this.close(CloseCodes.MESSAGE_TOO_BIG);
+      return;
+    }
+    if (!frame.fin) return;
+
+    const opcode = this.messageOpcode;
+    const message = Buffer.concat(this.fragments);
+    this.fragments = [];
+    this.fragmentBytes = 0;
+    this.messageOpcode = null;
+
+    // JSON-RPC messages are text
+    if (opcode !== Opcodes.TEXT) {
+      this.close(CloseCodes.UNSUPPORTED_DATA);
+      return;
+    }
+    this.onMessage(message.toString("utf-8"));
+  }
+}
+
+// Completes the opening handshake for an HTTP upgrade request. Answers 400 and returns null
+// when the request isn't a WebSocket handshake, or 426 when it asks for a version other than
+// 13. head holds any bytes read past the handshake.
+export function upgradeToWebSocket(
+  request: IncomingMessage,
+  socket: Duplex,
+  head: Buffer,
+  maxMessageBytes: number,
+  onMessage: (text: string) => void
+): WebSocketSession | null {
+  const key = request.headers["sec-websocket-key"];
+  if (request.headers.upgrade?.toLowerCase() !== "websocket" || typeof key !== "string") {
+    socket.end("HTTP/1.1 400 Bad Request\r\nConnection: close\r\n\r\n");
+    return null;
+  }
+  if (request.headers["sec-websocket-version"] !== WEBSOCKET_VERSION) {
+    socket.end(
+      `HTTP/1.1 426 Upgrade Required\r\nSec-WebSocket-Version: ${WEBSOCKET_VERSION}\r\nConnection: close\r\n\r\n`
+    );
+    return null;
+  }
+
+  socket.write(
+    [
+      "HTTP/1.1 101 Switching Protocols",
+      "Upgrade: websocket",
+      "Connection: Upgrade",
+      `Sec-WebSocket-Accept: ${acceptKey(key)}`,
+      "",
+      "",
+    ].join("\r\n")
+  );
+
+  const session = new WebSocketSession(socket, maxMessageBytes, onMessage);
+  if (head.length > 0) session.receive(head);
+  return session;
+}
@@ -0,0 +1,200 @@
+// Minimal MCP server used by the test suite. Behaviour is tweaked through env vars:
+//   MOCK_NAME         serverInfo.name (default "mock")
+//   MOCK_TOOLS        comma-separated tool names (default "echo")
+//   MOCK_EMPTY_LISTS  number of initial tools/list calls that return no tools
+//   MOCK_RESOURCES    comma-separated resource URIs; enables resources with subscribe
+//   MOCK_TEMPLATES    comma-separated resource URI templates; enables resources
+//   MOCK_FAIL_CALLS   number of initial tools/call requests answered with a JSON-RPC error
+//   MOCK_SILENT_INIT  "1" never answers initialize
+//   MOCK_IGNORE_TERM  "1" ignores SIGTERM, so only SIGKILL stops it
+//   MOCK_STDOUT_LOGS  "1" writes a plain-text log line to stdout before handling each request,
+//                     "bracketed" writes "[INFO] ..." and an unbalanced "[WARN ..." line instead
+//   MOCK_MIRROR_IDS   "1" sends roots/list under the id of the tools/call that asked for it,
+//                     instead of "mock-N", so both sides have a request out with the same id
+//   MOCK_FRAMING      "concat" writes messages back to back with no newlines, "split" pretty-prints
+//                     each message over several lines and writes it in two chunks
+//
+// tools/call replies with `arguments.label` as text (carrying `arguments.annotations`, if any)
+// after `arguments.delay` ms. A few tool names behave differently instead:
+//   roots          asks the client for roots/list and replies with the result as JSON
+//   env            replies with the values of the env vars listed in `arguments.keys` as JSON
+//   cwd            replies with its working directory
+//   client_info    replies with the clientInfo sent in initialize as JSON
+//   init_params    replies with all the params sent in initialize as JSON
+//   protocol       replies with the protocolVersion sent in initialize
+//   fail           replies with `arguments.label` as an isError result
+//   stderr         writes `arguments.text` to stderr before replying
+//   tools_changed  sends notifications/tools/list_changed after replying
+//   resources_changed  adds `arguments.uri` to its resources, then sends notifications/resources/list_changed
+//   hang           replies, then ignores every later request while staying alive
+//   count          replies with how many tools/call requests it has received, after `arguments.delay` ms
+//   exit           exits with `arguments.code` without replying, or kills itself with `arguments.signal`
+//
+// mock/echo replies with the params it was sent, standing in for a vendor extension method.
+// resources/list reports progress twice before replying when the request carries a progressToken.
+import * as readline from "node:readline";
+
+const name = process.env.MOCK_NAME ?? "mock";
+const tools = (process.env.MOCK_TOOLS ?? "echo").split(",").filter(Boolean);
+let emptyLists = Number(process.env.MOCK_EMPTY_LISTS ?? 0);
+const resources = (process.env.MOCK_RESOURCES ?? "").split(",").filter(Boolean);
+const templates = (process.env.MOCK_TEMPLATES ?? "").split(",").filter(Boolean);
+let failCalls = Number(process.env.MOCK_FAIL_CALLS ?? 0);
+
+const framing = process.env.MOCK_FRAMING ?? "";
+
+if (process.env.MOCK_IGNORE_TERM === "1") process.on("SIGTERM", () => {});
+
+const send = (msg) => {
+  if (framing === "concat") return process.stdout.write(JSON.stringify(msg));
+  if (framing === "split") {
+    const text = JSON.stringify(msg, null, 2) + "\n";
+    const half = Math.floor(text.length / 2);
+    process.stdout.write(text.slice(0, half));
+    return setTimeout(() => process.stdout.write(text.slice(half)), 5);
+  }
+  process.stdout.write(JSON.stringify(msg) + "\n");
+};
+const reply = (id, result) => send({ jsonrpc: "2.0", id, result });
+const notify = (method, params) => send({ jsonrpc: "2.0", method, params });
+
+// Requests we send to the client, keyed by id
+const pending = new Map();
+let nextId = 1;
+const request = (method, params, id = `mock-${nextId++}`) =>
+  new Promise((resolve) => {
+    pending.set(id, resolve);
+    send({ jsonrpc: "2.0", id, method, params });
+  });
+
+let clientInfo = null;
+let initParams = null;
+let protocolVersion = null;
+let hung = false;
+let callsReceived = 0;
+
+const capabilities = { tools: {} };
+if (resources.length > 0 || templates.length > 0) capabilities.resources = { subscribe: true, listChanged: true };
+
+const rl = readline.createInterface({ input: process.stdin });
+
+rl.on("line", (line) => {
+  const req = JSON.parse(line);
+  if (process.env.MOCK_STDOUT_LOGS === "1") process.stdout.write(`INFO handling ${req.method ?? "response"}\n`);
+  if (process.env.MOCK_STDOUT_LOGS === "bracketed") {
+    process.stdout.write(`[INFO] handling ${req.method ?? "response"}\n[WARN still handling ${req.method}\n`);
+  }
+  if (req.method === undefined) {
+    pending.get(req.id)?.(req);
+    pending.delete(req.id);
+    return;
+  }
+  if (req.id === undefined || hung) return;
+
+  switch (req.method) {
+    case "initialize":
+      if (process.env.MOCK_SILENT_INIT === "1") return;
+      clientInfo = req.params?.clientInfo ?? null;
+      initParams = req.params ?? null;
+      protocolVersion = req.params?.protocolVersion ?? null;
+      return reply(req.id, {
+        protocolVersion: "2024-11-05",
+        capabilities,
+        serverInfo: { name, version: "1.0.0" },
+      });
+    case "ping":
+      return reply(req.id, {});
+    case "mock/echo":
+      return reply(req.id, { params: req.params ?? null });
+    case "tools/list": {
+      if (emptyLists > 0) {
+        emptyLists--;
+        return reply(req.id, { tools: [] });
+      }
+      return reply(req.id, { tools: tools.map((t) => ({ name: t, description: `${name} ${t}`, inputSchema: {} })) });
+    }
+    case "tools/call": {
+      callsReceived++;
+      if (req.params.name === "count") {
+        const text = String(callsReceived);
+        return setTimeout(() => reply(req.id, { content: [{ type: "text", text }] }), req.params.arguments?.delay ?? 0);
+      }
+      if (req.params.name === "roots") {
+        const id = process.env.MOCK_MIRROR_IDS === "1" ? req.id : undefined;
+        return request("roots/list", undefined, id).then((res) =>
+          reply(req.id, { content: [{ type: "text", text: JSON.stringify(res.result ?? res.error) }] })
+        );
+      }
+      if (req.params.name === "cwd") {
+        return reply(req.id, { content: [{ type: "text", text: process.cwd() }] });
+      }
+      if (req.params.name === "env") {
+        const keys = req.params.arguments?.keys ?? [];
+        const values = Object.fromEntries(keys.map((key) => [key, process.env[key] ?? null]));
+        return reply(req.id, { content: [{ type: "text", text: JSON.stringify(values) }] });
+      }
+      if (req.params.name === "stderr") {
+        process.stderr.write(`${req.params.arguments?.text ?? ""}\n`);
+        return reply(req.id, { content: [] });
+      }
+      if (req.params.name === "tools_changed") {
+        reply(req.id, { content: [] });
+        return notify("notifications/tools/list_changed");
+      }
+      if (req.params.name === "resources_changed") {
+        resources.push(req.params.arguments.uri);
+        reply(req.id, { content: [] });
+        return notify("notifications/resources/list_changed");
+      }
+      if (req.params.name === "exit") {
+        const { code, signal } = req.params.arguments ?? {};
+        if (signal) process.kill(process.pid, signal);
+        else process.exit(code ?? 1);
+        return;
+      }
+      if (req.params.name === "hang") {
+        hung = true;
+        return reply(req.id, { content: [] });
+      }
+      if (req.params.name === "fail") {
+        return reply(req.id, { content: [{ type: "text", text: String(req.params.arguments?.label ?? "") }], isError: true });
+      }
+      if (req.params.name === "protocol") {
+        return reply(req.id, { content: [{ type: "text", text: String(protocolVersion) }] });
+      }
+      if (req.params.name === "client_info") {
+        return reply(req.id, { content: [{ type: "text", text: JSON.stringify(clientInfo) }] });
+      }
+      if (req.params.name === "init_params") {
+        return reply(req.id, { content: [{ type: "text", text: JSON.stringify(initParams) }] });
+      }
+      if (failCalls > 0) {
+        failCalls--;
+        return send({ jsonrpc: "2.0", id: req.id, error: { code: -32603, message: `${name} is failing` } });
+      }
+      const args = req.params.arguments ?? {};
+      const content = { type: "text", text: String(args.label ?? "") };
+      if (args.annotations) content.annotations = args.annotations;
+      return setTimeout(() => reply(req.id, { content: [content] }), args.delay ?? 0);
+    }
+    case "resources/list": {
+      const progressToken = req.params?._meta?.progressToken;
+      if (progressToken !== undefined) {
+        notify("notifications/progress", { progressToken, progress: 1, total: 2 });
+        notify("notifications/progress", { progressToken, progress: 2, total: 2 });
+      }
+      return reply(req.id, { resources: resources.map((uri) => ({ uri, name: uri })) });
+    }
+    case "resources/templates/list":
+      return reply(req.id, { resourceTemplates: templates.map((uriTemplate) => ({ uriTemplate, name: uriTemplate })) });
+    case "resources/read":
+      return reply(req.id, { contents: [{ uri: req.params.uri, text: `contents of ${req.params.uri}` }] });
+    case "resources/subscribe":
+      reply(req.id, {});
+      return setTimeout(() => notify("notifications/resources/updated", { uri: req.params.uri }), 10);
+    case "resources/unsubscribe":
+      return reply(req.id, {});
+    default:
+      return send({ jsonrpc: "2.0", id: req.id, error: { code: -32601, message: `Method not found: ${req.method}` } });
+  }
+});
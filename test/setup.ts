@@ -0,0 +1,13 @@
+import * as fs from "node:fs";
+import * as os from "node:os";
+import * as path from "node:path";
+import { afterAll } from "vitest";
+
+// Each test file gets an empty config dir of its own, so usage logs, stats and caches written
+// by the code under test never land in the developer's real one
+const dir = fs.mkdtempSync(path.join(os.tmpdir(), "mcp-central-test-"));
+process.env.MCP_CENTRAL_CONFIG_DIR = dir;
+
+afterAll(() => {
+  fs.rmSync(dir, { recursive: true, force: true });
+});
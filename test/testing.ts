@@ -0,0 +1,21 @@
+import * as fs from "node:fs";
+import * as os from "node:os";
+import * as path from "node:path";
+import { afterEach, beforeEach } from "vitest";
+
+// Test helper: gives each test in the calling describe block an empty config dir of its own,
+// removed afterwards, for tests that look at what ends up in it
+export function useTempConfigDir(prefix: string): void {
+  const originalDir = process.env.MCP_CENTRAL_CONFIG_DIR;
+  let dir: string;
+
+  beforeEach(() => {
+    dir = fs.mkdtempSync(path.join(os.tmpdir(), prefix));
+    process.env.MCP_CENTRAL_CONFIG_DIR = dir;
+  });
+
+  afterEach(() => {
+    process.env.MCP_CENTRAL_CONFIG_DIR = originalDir;
+    fs.rmSync(dir, { recursive: true, force: true });
+  });
+}